@@ -0,0 +1,236 @@
+// Converts the monochrome images in assets/ into lmc_ui::bitmap::Bitmap
+// consts in $OUT_DIR/assets.rs, named after the file in upper case
+// (logo.pbm becomes LOGO). Takes plain and raw PBM (P1, P4) and uncompressed
+// 1 bit BMP, dark pixels are lit.
+//
+// Also exports LMC_BUILD, the short git hash of the build or "unknown".
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const ASSETS_DIR: &str = "assets";
+
+struct Bitmap {
+    width: usize,
+    height: usize,
+    // Rows padded to a whole byte, MSB first, set bits lit
+    data: Vec<u8>,
+}
+
+impl Bitmap {
+    fn new(width: usize, height: usize) -> Self {
+        Bitmap {
+            width,
+            height,
+            data: vec![0; width.div_ceil(8) * height],
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize) {
+        self.data[y * self.width.div_ceil(8) + x / 8] |= 0x80 >> (x % 8);
+    }
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", ASSETS_DIR);
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(ASSETS_DIR)
+        .map(|dir| dir.map(|entry| entry.unwrap().path()).collect())
+        .unwrap_or_default();
+    paths.sort();
+
+    let mut out = String::new();
+    for path in paths {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let bytes = fs::read(&path).unwrap();
+        let bitmap = match ext.to_ascii_lowercase().as_str() {
+            "pbm" => parse_pbm(&bytes),
+            "bmp" => parse_bmp(&bytes),
+            _ => continue,
+        }
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+
+        println!("cargo:rerun-if-changed={}", path.display());
+        write_const(&mut out, &path, &bitmap);
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("assets.rs"), out).unwrap();
+
+    println!("cargo:rustc-env=LMC_BUILD={}", git_hash());
+}
+
+fn write_const(out: &mut String, path: &Path, bitmap: &Bitmap) {
+    let name: String = path
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+
+    writeln!(out, "// From {}", path.display()).unwrap();
+    writeln!(out, "pub const {}: Bitmap = Bitmap {{", name).unwrap();
+    writeln!(out, "    width: {},", bitmap.width).unwrap();
+    writeln!(out, "    height: {},", bitmap.height).unwrap();
+    writeln!(out, "    data: &[").unwrap();
+    for row in bitmap.data.chunks(12) {
+        let bytes: Vec<String> = row.iter().map(|b| format!("0x{:02x},", b)).collect();
+        writeln!(out, "        {}", bytes.join(" ")).unwrap();
+    }
+    writeln!(out, "    ],").unwrap();
+    writeln!(out, "}};").unwrap();
+}
+
+fn parse_pbm(bytes: &[u8]) -> Result<Bitmap, String> {
+    let mut pos = 0;
+    let magic = pbm_token(bytes, &mut pos)?;
+    let width = pbm_number(bytes, &mut pos)?;
+    let height = pbm_number(bytes, &mut pos)?;
+    let mut bitmap = Bitmap::new(width, height);
+
+    match magic.as_str() {
+        "P1" => {
+            let mut i = 0;
+            while i < width * height {
+                match bytes.get(pos) {
+                    Some(b'1') => bitmap.set(i % width, i / width),
+                    Some(b'0') => (),
+                    Some(b'#') => {
+                        skip_comment(bytes, &mut pos);
+                        continue;
+                    }
+                    Some(c) if c.is_ascii_whitespace() => {
+                        pos += 1;
+                        continue;
+                    }
+                    Some(c) => return Err(format!("unexpected {:?} in the pixels", *c as char)),
+                    None => return Err("truncated".into()),
+                }
+                pos += 1;
+                i += 1;
+            }
+        }
+        // Already in the Bitmap layout after a single whitespace
+        "P4" => {
+            let data = bytes
+                .get(pos + 1..pos + 1 + bitmap.data.len())
+                .ok_or("truncated")?;
+            bitmap.data.copy_from_slice(data);
+        }
+        _ => return Err(format!("unsupported PBM format {}", magic)),
+    }
+
+    Ok(bitmap)
+}
+
+fn pbm_token(bytes: &[u8], pos: &mut usize) -> Result<String, String> {
+    loop {
+        match bytes.get(*pos) {
+            Some(b'#') => skip_comment(bytes, pos),
+            Some(c) if c.is_ascii_whitespace() => *pos += 1,
+            Some(_) => break,
+            None => return Err("truncated header".into()),
+        }
+    }
+
+    let start = *pos;
+    while bytes.get(*pos).is_some_and(|c| !c.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    Ok(String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
+}
+
+fn pbm_number(bytes: &[u8], pos: &mut usize) -> Result<usize, String> {
+    let token = pbm_token(bytes, pos)?;
+    token
+        .parse()
+        .map_err(|_| format!("bad size {:?} in the header", token))
+}
+
+fn skip_comment(bytes: &[u8], pos: &mut usize) {
+    while bytes.get(*pos).is_some_and(|&c| c != b'\n') {
+        *pos += 1;
+    }
+}
+
+fn parse_bmp(bytes: &[u8]) -> Result<Bitmap, String> {
+    let u16_at = |i: usize| {
+        bytes
+            .get(i..i + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let u32_at = |i: usize| {
+        bytes
+            .get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    if bytes.get(0..2) != Some(b"BM") {
+        return Err("not a BMP".into());
+    }
+    let truncated = || "truncated header".to_string();
+    let data_offset = u32_at(10).ok_or_else(truncated)? as usize;
+    let header_size = u32_at(14).ok_or_else(truncated)? as usize;
+    let width = u32_at(18).ok_or_else(truncated)? as i32;
+    let height = u32_at(22).ok_or_else(truncated)? as i32;
+    let bpp = u16_at(28).ok_or_else(truncated)?;
+    let compression = u32_at(30).ok_or_else(truncated)?;
+
+    if bpp != 1 || compression != 0 {
+        return Err(format!(
+            "{} bpp with compression {}, only uncompressed 1 bpp is supported",
+            bpp, compression
+        ));
+    }
+
+    // Which of the two palette entries are dark, BGRA
+    let mut dark = [false; 2];
+    for (i, d) in dark.iter_mut().enumerate() {
+        let entry = bytes
+            .get(14 + header_size + 4 * i..14 + header_size + 4 * i + 3)
+            .ok_or("truncated palette")?;
+        let luma: u32 = entry.iter().map(|&c| c as u32).sum::<u32>() / 3;
+        *d = luma < 128;
+    }
+
+    // Rows are bottom up unless the height is negative, each padded to 4
+    // bytes
+    let (width, bottom_up) = (width as usize, height > 0);
+    let height = height.unsigned_abs() as usize;
+    let stride = width.div_ceil(32) * 4;
+    let mut bitmap = Bitmap::new(width, height);
+
+    for y in 0..height {
+        let row = if bottom_up { height - 1 - y } else { y };
+        let start = data_offset + row * stride;
+        let data = bytes.get(start..start + stride).ok_or("truncated pixels")?;
+        for x in 0..width {
+            let index = (data[x / 8] >> (7 - x % 8)) & 1;
+            if dark[index as usize] {
+                bitmap.set(x, y);
+            }
+        }
+    }
+
+    Ok(bitmap)
+}
+
+fn git_hash() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".into())
+}
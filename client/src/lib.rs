@@ -0,0 +1,206 @@
+// Host end of the lmc binary protocol over any Read + Write, usually the
+// USART2 tty. Responses are matched to requests by sequence number, events
+// that arrive meanwhile are queued and console text is skipped.
+
+use lmc_proto::decoder::{Decoder, Input};
+use lmc_proto::packet::{Body, ErrorCode, Event, Packet, Request, Response};
+use lmc_types::status::Status;
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+// Sends of a request, each with a new sequence number, before giving up
+const ATTEMPTS: usize = 3;
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    // No response to any attempt
+    Timeout,
+    // The device refused the request
+    Device(ErrorCode),
+    // A response that doesn't fit the request
+    Unexpected(Response),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Timeout => write!(f, "no response"),
+            Error::Device(ErrorCode::Version) => write!(f, "protocol version mismatch"),
+            Error::Device(ErrorCode::Malformed) => write!(f, "request not understood"),
+            Error::Device(ErrorCode::Busy) => write!(f, "busy, close the menu first"),
+            Error::Device(ErrorCode::Refused) => write!(f, "refused, clear the fault first"),
+            Error::Device(ErrorCode::OutOfRange) => write!(f, "out of range"),
+            Error::Unexpected(r) => write!(f, "unexpected response {:?}", r),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+pub struct Client<T> {
+    port: T,
+    decoder: Decoder,
+    // Read but not decoded yet
+    rx: VecDeque<u8>,
+    seq: u8,
+    events: VecDeque<Event>,
+    timeout: Duration,
+}
+
+impl<T> Client<T>
+where
+    T: Read + Write,
+{
+    // Reads from the port should time out with TimedOut or WouldBlock
+    // rather than block
+    pub fn new(port: T) -> Self {
+        Client {
+            port,
+            decoder: Decoder::new(),
+            rx: VecDeque::new(),
+            seq: 0,
+            events: VecDeque::new(),
+            timeout: TIMEOUT,
+        }
+    }
+
+    // How long to wait for each response
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    pub fn ping(&mut self) -> Result<(), Error> {
+        match self.request(Request::Ping)? {
+            Response::Pong => Ok(()),
+            r => Err(Error::Unexpected(r)),
+        }
+    }
+
+    pub fn status(&mut self) -> Result<Status, Error> {
+        match self.request(Request::GetStatus)? {
+            Response::Status(status) => Ok(status),
+            r => Err(Error::Unexpected(r)),
+        }
+    }
+
+    // Held until the pot moves, like the shell's pwm
+    pub fn set_pwm(&mut self, pwm: u16) -> Result<(), Error> {
+        self.command(Request::SetPwm(pwm))
+    }
+
+    // Hz, None for continuous
+    pub fn set_freq(&mut self, hz: Option<u32>) -> Result<(), Error> {
+        self.command(Request::SetFreq(hz.unwrap_or(0)))
+    }
+
+    pub fn set_relay(&mut self, on: bool) -> Result<(), Error> {
+        self.command(Request::SetRelay(on))
+    }
+
+    // On lapses after 500 ms on the device unless it's sent again
+    pub fn set_oe(&mut self, on: bool) -> Result<(), Error> {
+        self.command(Request::SetOe(on))
+    }
+
+    pub fn apply_preset(&mut self, preset: u8) -> Result<(), Error> {
+        self.command(Request::ApplyPreset(preset))
+    }
+
+    // Takes the oldest event, waiting up to the timeout for one
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        if self.events.is_empty() {
+            self.receive(None)?;
+        }
+        Ok(self.events.pop_front())
+    }
+
+    // Every request is idempotent, so a lost request or response is simply
+    // sent again
+    pub fn request(&mut self, request: Request) -> Result<Response, Error> {
+        for _ in 0..ATTEMPTS {
+            self.seq = self.seq.wrapping_add(1);
+            let frame = Packet::new(self.seq, Body::Request(request)).encode();
+            self.port.write_all(frame.as_bytes())?;
+            self.port.flush()?;
+
+            match self.receive(Some(self.seq))? {
+                Some(Response::Error(code)) => return Err(Error::Device(code)),
+                Some(response) => return Ok(response),
+                None => (),
+            }
+        }
+
+        Err(Error::Timeout)
+    }
+
+    fn command(&mut self, request: Request) -> Result<(), Error> {
+        match self.request(request)? {
+            Response::Ok => Ok(()),
+            r => Err(Error::Unexpected(r)),
+        }
+    }
+
+    // Waits for the response with the sequence number, or with None for an
+    // event, queueing events and dropping anything else
+    fn receive(&mut self, seq: Option<u8>) -> Result<Option<Response>, Error> {
+        let deadline = Instant::now() + self.timeout;
+
+        while Instant::now() < deadline {
+            let packet = match self.next_packet()? {
+                Some(packet) => packet,
+                None => continue,
+            };
+
+            match packet.body {
+                Body::Event(event) => {
+                    self.events.push_back(event);
+                    if seq.is_none() {
+                        return Ok(None);
+                    }
+                }
+                Body::Response(response) if Some(packet.seq) == seq => return Ok(Some(response)),
+                _ => (),
+            }
+        }
+
+        Ok(None)
+    }
+
+    // None when the port timed out
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        loop {
+            while let Some(byte) = self.rx.pop_front() {
+                if let Input::Packet(Ok(packet)) = self.decoder.push(byte) {
+                    return Ok(Some(packet));
+                }
+            }
+
+            let mut buf = [0; 64];
+            match self.port.read(&mut buf) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => self.rx.extend(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(None),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
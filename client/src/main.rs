@@ -0,0 +1,136 @@
+// lmc-client <tty> <command>, talks to the firmware over the USART2 console
+// at 115200 baud
+
+use lmc_client::{Client, Error};
+use lmc_proto::packet::Event;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::process::{self, Command};
+use std::thread;
+use std::time::Duration;
+
+// Remote OE lapses after 500 ms unless it's sent again
+const OE_REFRESH: Duration = Duration::from_millis(200);
+
+const USAGE: &str = "\
+usage: lmc-client <tty> <command>
+
+ping              check the link
+status            status
+pwm <0-4095>      hold the PWM until the pot moves
+freq <hz|cont>    hold the strobe rate until the pot moves
+relay on|off      like B1 and B0, off also clears a fault
+oe on|off         like holding B2, on lasts until interrupted
+preset <n>        apply a preset
+watch             print status events until interrupted
+";
+
+// Raw mode, reads give up after a tenth of a second without data
+struct Tty(File);
+
+impl Tty {
+    fn open(path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let stty = Command::new("stty")
+            .args([
+                "-F", path, "115200", "raw", "-echo", "min", "0", "time", "1",
+            ])
+            .status()?;
+        if !stty.success() {
+            return Err(io::Error::other("stty failed"));
+        }
+        Ok(Tty(file))
+    }
+}
+
+impl Read for Tty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => Err(io::ErrorKind::TimedOut.into()),
+            n => Ok(n),
+        }
+    }
+}
+
+impl Write for Tty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let (tty, command) = match args.split_first() {
+        Some((tty, command)) if !command.is_empty() => (tty, command),
+        _ => usage(),
+    };
+
+    let mut client = match Tty::open(tty) {
+        Ok(tty) => Client::new(tty),
+        Err(e) => {
+            eprintln!("{}: {}", tty, e);
+            process::exit(1);
+        }
+    };
+
+    if let Err(e) = run(&mut client, command) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(client: &mut Client<Tty>, command: &[&str]) -> Result<(), Error> {
+    match command {
+        ["ping"] => client.ping().map(|_| println!("pong")),
+        ["status"] => client.status().map(|status| println!("{}", status)),
+        ["pwm", n] => match n.parse() {
+            Ok(pwm) if pwm <= lmc_types::status::PWM_MAX => client.set_pwm(pwm),
+            _ => usage(),
+        },
+        ["freq", "cont"] => client.set_freq(None),
+        ["freq", hz] => match hz.parse() {
+            Ok(0) => client.set_freq(None),
+            Ok(hz) => client.set_freq(Some(hz)),
+            Err(_) => usage(),
+        },
+        ["relay", s] => client.set_relay(on_off(s)),
+        ["oe", s] if on_off(s) => loop {
+            client.set_oe(true)?;
+            thread::sleep(OE_REFRESH);
+        },
+        ["oe", _] => client.set_oe(false),
+        ["preset", n] => match n.parse() {
+            Ok(preset) => client.apply_preset(preset),
+            Err(_) => usage(),
+        },
+        ["watch"] => {
+            // Events only start once the device has seen a request
+            client.ping()?;
+            loop {
+                if let Some(Event::Status(status)) = client.next_event()? {
+                    println!("{}", status);
+                }
+            }
+        }
+        _ => usage(),
+    }
+}
+
+fn on_off(s: &str) -> bool {
+    match s {
+        "on" => true,
+        "off" => false,
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprint!("{}", USAGE);
+    process::exit(2);
+}
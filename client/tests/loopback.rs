@@ -0,0 +1,313 @@
+// The client against the device end of the protocol over an in-memory pipe,
+// with a fake LCM in place of the firmware's main loop
+
+use lmc_client::{Client, Error};
+use lmc_proto::link::{Link, Received};
+use lmc_proto::packet::{ErrorCode, Event, Request, Response};
+use lmc_types::status::{Fault, Freq, State, Status, PWM_MAX};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+struct Pipe {
+    tx: Sender<Vec<u8>>,
+    rx: Receiver<Vec<u8>>,
+    pending: VecDeque<u8>,
+}
+
+fn pipe() -> (Pipe, Pipe) {
+    let (a_tx, b_rx) = mpsc::channel();
+    let (b_tx, a_rx) = mpsc::channel();
+    let end = |tx, rx| Pipe {
+        tx,
+        rx,
+        pending: VecDeque::new(),
+    };
+    (end(a_tx, a_rx), end(b_tx, b_rx))
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.rx.recv_timeout(Duration::from_millis(10)) {
+                Ok(bytes) => self.pending.extend(bytes),
+                Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::TimedOut.into()),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.pending.len());
+        for (b, p) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *b = p;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tx
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Mischief {
+    // Responses to drop
+    drop: AtomicUsize,
+    // Responses to send with a flipped bit
+    corrupt: AtomicUsize,
+}
+
+impl Mischief {
+    fn take(count: &AtomicUsize) -> bool {
+        count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+
+struct FakeLcm {
+    pwm: u16,
+    hz: u32,
+    relay: bool,
+    oe: bool,
+    fault: Option<Fault>,
+}
+
+impl FakeLcm {
+    fn status(&self) -> Status {
+        let (state, fault) = match self.fault {
+            Some(fault) => (State::Error, Some(fault)),
+            None if self.relay => (State::On, None),
+            None => (State::Off, None),
+        };
+        let freq = match self.hz {
+            0 => Freq::Continuous,
+            hz => Freq::Periodic(hz * 1000),
+        };
+        Status::new(state, self.pwm, freq)
+            .with_outputs(self.oe, self.relay)
+            .with_fault(fault)
+    }
+
+    fn serve(&mut self, request: Request) -> Response {
+        match request {
+            Request::Ping => Response::Pong,
+            Request::GetStatus => Response::Status(self.status()),
+            Request::SetPwm(pwm) if pwm > PWM_MAX => Response::Error(ErrorCode::OutOfRange),
+            Request::SetPwm(pwm) => {
+                self.pwm = pwm;
+                Response::Ok
+            }
+            Request::SetFreq(hz) => {
+                self.hz = hz;
+                Response::Ok
+            }
+            Request::SetRelay(true) | Request::SetOe(true) if self.fault.is_some() => {
+                Response::Error(ErrorCode::Refused)
+            }
+            Request::SetRelay(on) => {
+                self.relay = on;
+                if !on {
+                    self.fault = None;
+                }
+                Response::Ok
+            }
+            Request::SetOe(on) => {
+                self.oe = on;
+                Response::Ok
+            }
+            Request::ApplyPreset(0) => {
+                self.pwm = 1000;
+                self.hz = 30;
+                Response::Ok
+            }
+            Request::ApplyPreset(_) => Response::Error(ErrorCode::OutOfRange),
+        }
+    }
+}
+
+// Runs until the host end is dropped, returns the console text it got
+fn device(mut port: Pipe, mut lcm: FakeLcm, mischief: Arc<Mischief>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut link = Link::new();
+        let mut text = Vec::new();
+        let mut buf = [0; 16];
+
+        loop {
+            let n = match port.read(&mut buf) {
+                Ok(0) => return text,
+                Ok(n) => n,
+                Err(_) => continue,
+            };
+
+            for &byte in &buf[..n] {
+                let before = (lcm.status().state(), lcm.status().fault());
+                let (seq, response) = match link.push(byte) {
+                    Received::Nothing => continue,
+                    Received::Text(byte) => {
+                        text.push(byte);
+                        continue;
+                    }
+                    Received::Request(seq, request) => (seq, lcm.serve(request)),
+                    Received::Invalid(seq, code) => (seq, Response::Error(code)),
+                };
+
+                // Console output in between the frames
+                port.write_all(b"[info] request\r\n").ok();
+
+                let mut frame = link.respond(seq, response).as_bytes().to_vec();
+                if Mischief::take(&mischief.drop) {
+                    continue;
+                }
+                if Mischief::take(&mischief.corrupt) {
+                    frame[3] ^= 0x10;
+                }
+                port.write_all(&frame).ok();
+
+                if (lcm.status().state(), lcm.status().fault()) != before {
+                    if let Some(event) = link.event(Event::Status(lcm.status())) {
+                        port.write_all(event.as_bytes()).ok();
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn setup(lcm: FakeLcm) -> (Client<Pipe>, Arc<Mischief>, JoinHandle<Vec<u8>>) {
+    let (host, dev) = pipe();
+    let mischief = Arc::new(Mischief::default());
+    let handle = device(dev, lcm, mischief.clone());
+    let mut client = Client::new(host);
+    client.set_timeout(Duration::from_millis(100));
+    (client, mischief, handle)
+}
+
+fn idle() -> FakeLcm {
+    FakeLcm {
+        pwm: 0,
+        hz: 0,
+        relay: false,
+        oe: false,
+        fault: None,
+    }
+}
+
+#[test]
+fn ping_and_status() {
+    let (mut client, _, _) = setup(idle());
+    client.ping().unwrap();
+
+    let status = client.status().unwrap();
+    assert_eq!(status.state(), State::Off);
+    assert_eq!(status.freq(), Freq::Continuous);
+    assert_eq!(status.fault(), None);
+}
+
+#[test]
+fn settings_show_in_the_status() {
+    let (mut client, _, _) = setup(idle());
+    client.set_pwm(2048).unwrap();
+    client.set_freq(Some(120)).unwrap();
+    client.set_oe(true).unwrap();
+
+    let status = client.status().unwrap();
+    assert_eq!(status.pwm(), 2048);
+    assert_eq!(status.freq(), Freq::Periodic(120_000));
+    assert!(status.pwm_oe());
+
+    client.apply_preset(0).unwrap();
+    client.set_freq(None).unwrap();
+    let status = client.status().unwrap();
+    assert_eq!(status.pwm(), 1000);
+    assert_eq!(status.freq(), Freq::Continuous);
+
+    assert!(matches!(
+        client.apply_preset(9),
+        Err(Error::Device(ErrorCode::OutOfRange))
+    ));
+    assert!(matches!(
+        client.set_pwm(PWM_MAX + 1),
+        Err(Error::Device(ErrorCode::OutOfRange))
+    ));
+}
+
+#[test]
+fn refused_while_faulted() {
+    let mut lcm = idle();
+    lcm.fault = Some(Fault::OverTemperature);
+    let (mut client, _, _) = setup(lcm);
+
+    assert!(matches!(
+        client.set_relay(true),
+        Err(Error::Device(ErrorCode::Refused))
+    ));
+    assert_eq!(client.status().unwrap().state(), State::Error);
+
+    // Relay off clears the fault, like B0
+    client.set_relay(false).unwrap();
+    client.set_relay(true).unwrap();
+    assert_eq!(client.status().unwrap().state(), State::On);
+}
+
+#[test]
+fn retries_lost_and_corrupted_responses() {
+    let (mut client, mischief, _) = setup(idle());
+
+    mischief.drop.store(1, Ordering::SeqCst);
+    client.set_pwm(100).unwrap();
+
+    mischief.corrupt.store(2, Ordering::SeqCst);
+    assert_eq!(client.status().unwrap().pwm(), 100);
+
+    mischief.drop.store(3, Ordering::SeqCst);
+    assert!(matches!(client.ping(), Err(Error::Timeout)));
+    client.ping().unwrap();
+}
+
+#[test]
+fn state_changes_are_events() {
+    let (mut client, _, _) = setup(idle());
+    assert!(client.next_event().unwrap().is_none());
+
+    client.set_relay(true).unwrap();
+    match client.next_event().unwrap() {
+        Some(Event::Status(status)) => assert_eq!(status.state(), State::On),
+        None => panic!("no event"),
+    }
+
+    // Queued while waiting for a response
+    client.set_relay(false).unwrap();
+    client.ping().unwrap();
+    match client.next_event().unwrap() {
+        Some(Event::Status(status)) => assert_eq!(status.state(), State::Off),
+        None => panic!("no event"),
+    }
+    assert!(client.next_event().unwrap().is_none());
+}
+
+#[test]
+fn shares_the_line_with_the_console() {
+    let (mut client, _, handle) = setup(idle());
+    client.ping().unwrap();
+
+    let mut port = client.into_inner();
+    port.write_all(b"status\r").unwrap();
+    let mut client = Client::new(port);
+    client.ping().unwrap();
+
+    drop(client);
+    assert_eq!(handle.join().unwrap(), b"status\r");
+}
@@ -5,7 +5,7 @@
 ///
 /// Note that 90º and 270º rotations are not supported by
 // [`TerminalMode`](../mode/terminal/struct.TerminalMode.html).
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum DisplayRotation {
     /// No rotation, normal display
     Rotate0,
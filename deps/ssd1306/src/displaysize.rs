@@ -2,7 +2,7 @@
 
 // TODO: Add to prelude
 /// Display size enumeration
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum DisplaySize {
     /// 128 by 64 pixels
     Display128x64,
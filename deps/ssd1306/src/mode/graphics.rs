@@ -32,6 +32,8 @@ where
 {
     properties: DisplayProperties<DI>,
     buffer: [u8; 1024],
+    /// Buffer contents as of the last flush, `None` when the display contents are unknown
+    sent: Option<[u8; 1024]>,
 }
 
 impl<DI> DisplayModeTrait<DI> for GraphicsMode<DI>
@@ -43,6 +45,7 @@ where
         GraphicsMode {
             properties,
             buffer: [0; 1024],
+            sent: None,
         }
     }
 
@@ -85,11 +88,60 @@ where
         self.properties
             .set_draw_area((0, 0), (display_width, display_height))?;
 
+        // Unknown until the frame is fully sent
+        self.sent = None;
+
         match display_size {
             DisplaySize::Display128x64 => self.properties.draw(&self.buffer),
             DisplaySize::Display128x32 => self.properties.draw(&self.buffer[0..512]),
             DisplaySize::Display96x16 => self.properties.draw(&self.buffer[0..192]),
+        }?;
+
+        self.sent = Some(self.buffer);
+
+        Ok(())
+    }
+
+    /// Write out only the parts of the buffer that changed since the last flush. Each page with
+    /// changes is sent from its first to its last changed column. Returns whether anything was
+    /// sent. Falls back to a full `flush` when the display contents are unknown.
+    pub fn flush_changed(&mut self) -> Result<bool, ()> {
+        let mut sent = match self.sent.take() {
+            Some(sent) => sent,
+            None => return self.flush().map(|_| true),
+        };
+
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let width = display_width as usize;
+        let mut changed = false;
+
+        for page in 0..display_height as usize / 8 {
+            let row = page * width..(page + 1) * width;
+            let (new, old) = (&self.buffer[row.clone()], &mut sent[row]);
+
+            let first = match new.iter().zip(old.iter()).position(|(n, o)| n != o) {
+                Some(first) => first,
+                None => continue,
+            };
+            let last = new
+                .iter()
+                .zip(old.iter())
+                .rposition(|(n, o)| n != o)
+                .unwrap_or(first);
+
+            // On error self.sent stays None, the next call sends the full frame
+            let y = (page * 8) as u8;
+            self.properties
+                .set_draw_area((first as u8, y), (last as u8 + 1, y + 8))?;
+            self.properties.draw(&new[first..=last])?;
+
+            old[first..=last].copy_from_slice(&new[first..=last]);
+            changed = true;
         }
+
+        self.sent = Some(sent);
+
+        Ok(changed)
     }
 
     /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
@@ -145,6 +197,7 @@ where
     /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
     /// column 0 on the left, to column _n_ on the right
     pub fn init(&mut self) -> Result<(), ()> {
+        self.sent = None;
         self.properties.init_column_mode()?;
         Ok(())
     }
@@ -154,8 +207,24 @@ where
         self.properties.get_dimensions()
     }
 
+    /// Set the display contrast, higher values are brighter
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), ()> {
+        self.properties.set_contrast(contrast)
+    }
+
+    /// Turn the display on or off, the buffer is kept while off
+    pub fn display_on(&mut self, on: bool) -> Result<(), ()> {
+        self.properties.display_on(on)
+    }
+
+    /// Get the display rotation
+    pub fn get_rotation(&self) -> DisplayRotation {
+        self.properties.get_rotation()
+    }
+
     /// Set the display rotation
     pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), ()> {
+        self.sent = None;
         self.properties.set_rotation(rot)
     }
 }
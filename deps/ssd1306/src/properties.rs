@@ -82,6 +82,17 @@ where
         Ok(())
     }
 
+    /// Set the display contrast, higher values are brighter. Initialised to `0x8F`.
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), ()> {
+        Command::Contrast(contrast).send(&mut self.iface)
+    }
+
+    /// Turn the display on or off. While off the panel sleeps and keeps the contents of its
+    /// framebuffer.
+    pub fn display_on(&mut self, on: bool) -> Result<(), ()> {
+        Command::DisplayOn(on).send(&mut self.iface)
+    }
+
     /// Get the configured display size
     pub fn get_size(&self) -> DisplaySize {
         self.display_size
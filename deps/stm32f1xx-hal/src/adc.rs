@@ -1,5 +1,8 @@
 //! Analog to digital converter
 
+use core::ptr;
+use core::sync::atomic::{self, Ordering};
+
 use crate::stm32::{ADC1, ADC2, ADC3};
 
 use crate::hal::adc::{Channel, OneShot};
@@ -10,6 +13,7 @@ use crate::gpio::gpioa::*;
 use crate::gpio::gpiob::*;
 use crate::gpio::Analog;
 
+use crate::dma::dma1;
 use crate::rcc::APB2;
 
 pub struct Adc<ADC> {
@@ -17,6 +21,44 @@ pub struct Adc<ADC> {
     current_channel: Option<u8>,
 }
 
+/// Sample time, in ADC clock cycles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleTime {
+    Cycles1_5 = 0b000,
+    Cycles7_5 = 0b001,
+    Cycles13_5 = 0b010,
+    Cycles28_5 = 0b011,
+    Cycles41_5 = 0b100,
+    Cycles55_5 = 0b101,
+    Cycles71_5 = 0b110,
+    Cycles239_5 = 0b111,
+}
+
+/// External event that starts a conversion of the regular sequence on ADC1/ADC2
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trigger {
+    Tim1Cc1 = 0b000,
+    Tim1Cc2 = 0b001,
+    Tim1Cc3 = 0b010,
+    Tim2Cc2 = 0b011,
+    Tim3Trgo = 0b100,
+    Tim4Cc4 = 0b101,
+    Exti11 = 0b110,
+    SwStart = 0b111,
+}
+
+/// Continuous scan of a regular sequence on ADC1, with DMA1 channel 1 writing every
+/// conversion into a circular buffer
+pub struct Scan<BUFFER>
+where
+    BUFFER: 'static,
+{
+    adc: Adc<ADC1>,
+    channel: dma1::C1,
+    buffer: &'static mut BUFFER,
+    sequence_len: usize,
+}
+
 #[derive(Debug)]
 pub enum AdcReadError {
     /// Another conversion is already being performed
@@ -169,3 +211,181 @@ analog_pin_impls!{
         PB1: 9
     )
 }
+
+/// ADC1 channel of the internal temperature sensor
+pub const CHANNEL_TEMPERATURE: u8 = 16;
+
+/// ADC1 channel of the internal reference voltage, VREFINT
+pub const CHANNEL_VREFINT: u8 = 17;
+
+impl Adc<ADC1> {
+    /// Converts the regular `sequence` of channels on every `trigger` event. DMA1 channel 1
+    /// writes the results into `buffer` in circular mode, one sequence after another, so
+    /// the buffer length must be a multiple of the sequence length.
+    pub fn scan<B>(
+        self,
+        sequence: &[u8],
+        sample_time: SampleTime,
+        trigger: Trigger,
+        mut chan: dma1::C1,
+        buffer: &'static mut B,
+    ) -> Scan<B>
+    where
+        B: AsMut<[u16]> + AsRef<[u16]>,
+    {
+        assert!(!sequence.is_empty() && sequence.len() <= 16);
+
+        {
+            let buffer = buffer.as_mut();
+            assert!(!buffer.is_empty() && buffer.len() % sequence.len() == 0);
+
+            let (mut sqr1, mut sqr2, mut sqr3) = (0_u32, 0_u32, 0_u32);
+            for (i, &ch) in sequence.iter().enumerate() {
+                let bits = u32::from(ch & 0x1F);
+                match i {
+                    0..=5 => sqr3 |= bits << (5 * i),
+                    6..=11 => sqr2 |= bits << (5 * (i - 6)),
+                    _ => sqr1 |= bits << (5 * (i - 12)),
+                }
+            }
+            sqr1 |= (sequence.len() as u32 - 1) << 20;
+
+            let mut smpr1 = self.adc.smpr1.read().bits();
+            let mut smpr2 = self.adc.smpr2.read().bits();
+            for &ch in sequence {
+                let bits = sample_time as u32;
+                if ch < 10 {
+                    smpr2 = (smpr2 & !(0b111 << (3 * ch))) | (bits << (3 * ch));
+                } else {
+                    smpr1 = (smpr1 & !(0b111 << (3 * (ch - 10)))) | (bits << (3 * (ch - 10)));
+                }
+            }
+
+            unsafe {
+                self.adc.sqr1.write(|w| w.bits(sqr1));
+                self.adc.sqr2.write(|w| w.bits(sqr2));
+                self.adc.sqr3.write(|w| w.bits(sqr3));
+                self.adc.smpr1.write(|w| w.bits(smpr1));
+                self.adc.smpr2.write(|w| w.bits(smpr2));
+            }
+
+            chan.cpar()
+                .write(|w| w.pa().bits(&self.adc.dr as *const _ as usize as u32));
+            chan.cmar()
+                .write(|w| w.ma().bits(buffer.as_ptr() as usize as u32));
+            chan.cndtr().write(|w| w.ndt().bits(buffer.len() as u16));
+
+            // NOTE(compiler_fence) operations on `buffer` should not be reordered after
+            // the next statement, which starts the DMA transfer
+            atomic::compiler_fence(Ordering::SeqCst);
+
+            chan.ccr().modify(|_, w| {
+                w.mem2mem()
+                    .clear_bit()
+                    .pl()
+                    .medium()
+                    .msize()
+                    .bit16()
+                    .psize()
+                    .bit16()
+                    .minc()
+                    .set_bit()
+                    .pinc()
+                    .clear_bit()
+                    .circ()
+                    .set_bit()
+                    .dir()
+                    .clear_bit()
+                    .en()
+                    .set_bit()
+            });
+        }
+
+        // The temperature sensor and VREFINT channels need their buffers powered up
+        let internal = sequence
+            .iter()
+            .any(|&ch| ch == CHANNEL_TEMPERATURE || ch == CHANNEL_VREFINT);
+
+        self.adc.cr1.modify(|_, w| w.scan().set_bit());
+        self.adc.cr2.modify(|_, w| unsafe {
+            w.tsvrefe()
+                .bit(internal)
+                .cont()
+                .clear_bit()
+                .dma()
+                .set_bit()
+                .extsel()
+                .bits(trigger as u8)
+                .exttrig()
+                .set_bit()
+        });
+
+        Scan {
+            adc: self,
+            channel: chan,
+            buffer,
+            sequence_len: sequence.len(),
+        }
+    }
+}
+
+impl<B> Scan<B>
+where
+    B: AsRef<[u16]>,
+{
+    /// Number of channels in the regular sequence
+    pub fn sequence_len(&self) -> usize {
+        self.sequence_len
+    }
+
+    /// Length of the circular buffer, in samples
+    pub fn buffer_len(&self) -> usize {
+        self.buffer.as_ref().len()
+    }
+
+    /// Index in the buffer that the DMA writes next
+    pub fn write_index(&self) -> usize {
+        let len = self.buffer_len();
+        (len - self.channel.get_cndtr() as usize) % len
+    }
+
+    /// Reads the sample at `index`, which the DMA may be concurrently updating
+    pub fn read(&self, index: usize) -> u16 {
+        let buffer = self.buffer.as_ref();
+        // NOTE(read_volatile) the DMA writes to the buffer behind the compiler's back
+        unsafe { ptr::read_volatile(&buffer[index % buffer.len()]) }
+    }
+
+    /// Stops the conversions and the DMA transfer
+    pub fn stop(mut self) -> (Adc<ADC1>, dma1::C1, &'static mut B) {
+        self.adc.adc.cr2.modify(|_, w| w.exttrig().clear_bit().dma().clear_bit());
+        self.adc.adc.cr1.modify(|_, w| w.scan().clear_bit());
+        unsafe { self.adc.adc.sqr1.modify(|_, w| w.l().bits(0)) };
+
+        self.channel.ccr().modify(|_, w| w.en().clear_bit());
+
+        // NOTE(compiler_fence) operations on `buffer` should not be reordered
+        // before the previous statement, which stops the DMA transfer
+        atomic::compiler_fence(Ordering::SeqCst);
+
+        (self.adc, self.channel, self.buffer)
+    }
+}
+
+impl<B> Scan<B>
+where
+    B: AsRef<[u16]> + AsMut<[u16]>,
+{
+    /// Reads the sample at `index` and replaces it with `value`, such as a marker the ADC
+    /// can't produce to tell later whether the DMA has written the slot again
+    pub fn replace(&mut self, index: usize, value: u16) -> u16 {
+        let buffer = self.buffer.as_mut();
+        let slot = &mut buffer[index % buffer.len()];
+        // NOTE(volatile) the DMA writes to the buffer behind the compiler's back
+        unsafe {
+            let sample = ptr::read_volatile(slot);
+            ptr::write_volatile(slot, value);
+            sample
+        }
+    }
+}
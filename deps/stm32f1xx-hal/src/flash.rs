@@ -1,7 +1,35 @@
 //! Flash memory
 
+use core::ptr;
+
 use crate::pac::{flash, FLASH};
 
+/// Size of an erase page on the low and medium density devices
+pub const PAGE_SIZE: u32 = 1024;
+
+const FLASH_START: u32 = 0x0800_0000;
+
+const KEY1: u32 = 0x4567_0123;
+const KEY2: u32 = 0xCDEF_89AB;
+
+// Status flags, cleared by writing 1
+const SR_PGERR: u32 = 1 << 2;
+const SR_WRPRTERR: u32 = 1 << 4;
+const SR_EOP: u32 = 1 << 5;
+
+/// Flash erase and program errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The address is not in the main flash, or not aligned
+    Address,
+    /// The page is write protected
+    WriteProtected,
+    /// A halfword was programmed without being erased first
+    NotErased,
+    /// A programmed halfword reads back differently
+    Verify,
+}
+
 /// Extension trait to constrain the FLASH peripheral
 pub trait FlashExt {
     /// Constrains the FLASH peripheral to play nicely with the other abstractions
@@ -12,6 +40,7 @@ impl FlashExt for FLASH {
     fn constrain(self) -> Parts {
         Parts {
             acr: ACR { _0: () },
+            writer: FlashWriter { _0: () },
         }
     }
 }
@@ -20,6 +49,8 @@ impl FlashExt for FLASH {
 pub struct Parts {
     /// Opaque ACR register
     pub acr: ACR,
+    /// Erases and programs the main flash
+    pub writer: FlashWriter,
 }
 
 /// Opaque ACR register
@@ -33,3 +64,95 @@ impl ACR {
         unsafe { &(*FLASH::ptr()).acr }
     }
 }
+
+/// Erases pages and programs halfwords of the main flash, through the KEYR, SR, CR and
+/// AR registers. The CPU stalls on flash accesses while an operation is in progress, and
+/// the HSI must be running.
+pub struct FlashWriter {
+    _0: (),
+}
+
+impl FlashWriter {
+    /// Erases the page starting at `address`
+    pub fn erase_page(&mut self, address: u32) -> Result<(), Error> {
+        if address < FLASH_START || (address - FLASH_START) % PAGE_SIZE != 0 {
+            return Err(Error::Address);
+        }
+
+        self.unlock();
+        let flash = self.flash();
+        flash.cr.modify(|_, w| w.per().set_bit());
+        flash.ar.write(|w| unsafe { w.far().bits(address) });
+        flash.cr.modify(|_, w| w.strt().set_bit());
+        let result = self.wait();
+        self.flash().cr.modify(|_, w| w.per().clear_bit());
+        self.lock();
+        result
+    }
+
+    /// Programs `data` from `address` on, into halfwords erased beforehand
+    pub fn program(&mut self, address: u32, data: &[u16]) -> Result<(), Error> {
+        if address < FLASH_START || address % 2 != 0 {
+            return Err(Error::Address);
+        }
+
+        self.unlock();
+        self.flash().cr.modify(|_, w| w.pg().set_bit());
+        let mut result = Ok(());
+        for (i, &halfword) in data.iter().enumerate() {
+            let target = (address as usize + 2 * i) as *mut u16;
+            // NOTE(unsafe) a halfword write with PG set programs the flash
+            unsafe { ptr::write_volatile(target, halfword) };
+            result = self.wait().and_then(|_| {
+                // NOTE(unsafe) reads back the programmed flash
+                if unsafe { ptr::read_volatile(target) } == halfword {
+                    Ok(())
+                } else {
+                    Err(Error::Verify)
+                }
+            });
+            if result.is_err() {
+                break;
+            }
+        }
+        self.flash().cr.modify(|_, w| w.pg().clear_bit());
+        self.lock();
+        result
+    }
+
+    fn flash(&self) -> &flash::RegisterBlock {
+        // NOTE(unsafe) this proxy grants exclusive access to KEYR, SR, CR and AR
+        unsafe { &*FLASH::ptr() }
+    }
+
+    fn unlock(&mut self) {
+        let flash = self.flash();
+        if flash.cr.read().lock().bit_is_set() {
+            flash.keyr.write(|w| unsafe { w.key().bits(KEY1) });
+            flash.keyr.write(|w| unsafe { w.key().bits(KEY2) });
+        }
+    }
+
+    fn lock(&mut self) {
+        self.flash().cr.modify(|_, w| w.lock().set_bit());
+    }
+
+    // Waits for the operation in progress and clears its flags
+    fn wait(&self) -> Result<(), Error> {
+        let flash = self.flash();
+        while flash.sr.read().bsy().bit_is_set() {}
+
+        let sr = flash.sr.read().bits();
+        flash
+            .sr
+            .write(|w| unsafe { w.bits(SR_PGERR | SR_WRPRTERR | SR_EOP) });
+
+        if sr & SR_WRPRTERR != 0 {
+            Err(Error::WriteProtected)
+        } else if sr & SR_PGERR != 0 {
+            Err(Error::NotErased)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -530,8 +530,22 @@ gpio!(GPIOB, gpiob, gpioa, iopben, iopbrst, PBx, [
     PB15: (pb15, 15, Input<Floating>, CRH),
 ]);
 
+// PC0-PC12 are only bonded out on the 64 pin and larger packages
 #[cfg(not(feature = "stm32f100"))]
 gpio!(GPIOC, gpioc, gpioa, iopcen, iopcrst, PCx, [
+    PC0: (pc0, 0, Input<Floating>, CRL),
+    PC1: (pc1, 1, Input<Floating>, CRL),
+    PC2: (pc2, 2, Input<Floating>, CRL),
+    PC3: (pc3, 3, Input<Floating>, CRL),
+    PC4: (pc4, 4, Input<Floating>, CRL),
+    PC5: (pc5, 5, Input<Floating>, CRL),
+    PC6: (pc6, 6, Input<Floating>, CRL),
+    PC7: (pc7, 7, Input<Floating>, CRL),
+    PC8: (pc8, 8, Input<Floating>, CRH),
+    PC9: (pc9, 9, Input<Floating>, CRH),
+    PC10: (pc10, 10, Input<Floating>, CRH),
+    PC11: (pc11, 11, Input<Floating>, CRH),
+    PC12: (pc12, 12, Input<Floating>, CRH),
     PC13: (pc13, 13, Input<Floating>, CRH),
     PC14: (pc14, 14, Input<Floating>, CRH),
     PC15: (pc15, 15, Input<Floating>, CRH),
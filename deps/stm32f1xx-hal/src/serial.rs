@@ -48,6 +48,7 @@ use crate::afio::MAPR;
 // use crate::dma::{CircBuffer, Static, Transfer, R, W};
 use crate::gpio::gpioa::{PA10, PA2, PA3, PA9};
 use crate::gpio::gpiob::{PB10, PB11, PB6, PB7};
+use crate::gpio::gpioc::{PC10, PC11};
 use crate::gpio::{Alternate, Floating, Input, PushPull};
 use crate::rcc::{Clocks, APB1, APB2};
 use crate::time::Bps;
@@ -99,9 +100,9 @@ impl Pins<USART3> for (PB10<Alternate<PushPull>>, PB11<Input<Floating>>) {
     const REMAP: u8 = 0;
 }
 
-// impl Pins<USART3> for (PC10<Alternate<PushPull>>, PC11<Input<Floating>>) {
-//     const REMAP: u8 = 1;
-// }
+impl Pins<USART3> for (PC10<Alternate<PushPull>>, PC11<Input<Floating>>) {
+    const REMAP: u8 = 1;
+}
 
 // impl Pins<USART3> for (PD8<Alternate<PushPull>>, PD9<Input<Floating>>) {
 //     const REMAP: u8 = 0b11;
@@ -127,6 +127,14 @@ macro_rules! hal {
                         Event::Update => self.tim.dier.write(|w| w.uie().clear_bit()),
                     }
                 }
+
+                /// Emits TRGO on every update event, e.g. to trigger ADC conversions at the
+                /// timer rate
+                pub fn trigger_on_update(&mut self) {
+                    // NOTE(unsafe) MMS is only an unsafe field on TIM1
+                    #[allow(unused_unsafe)]
+                    self.tim.cr2.modify(|_, w| unsafe { w.mms().bits(0b010) });
+                }
             }
 
             impl CountDown for Timer<$TIMX> {
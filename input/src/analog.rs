@@ -0,0 +1,104 @@
+use core::cmp;
+use core::marker::PhantomData;
+use embedded_hal::adc::{Channel, OneShot};
+
+// Full-scale resolution of a channel, in bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Resolution(u8);
+
+impl Resolution {
+    pub fn bits(bits: u8) -> Self {
+        Resolution(bits.clamp(1, 16))
+    }
+
+    pub fn max(self) -> u16 {
+        ((1_u32 << self.0) - 1) as u16
+    }
+}
+
+// Type-erased analog channel, so channels of different pin types can share
+// storage while being sampled by the same ADC
+pub trait AnalogPin<ADC> {
+    // Number of samples to read on this update
+    fn pending(&mut self, adc: &mut ADC) -> usize;
+
+    fn read(&mut self, adc: &mut ADC) -> u16;
+
+    fn resolution(&self) -> Resolution;
+
+    // Times unread samples were lost, wrapping
+    fn overruns(&self) -> u32 {
+        0
+    }
+
+    // Conversions that failed, wrapping
+    fn errors(&self) -> u32 {
+        0
+    }
+}
+
+// Binds a pin to the ADC peripheral it is sampled by, ADC1, ADC2, an external
+// ADC, or anything else implementing OneShot. Polled once per update without
+// blocking, a conversion still in progress is picked up on a later update.
+pub struct AdcPin<A, PIN> {
+    pin: PIN,
+    resolution: Resolution,
+    sample: Option<u16>,
+    errors: u32,
+    _adc: PhantomData<A>,
+}
+
+impl<A, PIN> AdcPin<A, PIN>
+where
+    PIN: Channel<A>,
+{
+    pub fn new(pin: PIN, resolution: Resolution) -> Self {
+        AdcPin {
+            pin,
+            resolution,
+            sample: None,
+            errors: 0,
+            _adc: PhantomData,
+        }
+    }
+
+    // One conversion, clamped to the resolution
+    pub fn sample<ADC>(&mut self, adc: &mut ADC) -> nb::Result<u16, ADC::Error>
+    where
+        ADC: OneShot<A, u16, PIN>,
+    {
+        let sample = adc.read(&mut self.pin)?;
+        Ok(cmp::min(sample, self.resolution.max()))
+    }
+}
+
+impl<ADC, A, PIN> AnalogPin<ADC> for AdcPin<A, PIN>
+where
+    ADC: OneShot<A, u16, PIN>,
+    PIN: Channel<A>,
+{
+    // A failed conversion is counted and skipped
+    fn pending(&mut self, adc: &mut ADC) -> usize {
+        self.sample = match self.sample(adc) {
+            Ok(sample) => Some(sample),
+            Err(nb::Error::WouldBlock) => None,
+            Err(nb::Error::Other(_)) => {
+                self.errors = self.errors.wrapping_add(1);
+                None
+            }
+        };
+        self.sample.is_some() as usize
+    }
+
+    fn read(&mut self, _adc: &mut ADC) -> u16 {
+        self.sample.take().unwrap_or(0)
+    }
+
+    fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    fn errors(&self) -> u32 {
+        self.errors
+    }
+}
@@ -0,0 +1,46 @@
+// A button of the firmware's Input, by the order it was added
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Button(u8);
+
+impl Button {
+    // Up to 8 buttons, one bit of a ButtonSet each
+    pub fn new(index: u8) -> Self {
+        assert!(index < 8);
+        Button(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// Set of buttons, one bit per Button
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ButtonSet(u8);
+
+impl ButtonSet {
+    pub fn empty() -> Self {
+        ButtonSet(0)
+    }
+
+    pub fn with(self, btn: Button) -> Self {
+        ButtonSet(self.0 | (1 << btn.0))
+    }
+
+    pub fn contains(self, btn: Button) -> bool {
+        self.0 & (1 << btn.0) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: ButtonSet) -> Self {
+        ButtonSet(self.0 | other.0)
+    }
+
+    // The buttons of self that aren't in other
+    pub fn difference(self, other: ButtonSet) -> Self {
+        ButtonSet(self.0 & !other.0)
+    }
+}
@@ -0,0 +1,105 @@
+use core::cmp;
+
+// Percent of the measured span given to each dead zone by the calibration
+// routine
+const DEAD_ZONE_PERCENT: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Calibration {
+    // Raw readings at the end stops
+    pub min: u16,
+    pub max: u16,
+    // Counts just inside the end stops that still read as the end stop
+    pub dead_bottom: u16,
+    pub dead_top: u16,
+    pub reverse: bool,
+}
+
+impl Calibration {
+    pub fn full_scale(max: u16) -> Self {
+        Calibration {
+            min: 0,
+            max,
+            dead_bottom: 0,
+            dead_top: 0,
+            reverse: false,
+        }
+    }
+
+    // Maps a raw reading onto 0..=full
+    pub fn apply(&self, raw: u16, full: u16) -> u16 {
+        let lo = self.min.saturating_add(self.dead_bottom) as u32;
+        let hi = self.max.saturating_sub(self.dead_top) as u32;
+        let raw = raw as u32;
+
+        let x = if hi <= lo || raw <= lo {
+            0
+        } else if raw >= hi {
+            full as u32
+        } else {
+            (raw - lo) * full as u32 / (hi - lo)
+        };
+
+        if self.reverse {
+            full - x as u16
+        } else {
+            x as u16
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Step {
+    Min,
+    Max,
+}
+
+// Captures the end stops of a pot, one button press at each end of the travel
+pub struct Calibrator {
+    step: Step,
+    min: u16,
+}
+
+impl Default for Calibrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Calibrator {
+    pub fn new() -> Self {
+        Calibrator {
+            step: Step::Min,
+            min: 0,
+        }
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    // Returns the calibration once both end stops are captured
+    pub fn capture(&mut self, raw: u16) -> Option<Calibration> {
+        match self.step {
+            Step::Min => {
+                self.min = raw;
+                self.step = Step::Max;
+                None
+            }
+            Step::Max => {
+                self.step = Step::Min;
+
+                let (min, max) = (cmp::min(self.min, raw), cmp::max(self.min, raw));
+                let dead_zone = ((max - min) as u32 * DEAD_ZONE_PERCENT / 100) as u16;
+
+                Some(Calibration {
+                    min,
+                    max,
+                    dead_bottom: dead_zone,
+                    dead_top: dead_zone,
+                    reverse: raw < self.min,
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::analog::{AnalogPin, Resolution};
+use core::cell::Cell;
+use core::cmp;
+use embedded_hal::Qei;
+use heapless::consts::U4;
+use heapless::Vec;
+use lmc_types::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Error {
+    Full,
+}
+
+// Quadrature counts per detent, the timer counts every edge of both channels
+const COUNTS_PER_DETENT: i32 = 4;
+
+// Step multipliers for detents arriving faster than the given interval (ms),
+// fastest first
+const ACCELERATION: [(u32, i32); 2] = [(25, 8), (60, 3)];
+
+// A value adjusted by the encoder in steps of `step`, read back through Input
+// like the pots
+pub struct Setpoint {
+    value: Cell<u16>,
+    step: u16,
+    resolution: Resolution,
+}
+
+impl Setpoint {
+    pub fn new(value: u16, step: u16, resolution: Resolution) -> Self {
+        Setpoint {
+            value: Cell::new(cmp::min(value, resolution.max())),
+            step,
+            resolution,
+        }
+    }
+
+    pub fn value(&self) -> u16 {
+        self.value.get()
+    }
+
+    pub fn pin(&self) -> SetpointPin<'_> {
+        SetpointPin { setpoint: self }
+    }
+
+    fn adjust(&self, steps: i32) {
+        let value = self.value.get() as i32 + steps * self.step as i32;
+        self.value
+            .set(value.clamp(0, self.resolution.max() as i32) as u16);
+    }
+}
+
+// Analog channel view of a setpoint, the ADC is not involved
+pub struct SetpointPin<'s> {
+    setpoint: &'s Setpoint,
+}
+
+impl<'s, ADC> AnalogPin<ADC> for SetpointPin<'s> {
+    fn pending(&mut self, _adc: &mut ADC) -> usize {
+        1
+    }
+
+    fn read(&mut self, _adc: &mut ADC) -> u16 {
+        self.setpoint.value.get()
+    }
+
+    fn resolution(&self) -> Resolution {
+        self.setpoint.resolution
+    }
+}
+
+// Detented encoder adjusting one of several setpoints at a time
+pub struct Encoder<'s, QEI> {
+    qei: QEI,
+    count: u16,
+    // Counts toward the next detent
+    partial: i32,
+    last_detent: Option<Instant>,
+    setpoints: Vec<&'s Setpoint, U4>,
+    active: usize,
+}
+
+impl<'s, QEI> Encoder<'s, QEI>
+where
+    QEI: Qei<Count = u16>,
+{
+    pub fn new(qei: QEI) -> Self {
+        let count = qei.count();
+        Encoder {
+            qei,
+            count,
+            partial: 0,
+            last_detent: None,
+            setpoints: Vec::new(),
+            active: 0,
+        }
+    }
+
+    pub fn add_setpoint(&mut self, setpoint: &'s Setpoint) -> Result<(), Error> {
+        self.setpoints.push(setpoint).map_err(|_| Error::Full)
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.setpoints.is_empty() {
+            self.active = (self.active + 1) % self.setpoints.len();
+        }
+        self.partial = 0;
+    }
+
+    // Applies the detents turned since the last update to the active
+    // setpoint, returns the number of detents (negative counter-clockwise)
+    pub fn update(&mut self, now: Instant) -> i32 {
+        let count = self.qei.count();
+
+        // The timer wraps at u16::MAX, as long as this is polled more often
+        // than every 32k counts the signed difference is the true delta
+        let delta = count.wrapping_sub(self.count) as i16;
+        self.count = count;
+
+        let counts = self.partial + delta as i32;
+        let detents = counts / COUNTS_PER_DETENT;
+        self.partial = counts % COUNTS_PER_DETENT;
+
+        if detents == 0 {
+            return 0;
+        }
+
+        let multiplier = self.acceleration(now, detents.unsigned_abs());
+        self.last_detent = Some(now);
+
+        if let Some(setpoint) = self.setpoints.get(self.active) {
+            setpoint.adjust(detents * multiplier);
+        }
+
+        detents
+    }
+
+    fn acceleration(&self, now: Instant, detents: u32) -> i32 {
+        let interval = match self.last_detent {
+            Some(last) => now.since(last) / detents,
+            None => return 1,
+        };
+
+        ACCELERATION
+            .iter()
+            .find(|(ms, _)| interval < *ms)
+            .map_or(1, |(_, multiplier)| *multiplier)
+    }
+}
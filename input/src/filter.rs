@@ -0,0 +1,130 @@
+use core::cmp;
+
+const MEDIAN_MAX: usize = 5;
+const IIR_FRAC_BITS: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FilterConfig {
+    // Number of samples averaged into each output, 1 disables
+    pub oversample: u8,
+    // Median-of-N spike reject over the last N samples, 1 disables
+    pub median: u8,
+    // First-order IIR, y += (x - y) / 2^shift, 0 disables
+    pub iir_shift: u8,
+    // Output only follows the filtered value once it moves further than this
+    pub hysteresis: u16,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        FilterConfig {
+            oversample: 8,
+            median: 3,
+            iir_shift: 2,
+            hysteresis: 8,
+        }
+    }
+}
+
+pub struct Filter {
+    config: FilterConfig,
+    max: u16,
+    window: [u16; MEDIAN_MAX],
+    window_len: usize,
+    window_idx: usize,
+    acc: u32,
+    acc_len: u8,
+    iir: Option<u32>,
+    output: Option<u16>,
+}
+
+impl Filter {
+    pub fn new(config: FilterConfig, max: u16) -> Self {
+        Filter {
+            config,
+            max,
+            window: [0; MEDIAN_MAX],
+            window_len: 0,
+            window_idx: 0,
+            acc: 0,
+            acc_len: 0,
+            iir: None,
+            output: None,
+        }
+    }
+
+    // Returns a new output once every `oversample` samples
+    pub fn update(&mut self, sample: u16) -> Option<u16> {
+        let x = self.oversample(sample)?;
+        let x = self.median(x);
+        let x = self.iir(x);
+        Some(self.hysteresis(x))
+    }
+
+    fn oversample(&mut self, sample: u16) -> Option<u16> {
+        self.acc += sample as u32;
+        self.acc_len += 1;
+
+        if self.acc_len < cmp::max(self.config.oversample, 1) {
+            return None;
+        }
+
+        let x = self.acc / self.acc_len as u32;
+        self.acc = 0;
+        self.acc_len = 0;
+        Some(x as u16)
+    }
+
+    fn median(&mut self, sample: u16) -> u16 {
+        // Odd window lengths only, so there is always a middle sample
+        let n = cmp::min(self.config.median as usize, MEDIAN_MAX) | 1;
+        if n == 1 {
+            return sample;
+        }
+
+        self.window[self.window_idx % n] = sample;
+        self.window_idx = (self.window_idx + 1) % n;
+        self.window_len = cmp::min(self.window_len + 1, n);
+
+        let mut sorted = [0; MEDIAN_MAX];
+        let sorted = &mut sorted[..self.window_len];
+        sorted.copy_from_slice(&self.window[..self.window_len]);
+        sorted.sort_unstable();
+
+        sorted[self.window_len / 2]
+    }
+
+    fn iir(&mut self, sample: u16) -> u16 {
+        if self.config.iir_shift == 0 {
+            return sample;
+        }
+
+        let shift = cmp::min(self.config.iir_shift, 15);
+        let x = (sample as u32) << IIR_FRAC_BITS;
+        let y = match self.iir {
+            None => x,
+            Some(y) if x >= y => y + ((x - y) >> shift),
+            Some(y) => y - ((y - x) >> shift),
+        };
+        self.iir = Some(y);
+
+        // Round to nearest
+        ((y + (1 << (IIR_FRAC_BITS - 1))) >> IIR_FRAC_BITS) as u16
+    }
+
+    fn hysteresis(&mut self, sample: u16) -> u16 {
+        let band = self.config.hysteresis;
+        let output = match self.output {
+            None => sample,
+            Some(prev) if sample > prev.saturating_add(band) => sample,
+            Some(prev) if sample < prev.saturating_sub(band) => sample,
+            // Let the ends of the travel through, otherwise they are never
+            // reachable from within the band
+            Some(_) if sample <= band / 2 => 0,
+            Some(_) if sample >= self.max.saturating_sub(band / 2) => self.max,
+            Some(prev) => prev,
+        };
+        self.output = Some(output);
+        output
+    }
+}
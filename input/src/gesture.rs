@@ -0,0 +1,193 @@
+use crate::button::{Button, ButtonSet};
+use heapless::consts::U8;
+use heapless::Vec;
+use lmc_types::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Error {
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gesture {
+    // Exactly these buttons held together for the given time (ms)
+    Chord(ButtonSet, u32),
+    // The button pressed this many times, each press within the given time
+    // (ms) of the previous one
+    Sequence(Button, u8, u32),
+    // The button on its own, when it's released or once it has been held
+    // for the given time (ms), whichever comes first. Nothing fires if
+    // another button joins it before then, so it can start a chord.
+    Press(Button, u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Progress {
+    Idle,
+    // Chord held since, fires once per hold
+    Holding(Instant),
+    // Chord or press fired, until the buttons change or are released
+    Fired,
+    // Sequence presses so far and the time of the last one
+    Counting(u8, Instant),
+}
+
+struct Entry<A> {
+    gesture: Gesture,
+    action: A,
+    progress: Progress,
+}
+
+// Maps button chords and timed sequences onto actions
+pub struct Gestures<A> {
+    entries: Vec<Entry<A>, U8>,
+    held: ButtonSet,
+    // Every button pressed since the first of them went down, and when
+    session: ButtonSet,
+    since: Instant,
+}
+
+impl<A> Default for Gestures<A>
+where
+    A: Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Gestures<A>
+where
+    A: Copy,
+{
+    pub fn new() -> Self {
+        Gestures {
+            entries: Vec::new(),
+            held: ButtonSet::empty(),
+            session: ButtonSet::empty(),
+            since: Instant::from_millis(0),
+        }
+    }
+
+    pub fn add(&mut self, gesture: Gesture, action: A) -> Result<(), Error> {
+        self.entries
+            .push(Entry {
+                gesture,
+                action,
+                progress: Progress::Idle,
+            })
+            .map_err(|_| Error::Full)
+    }
+
+    // Takes the buttons held as of the latest Input update, returns the
+    // action of the first gesture completed
+    pub fn update(&mut self, now: Instant, held: ButtonSet) -> Option<A> {
+        let prev = self.held;
+        self.held = held;
+        if prev.is_empty() {
+            self.session = held;
+            self.since = now;
+        } else {
+            self.session = self.session.union(held);
+        }
+        let released = !prev.is_empty() && held.is_empty();
+
+        let mut action = None;
+        for entry in self.entries.iter_mut() {
+            let (progress, done) = match entry.gesture {
+                Gesture::Chord(buttons, hold_ms) => {
+                    Self::chord(entry.progress, now, held, buttons, hold_ms)
+                }
+                Gesture::Sequence(btn, count, window_ms) => {
+                    let pressed = held.contains(btn) && !prev.contains(btn);
+                    Self::sequence(entry.progress, now, pressed, count, window_ms)
+                }
+                Gesture::Press(btn, window_ms) => {
+                    let alone = self.session == ButtonSet::empty().with(btn);
+                    let held_ms = now.since(self.since);
+                    Self::press(entry.progress, alone, released, held_ms, window_ms)
+                }
+            };
+
+            entry.progress = progress;
+            if done && action.is_none() {
+                action = Some(entry.action);
+            }
+        }
+
+        action
+    }
+
+    // True while the button is held on its own and has been for at least
+    // window_ms, the level counterpart of Press
+    pub fn solo(&self, now: Instant, btn: Button, window_ms: u32) -> bool {
+        self.held == ButtonSet::empty().with(btn)
+            && self.session == self.held
+            && now.since(self.since) >= window_ms
+    }
+
+    fn chord(
+        progress: Progress,
+        now: Instant,
+        held: ButtonSet,
+        buttons: ButtonSet,
+        hold_ms: u32,
+    ) -> (Progress, bool) {
+        if held != buttons || buttons.is_empty() {
+            return (Progress::Idle, false);
+        }
+
+        match progress {
+            Progress::Holding(since) if now.since(since) >= hold_ms => (Progress::Fired, true),
+            Progress::Holding(since) => (Progress::Holding(since), false),
+            Progress::Fired => (Progress::Fired, false),
+            _ => (Progress::Holding(now), false),
+        }
+    }
+
+    fn sequence(
+        progress: Progress,
+        now: Instant,
+        pressed: bool,
+        count: u8,
+        window_ms: u32,
+    ) -> (Progress, bool) {
+        let (presses, last) = match progress {
+            Progress::Counting(presses, last) if now.since(last) <= window_ms => (presses, last),
+            _ => (0, now),
+        };
+
+        if !pressed {
+            return match presses {
+                0 => (Progress::Idle, false),
+                _ => (Progress::Counting(presses, last), false),
+            };
+        }
+
+        if presses + 1 >= count {
+            (Progress::Idle, true)
+        } else {
+            (Progress::Counting(presses + 1, now), false)
+        }
+    }
+
+    fn press(
+        progress: Progress,
+        alone: bool,
+        released: bool,
+        held_ms: u32,
+        window_ms: u32,
+    ) -> (Progress, bool) {
+        if !alone {
+            return (Progress::Idle, false);
+        }
+
+        match progress {
+            Progress::Fired if released => (Progress::Idle, false),
+            Progress::Fired => (Progress::Fired, false),
+            _ if released => (Progress::Idle, true),
+            _ if held_ms >= window_ms => (Progress::Fired, true),
+            _ => (Progress::Idle, false),
+        }
+    }
+}
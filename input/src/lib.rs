@@ -0,0 +1,14 @@
+// Setpoint and button processing behind the firmware's Input, kept free of
+// the HAL so it can be tested on the host.
+
+#![no_std]
+
+pub mod analog;
+pub mod button;
+pub mod calibration;
+pub mod encoder;
+pub mod filter;
+pub mod gesture;
+pub mod mapping;
+pub mod plausibility;
+pub mod scan;
@@ -0,0 +1,196 @@
+use core::cmp;
+
+// Fractional bits of the Q16.16 fixed-point values
+pub const Q16_ONE: i32 = 1 << 16;
+
+// Largest quotient kept by scale(), anything larger saturates the result
+const QUOTIENT_MAX: u64 = 1 << 40;
+
+// Normalized curves, y over 0..=Q16_ONE sampled at 17 evenly spaced points of
+// 0..=Q16_ONE, spanning 64:1 (~36 dB) between the ends
+const EXP_CURVE: [i32; 17] = [
+    0, 309, 709, 1229, 1902, 2775, 3908, 5377, 7282, 9752, 12956, 17110, 22498, 29485, 38546,
+    50297, 65536,
+];
+const LOG_CURVE: [i32; 17] = [
+    0, 25163, 34404, 40190, 44413, 47740, 50485, 52823, 54858, 56660, 58277, 59743, 61085, 62321,
+    63467, 64535, 65536,
+];
+
+// What to do with inputs outside the input range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Clamp {
+    Saturate,
+    Extrapolate,
+    Wrap,
+}
+
+// Overflow-safe linear map between two i32 ranges, either of which may be
+// signed or inverted (max < min)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinearMap {
+    in_min: i32,
+    in_max: i32,
+    out_min: i32,
+    out_max: i32,
+    clamp: Clamp,
+}
+
+impl LinearMap {
+    pub fn new(input: (i32, i32), output: (i32, i32), clamp: Clamp) -> Self {
+        LinearMap {
+            in_min: input.0,
+            in_max: input.1,
+            out_min: output.0,
+            out_max: output.1,
+            clamp,
+        }
+    }
+
+    pub fn map(&self, x: i32) -> i32 {
+        saturate(div_round(self.map_fixed(x), Q16_ONE as i64))
+    }
+
+    // Same as map, with the result in Q16.16 fixed point
+    pub fn map_q16(&self, x: i32) -> i32 {
+        saturate(self.map_fixed(x))
+    }
+
+    fn map_fixed(&self, x: i32) -> i64 {
+        let in_span = self.in_max as i64 - self.in_min as i64;
+        let out_span = self.out_max as i64 - self.out_min as i64;
+
+        if in_span == 0 {
+            return (self.out_min as i64) << 16;
+        }
+
+        let x = self.clamp_input(x) as i64 - self.in_min as i64;
+        ((self.out_min as i64) << 16) + scale(x, out_span, in_span)
+    }
+
+    fn clamp_input(&self, x: i32) -> i32 {
+        let lo = cmp::min(self.in_min, self.in_max);
+        let hi = cmp::max(self.in_min, self.in_max);
+
+        match self.clamp {
+            Clamp::Saturate => x.clamp(lo, hi),
+            Clamp::Extrapolate => x,
+            Clamp::Wrap => {
+                let span = hi as i64 - lo as i64 + 1;
+                (lo as i64 + (x as i64 - lo as i64).rem_euclid(span)) as i32
+            }
+        }
+    }
+}
+
+// Piecewise-linear map through (input, output) points sorted by input,
+// saturating outside the first and last points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Table<'a> {
+    points: &'a [(i32, i32)],
+}
+
+impl<'a> Table<'a> {
+    pub fn new(points: &'a [(i32, i32)]) -> Self {
+        assert!(!points.is_empty());
+        assert!(points.windows(2).all(|w| w[0].0 <= w[1].0));
+        Table { points }
+    }
+
+    pub fn map(&self, x: i32) -> i32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+
+        if x <= first.0 {
+            return first.1;
+        }
+        if x >= last.0 {
+            return last.1;
+        }
+
+        let i = self.points.iter().position(|p| p.0 > x).unwrap_or(1);
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        LinearMap::new((a.0, b.0), (a.1, b.1), Clamp::Saturate).map(x)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Curve {
+    Linear,
+    // Fine control at the low end of the input range
+    Exp,
+    // Fine control at the high end of the input range
+    Log,
+}
+
+// Maps through a normalized curve, e.g. for perceptual brightness or
+// frequency knobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CurveMap {
+    curve: Curve,
+    normalize: LinearMap,
+    scale: LinearMap,
+}
+
+impl CurveMap {
+    pub fn new(curve: Curve, input: (i32, i32), output: (i32, i32)) -> Self {
+        CurveMap {
+            curve,
+            normalize: LinearMap::new(input, (0, Q16_ONE), Clamp::Saturate),
+            scale: LinearMap::new((0, Q16_ONE), output, Clamp::Saturate),
+        }
+    }
+
+    pub fn map(&self, x: i32) -> i32 {
+        let t = self.normalize.map(x);
+        let y = match self.curve {
+            Curve::Linear => t,
+            Curve::Exp => curve(&EXP_CURVE, t),
+            Curve::Log => curve(&LOG_CURVE, t),
+        };
+        self.scale.map(y)
+    }
+}
+
+fn curve(points: &[i32; 17], t: i32) -> i32 {
+    let step = Q16_ONE / 16;
+    let i = cmp::min(t / step, 15) as usize;
+    let x0 = i as i32 * step;
+
+    LinearMap::new((x0, x0 + step), (points[i], points[i + 1]), Clamp::Saturate).map(t)
+}
+
+// x * num / den in Q16.16, rounding half away from zero. The operands are
+// differences of two i32, so the product of their magnitudes fits a u64. An
+// extrapolated quotient past QUOTIENT_MAX is clamped there, far outside the
+// i32 results.
+fn scale(x: i64, num: i64, den: i64) -> i64 {
+    let n = x.unsigned_abs() * num.unsigned_abs();
+    let d = den.unsigned_abs();
+
+    let q = n / d;
+    let y = if q >= QUOTIENT_MAX {
+        QUOTIENT_MAX << 16
+    } else {
+        (q << 16) + (((n % d) << 16) + d / 2) / d
+    };
+
+    if (x < 0) != ((num < 0) != (den < 0)) {
+        -(y as i64)
+    } else {
+        y as i64
+    }
+}
+
+// Division rounding half away from zero
+fn div_round(n: i64, d: i64) -> i64 {
+    if (n < 0) == (d < 0) {
+        (n + d / 2) / d
+    } else {
+        (n - d / 2) / d
+    }
+}
+
+fn saturate(x: i64) -> i32 {
+    x.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
@@ -0,0 +1,165 @@
+use core::cmp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InputFault {
+    // Stuck at either rail, open or shorted wiper
+    Rail,
+    // Moved further than a hand can turn the knob
+    Step,
+    // Samples of one group disagree, intermittent contact
+    Noise,
+}
+
+// Checks on the raw samples of a channel, taken in groups of the filter's
+// oversample count. All disabled by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Plausibility {
+    // Groups averaging within this many counts of either rail count as
+    // stuck, 0 disables. Needs pots with end resistors that never reach the
+    // rails.
+    pub rail_margin: u16,
+    // Consecutive groups at a rail before it is a fault
+    pub rail_groups: u16,
+    // Largest change between the averages of consecutive groups, 0 disables
+    pub max_step: u16,
+    // Largest spread between the samples of one group, 0 disables
+    pub max_spread: u16,
+}
+
+// The setpoint pots, in groups of 8 samples at 200 Hz. The pots have end
+// resistors keeping the wiper at least 1% of the scale off either rail, end
+// stops included, so more than a second within 16 counts of a rail is an open
+// or shorted wiper. A quarter turn between groups, or a noisy group, is
+// implausible too.
+pub const POTS: Plausibility = Plausibility {
+    rail_margin: 16,
+    rail_groups: 25,
+    max_step: 1024,
+    max_spread: 256,
+};
+
+// Latches the first fault until cleared
+pub struct Checker {
+    config: Plausibility,
+    group_len: u8,
+    max: u16,
+    len: u8,
+    sum: u32,
+    lo: u16,
+    hi: u16,
+    prev: Option<u16>,
+    rail_count: u16,
+    fault: Option<InputFault>,
+}
+
+impl Checker {
+    // Groups of group_len samples on a channel reading up to max
+    pub fn new(group_len: u8, max: u16) -> Self {
+        Checker {
+            config: Plausibility::default(),
+            group_len: cmp::max(group_len, 1),
+            max,
+            len: 0,
+            sum: 0,
+            lo: u16::MAX,
+            hi: 0,
+            prev: None,
+            rail_count: 0,
+            fault: None,
+        }
+    }
+
+    pub fn set_config(&mut self, config: Plausibility) {
+        self.config = config;
+        self.clear();
+    }
+
+    pub fn config(&self) -> Plausibility {
+        self.config
+    }
+
+    pub fn fault(&self) -> Option<InputFault> {
+        self.fault
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.sum = 0;
+        self.lo = u16::MAX;
+        self.hi = 0;
+        self.prev = None;
+        self.rail_count = 0;
+        self.fault = None;
+    }
+
+    pub fn update(&mut self, sample: u16) -> Option<InputFault> {
+        if self.fault.is_some() {
+            return self.fault;
+        }
+
+        self.len += 1;
+        self.sum += sample as u32;
+        self.lo = cmp::min(self.lo, sample);
+        self.hi = cmp::max(self.hi, sample);
+
+        if self.len < self.group_len {
+            return None;
+        }
+
+        let mean = (self.sum / self.len as u32) as u16;
+        let spread = self.hi - self.lo;
+        self.len = 0;
+        self.sum = 0;
+        self.lo = u16::MAX;
+        self.hi = 0;
+
+        self.fault = self
+            .check_rail(mean)
+            .or_else(|| self.check_step(mean))
+            .or_else(|| self.check_spread(spread));
+        self.prev = Some(mean);
+
+        self.fault
+    }
+
+    fn check_rail(&mut self, mean: u16) -> Option<InputFault> {
+        let margin = self.config.rail_margin;
+        if margin == 0 {
+            return None;
+        }
+
+        if mean <= margin || mean >= self.max.saturating_sub(margin) {
+            self.rail_count = self.rail_count.saturating_add(1);
+        } else {
+            self.rail_count = 0;
+        }
+
+        if self.rail_count > self.config.rail_groups {
+            Some(InputFault::Rail)
+        } else {
+            None
+        }
+    }
+
+    fn check_step(&self, mean: u16) -> Option<InputFault> {
+        let max_step = self.config.max_step;
+        match self.prev {
+            Some(prev) if max_step != 0 && abs_diff(prev, mean) > max_step => {
+                Some(InputFault::Step)
+            }
+            _ => None,
+        }
+    }
+
+    fn check_spread(&self, spread: u16) -> Option<InputFault> {
+        if self.config.max_spread != 0 && spread > self.config.max_spread {
+            Some(InputFault::Noise)
+        } else {
+            None
+        }
+    }
+}
+
+fn abs_diff(a: u16, b: u16) -> u16 {
+    cmp::max(a, b) - cmp::min(a, b)
+}
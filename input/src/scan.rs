@@ -0,0 +1,99 @@
+use crate::analog::{AnalogPin, Resolution};
+use core::cmp;
+
+// The circular buffer a DMA driven ADC scan converts into, each channel of
+// the regular sequence in turn
+pub trait ScanBuffer {
+    // Number of channels in the regular sequence
+    fn sequence_len(&self) -> usize;
+
+    // Length of the buffer, in samples
+    fn buffer_len(&self) -> usize;
+
+    // Index in the buffer that the DMA writes next
+    fn write_index(&self) -> usize;
+
+    fn read(&self, index: usize) -> u16;
+
+    // Reads the sample at `index` and replaces it with `value`
+    fn replace(&mut self, index: usize, value: u16) -> u16;
+}
+
+// Written over each sample of a scan once read, the ADC can't convert it
+const SCAN_READ: u16 = 0xFFFF;
+
+// Channel `index` of the regular sequence of a DMA driven ADC scan, reads
+// every sample converted since the previous update. Samples are marked as
+// they are read, so a marked sample written again means the DMA lapped the
+// cursor: the unread samples are lost and reading resumes at the newest scan.
+pub struct ScanPin {
+    index: usize,
+    cursor: Option<usize>,
+    resolution: Resolution,
+    overruns: u32,
+}
+
+impl ScanPin {
+    pub fn new(index: usize, resolution: Resolution) -> Self {
+        ScanPin {
+            index,
+            cursor: None,
+            resolution,
+            overruns: 0,
+        }
+    }
+}
+
+impl<S> AnalogPin<S> for ScanPin
+where
+    S: ScanBuffer,
+{
+    fn pending(&mut self, scan: &mut S) -> usize {
+        let len = scan.buffer_len();
+        let seq = scan.sequence_len();
+        let write = scan.write_index();
+        let before = |cursor| (cursor + len - seq) % len;
+
+        let lapped = match self.cursor {
+            Some(cursor) => scan.read(before(cursor)) != SCAN_READ,
+            None => false,
+        };
+        if lapped {
+            self.overruns = self.overruns.wrapping_add(1);
+        }
+
+        // Start from the last complete sequence
+        let cursor = match self.cursor {
+            Some(cursor) if !lapped => cursor,
+            _ => {
+                let cursor = ((write / seq) * seq + len - seq + self.index) % len;
+                scan.replace(before(cursor), SCAN_READ);
+                self.cursor = Some(cursor);
+                cursor
+            }
+        };
+
+        // Once read up to the write index, the cursor of a channel later in
+        // the sequence sits ahead of it until the DMA gets there
+        let written = (write + len - cursor) % len;
+        if written > len - seq {
+            return 0;
+        }
+        written.div_ceil(seq)
+    }
+
+    fn read(&mut self, scan: &mut S) -> u16 {
+        let cursor = self.cursor.unwrap_or(self.index);
+        self.cursor = Some((cursor + scan.sequence_len()) % scan.buffer_len());
+
+        cmp::min(scan.replace(cursor, SCAN_READ), self.resolution.max())
+    }
+
+    fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    fn overruns(&self) -> u32 {
+        self.overruns
+    }
+}
@@ -0,0 +1,69 @@
+use embedded_hal::adc::{Channel, OneShot};
+use lmc_input::analog::{AdcPin, AnalogPin, Resolution};
+use std::collections::VecDeque;
+
+struct MockAdc(VecDeque<nb::Result<u16, ()>>);
+
+struct Pin;
+
+impl Channel<MockAdc> for Pin {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        0
+    }
+}
+
+impl OneShot<MockAdc, u16, Pin> for MockAdc {
+    type Error = ();
+
+    fn read(&mut self, _pin: &mut Pin) -> nb::Result<u16, ()> {
+        self.0.pop_front().unwrap_or(Err(nb::Error::WouldBlock))
+    }
+}
+
+fn errors(pin: &AdcPin<MockAdc, Pin>) -> u32 {
+    AnalogPin::<MockAdc>::errors(pin)
+}
+
+#[test]
+fn resolution() {
+    assert_eq!(Resolution::bits(12).max(), 4095);
+    assert_eq!(Resolution::bits(7).max(), 127);
+    assert_eq!(Resolution::bits(0).max(), 1);
+    assert_eq!(Resolution::bits(20).max(), 65535);
+}
+
+#[test]
+fn conversions_are_clamped_to_the_resolution() {
+    let mut adc = MockAdc(vec![Ok(100), Ok(5000)].into());
+    let mut pin = AdcPin::new(Pin, Resolution::bits(12));
+
+    assert_eq!(pin.pending(&mut adc), 1);
+    assert_eq!(pin.read(&mut adc), 100);
+    assert_eq!(pin.pending(&mut adc), 1);
+    assert_eq!(pin.read(&mut adc), 4095);
+}
+
+#[test]
+fn conversion_in_progress_is_not_pending() {
+    let mut adc = MockAdc(vec![Err(nb::Error::WouldBlock), Ok(7)].into());
+    let mut pin = AdcPin::new(Pin, Resolution::bits(12));
+
+    assert_eq!(pin.pending(&mut adc), 0);
+    assert_eq!(pin.pending(&mut adc), 1);
+    assert_eq!(pin.read(&mut adc), 7);
+    assert_eq!(errors(&pin), 0);
+}
+
+#[test]
+fn errors_are_returned_and_counted() {
+    let mut adc = MockAdc(vec![Err(nb::Error::Other(())), Err(nb::Error::Other(()))].into());
+    let mut pin = AdcPin::new(Pin, Resolution::bits(12));
+
+    assert_eq!(pin.sample(&mut adc), Err(nb::Error::Other(())));
+    assert_eq!(errors(&pin), 0);
+
+    assert_eq!(pin.pending(&mut adc), 0);
+    assert_eq!(errors(&pin), 1);
+}
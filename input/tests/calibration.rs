@@ -0,0 +1,101 @@
+use lmc_input::calibration::{Calibration, Calibrator, Step};
+
+const FULL: u16 = 4095;
+
+fn apply(cal: &Calibration, raws: &[u16]) -> Vec<u16> {
+    raws.iter().map(|&raw| cal.apply(raw, FULL)).collect()
+}
+
+#[test]
+fn full_scale_is_the_identity() {
+    let cal = Calibration::full_scale(FULL);
+    assert_eq!(
+        apply(&cal, &[0, 1, 2048, 4094, FULL]),
+        [0, 1, 2048, 4094, FULL]
+    );
+}
+
+#[test]
+fn dead_zones_read_as_the_end_stops() {
+    let cal = Calibration {
+        min: 100,
+        max: 3900,
+        dead_bottom: 50,
+        dead_top: 50,
+        reverse: false,
+    };
+    assert_eq!(apply(&cal, &[0, 100, 150, 151]), [0, 0, 0, 1]);
+    assert_eq!(
+        apply(&cal, &[3849, 3850, 3900, FULL]),
+        [4093, FULL, FULL, FULL]
+    );
+    assert_eq!(apply(&cal, &[2000]), [2047]);
+}
+
+#[test]
+fn reverse_flips_the_output() {
+    let cal = Calibration {
+        min: 100,
+        max: 3900,
+        dead_bottom: 50,
+        dead_top: 200,
+        reverse: true,
+    };
+
+    // The bottom dead zone is still at the low raw readings, now full scale
+    assert_eq!(apply(&cal, &[0, 150, 151]), [FULL, FULL, 4094]);
+    assert_eq!(apply(&cal, &[3699, 3700, FULL]), [2, 0, 0]);
+    assert_eq!(apply(&cal, &[1925]), [2048]);
+}
+
+#[test]
+fn dead_zones_that_overlap_read_as_the_bottom() {
+    let cal = Calibration {
+        min: 1000,
+        max: 1100,
+        dead_bottom: 60,
+        dead_top: 60,
+        reverse: false,
+    };
+    assert_eq!(apply(&cal, &[0, 1050, FULL]), [0, 0, 0]);
+
+    let cal = Calibration {
+        reverse: true,
+        ..cal
+    };
+    assert_eq!(apply(&cal, &[0, 1050, FULL]), [FULL, FULL, FULL]);
+}
+
+#[test]
+fn calibrator_captures_both_end_stops() {
+    let mut calibrator = Calibrator::new();
+    assert_eq!(calibrator.step(), Step::Min);
+    assert_eq!(calibrator.capture(200), None);
+    assert_eq!(calibrator.step(), Step::Max);
+
+    // 2% of the span at each end
+    let cal = calibrator.capture(3800).unwrap();
+    assert_eq!(
+        cal,
+        Calibration {
+            min: 200,
+            max: 3800,
+            dead_bottom: 72,
+            dead_top: 72,
+            reverse: false,
+        }
+    );
+    assert_eq!(apply(&cal, &[200, 272, 3728, 3800]), [0, 0, FULL, FULL]);
+
+    // Ready for the next pot
+    assert_eq!(calibrator.step(), Step::Min);
+}
+
+#[test]
+fn calibrator_reverses_a_pot_wired_backwards() {
+    let mut calibrator = Calibrator::new();
+    calibrator.capture(3800);
+    let cal = calibrator.capture(200).unwrap();
+    assert_eq!((cal.min, cal.max, cal.reverse), (200, 3800, true));
+    assert_eq!(apply(&cal, &[3800, 200]), [0, FULL]);
+}
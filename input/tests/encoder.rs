@@ -0,0 +1,177 @@
+use embedded_hal::{Direction, Qei};
+use lmc_input::analog::Resolution;
+use lmc_input::encoder::{Encoder, Error, Setpoint};
+use lmc_types::time::Instant;
+use std::cell::Cell;
+use std::rc::Rc;
+
+// Timer counter shared with the test, which turns the knob
+struct MockQei(Rc<Cell<u16>>);
+
+impl Qei for MockQei {
+    type Count = u16;
+
+    fn count(&self) -> u16 {
+        self.0.get()
+    }
+
+    fn direction(&self) -> Direction {
+        Direction::Upcounting
+    }
+}
+
+fn ms(ms: u32) -> Instant {
+    Instant::from_millis(ms)
+}
+
+// Turns by the given counts, 4 to a detent
+fn turn(counter: &Cell<u16>, counts: i16) {
+    counter.set(counter.get().wrapping_add(counts as u16));
+}
+
+fn encoder(start: u16) -> (Encoder<'static, MockQei>, Rc<Cell<u16>>) {
+    let counter = Rc::new(Cell::new(start));
+    (Encoder::new(MockQei(counter.clone())), counter)
+}
+
+fn setpoint(value: u16, step: u16) -> &'static Setpoint {
+    Box::leak(Box::new(Setpoint::new(value, step, Resolution::bits(12))))
+}
+
+#[test]
+fn counter_wraps_forward() {
+    let (mut encoder, counter) = encoder(65534);
+    let setpoint = setpoint(100, 1);
+    encoder.add_setpoint(setpoint).unwrap();
+
+    turn(&counter, 4);
+    assert_eq!(counter.get(), 2);
+    assert_eq!(encoder.update(ms(0)), 1);
+    assert_eq!(setpoint.value(), 101);
+}
+
+#[test]
+fn counter_wraps_backward() {
+    let (mut encoder, counter) = encoder(2);
+    let setpoint = setpoint(100, 1);
+    encoder.add_setpoint(setpoint).unwrap();
+
+    turn(&counter, -8);
+    assert_eq!(counter.get(), 65530);
+    assert_eq!(encoder.update(ms(0)), -2);
+    assert_eq!(setpoint.value(), 98);
+}
+
+#[test]
+fn partial_detents_carry_over() {
+    let (mut encoder, counter) = encoder(0);
+    let setpoint = setpoint(100, 1);
+    encoder.add_setpoint(setpoint).unwrap();
+
+    turn(&counter, 3);
+    assert_eq!(encoder.update(ms(0)), 0);
+    turn(&counter, 3);
+    assert_eq!(encoder.update(ms(1000)), 1);
+    turn(&counter, -3);
+    assert_eq!(encoder.update(ms(2000)), 0);
+    assert_eq!(setpoint.value(), 101);
+}
+
+#[test]
+fn acceleration_thresholds() {
+    let (mut encoder, counter) = encoder(0);
+    let setpoint = setpoint(1000, 1);
+    encoder.add_setpoint(setpoint).unwrap();
+
+    // The first detent has nothing to compare with
+    let mut now = 0;
+    let mut steps = |interval, expected| {
+        now += interval;
+        let before = setpoint.value();
+        turn(&counter, 4);
+        assert_eq!(encoder.update(ms(now)), 1);
+        assert_eq!(setpoint.value() - before, expected, "{} ms", interval);
+    };
+    steps(0, 1);
+    steps(100, 1);
+    steps(60, 1);
+    steps(59, 3);
+    steps(25, 3);
+    steps(24, 8);
+    steps(1, 8);
+    steps(500, 1);
+}
+
+#[test]
+fn acceleration_spreads_over_detents_in_one_update() {
+    let (mut encoder, counter) = encoder(0);
+    let setpoint = setpoint(1000, 1);
+    encoder.add_setpoint(setpoint).unwrap();
+    turn(&counter, 4);
+    encoder.update(ms(0));
+
+    // Two detents in 40 ms, 20 ms each
+    turn(&counter, -8);
+    assert_eq!(encoder.update(ms(40)), -2);
+    assert_eq!(setpoint.value(), 1001 - 16);
+}
+
+#[test]
+fn setpoint_clamps_at_the_limits() {
+    let (mut encoder, counter) = encoder(0);
+    let setpoint = setpoint(4090, 16);
+    encoder.add_setpoint(setpoint).unwrap();
+
+    turn(&counter, 4);
+    encoder.update(ms(0));
+    assert_eq!(setpoint.value(), 4095);
+
+    turn(&counter, -4);
+    encoder.update(ms(1000));
+    assert_eq!(setpoint.value(), 4095 - 16);
+
+    // A fast spin down past 0
+    for i in 0..40 {
+        turn(&counter, -4);
+        encoder.update(ms(2000 + i * 10));
+    }
+    assert_eq!(setpoint.value(), 0);
+
+    assert_eq!(Setpoint::new(5000, 1, Resolution::bits(12)).value(), 4095);
+}
+
+#[test]
+fn select_next_moves_between_setpoints() {
+    let (mut encoder, counter) = encoder(0);
+    let pwm = setpoint(0, 16);
+    let freq = setpoint(0, 1);
+    encoder.add_setpoint(pwm).unwrap();
+    encoder.add_setpoint(freq).unwrap();
+
+    turn(&counter, 4);
+    encoder.update(ms(0));
+
+    // A partial detent is dropped on selecting the next setpoint
+    turn(&counter, 2);
+    encoder.update(ms(1000));
+    encoder.select_next();
+    turn(&counter, 2);
+    encoder.update(ms(2000));
+    turn(&counter, 4);
+    encoder.update(ms(3000));
+    assert_eq!((pwm.value(), freq.value()), (16, 1));
+
+    encoder.select_next();
+    turn(&counter, 4);
+    encoder.update(ms(4000));
+    assert_eq!((pwm.value(), freq.value()), (32, 1));
+}
+
+#[test]
+fn setpoints_are_limited() {
+    let (mut encoder, _) = encoder(0);
+    for _ in 0..4 {
+        encoder.add_setpoint(setpoint(0, 1)).unwrap();
+    }
+    assert_eq!(encoder.add_setpoint(setpoint(0, 1)), Err(Error::Full));
+}
@@ -0,0 +1,159 @@
+use lmc_input::filter::{Filter, FilterConfig};
+
+const MAX: u16 = 4095;
+
+const UNFILTERED: FilterConfig = FilterConfig {
+    oversample: 1,
+    median: 1,
+    iir_shift: 0,
+    hysteresis: 0,
+};
+
+// Outputs for the samples, one sample per output
+fn outputs(config: FilterConfig, samples: &[u16]) -> Vec<u16> {
+    let mut filter = Filter::new(config, MAX);
+    samples.iter().map(|&s| filter.update(s).unwrap()).collect()
+}
+
+#[test]
+fn unfiltered_passes_through() {
+    let samples = [0, 4095, 17, 2048, 2049];
+    assert_eq!(outputs(UNFILTERED, &samples), samples);
+}
+
+#[test]
+fn oversample_averages_groups() {
+    let mut filter = Filter::new(
+        FilterConfig {
+            oversample: 4,
+            ..UNFILTERED
+        },
+        MAX,
+    );
+    let updates: Vec<_> = [10, 20, 30, 41, 100, 100, 100, 100]
+        .iter()
+        .map(|&s| filter.update(s))
+        .collect();
+    assert_eq!(
+        updates,
+        [None, None, None, Some(25), None, None, None, Some(100)]
+    );
+
+    // 0 is the same as 1
+    let config = FilterConfig {
+        oversample: 0,
+        ..UNFILTERED
+    };
+    assert_eq!(outputs(config, &[5, 6]), [5, 6]);
+}
+
+#[test]
+fn median_rejects_spikes() {
+    let config = FilterConfig {
+        median: 3,
+        ..UNFILTERED
+    };
+    assert_eq!(
+        outputs(config, &[100, 100, 4000, 100, 0, 100, 100]),
+        [100, 100, 100, 100, 100, 100, 100]
+    );
+
+    // Two spikes in a row get through a window of 3, not one of 5
+    let samples = [100, 100, 100, 4000, 4000, 100, 100];
+    assert_eq!(
+        outputs(config, &samples),
+        [100, 100, 100, 100, 4000, 4000, 100]
+    );
+    let config = FilterConfig {
+        median: 5,
+        ..UNFILTERED
+    };
+    assert_eq!(outputs(config, &samples), [100; 7]);
+}
+
+#[test]
+fn median_window_is_odd_and_bounded() {
+    // 4 rounds up to 5, and anything over 5 is 5
+    for &median in [4, 5, 200].iter() {
+        let config = FilterConfig {
+            median,
+            ..UNFILTERED
+        };
+        let out = outputs(config, &[0, 0, 0, 9, 9, 9, 9]);
+        assert_eq!(out, [0, 0, 0, 0, 0, 9, 9], "median {}", median);
+    }
+}
+
+#[test]
+fn iir_steps_towards_the_input() {
+    let config = FilterConfig {
+        iir_shift: 2,
+        ..UNFILTERED
+    };
+
+    // Starts at the first sample, then a quarter of the way each update
+    let out = outputs(config, &[0, 1000, 1000, 1000]);
+    assert_eq!(out, [0, 250, 438, 578]);
+
+    // Settles on the input rather than just short of it, both ways
+    let mut filter = Filter::new(config, MAX);
+    filter.update(0);
+    let up = (0..64).map(|_| filter.update(1000).unwrap()).last();
+    assert_eq!(up, Some(1000));
+    let down = (0..64).map(|_| filter.update(3).unwrap()).last();
+    assert_eq!(down, Some(3));
+
+    // The full scale doesn't overflow
+    let config = FilterConfig {
+        iir_shift: 15,
+        ..UNFILTERED
+    };
+    assert_eq!(outputs(config, &[MAX, MAX, 0])[2], MAX);
+}
+
+#[test]
+fn hysteresis_holds_within_the_band() {
+    let config = FilterConfig {
+        hysteresis: 8,
+        ..UNFILTERED
+    };
+    assert_eq!(
+        outputs(config, &[2000, 2005, 2008, 2009, 2001, 2000, 1992, 1991]),
+        [2000, 2000, 2000, 2009, 2009, 2000, 2000, 1991]
+    );
+}
+
+#[test]
+fn hysteresis_lets_the_ends_through() {
+    let config = FilterConfig {
+        hysteresis: 8,
+        ..UNFILTERED
+    };
+
+    // Within half a band of either end reads as the end
+    assert_eq!(outputs(config, &[6, 4]), [6, 0]);
+    assert_eq!(outputs(config, &[6, 5]), [6, 6]);
+    assert_eq!(outputs(config, &[4089, 4091]), [4089, MAX]);
+    assert_eq!(outputs(config, &[4089, 4090]), [4089, 4089]);
+
+    // Straight across the whole scale
+    assert_eq!(outputs(config, &[MAX, 0]), [MAX, 0]);
+}
+
+#[test]
+fn defaults_ride_out_a_glitch() {
+    // A whole oversampled group at the rail, then a small wobble
+    let mut filter = Filter::new(FilterConfig::default(), MAX);
+    let mut out = Vec::new();
+    for group in 0..12 {
+        let sample = match group {
+            5 => MAX,
+            8..=11 => 2052,
+            _ => 2048,
+        };
+        for _ in 0..8 {
+            out.extend(filter.update(sample));
+        }
+    }
+    assert_eq!(out, [2048; 12]);
+}
@@ -0,0 +1,219 @@
+use lmc_input::button::{Button, ButtonSet};
+use lmc_input::gesture::{Gesture, Gestures};
+use lmc_types::time::Instant;
+
+const HOLD_MS: u32 = 1000;
+const WINDOW_MS: u32 = 400;
+
+fn set(btns: &[u8]) -> ButtonSet {
+    btns.iter()
+        .fold(ButtonSet::empty(), |set, &i| set.with(Button::new(i)))
+}
+
+// Runs the buttons held at each time (ms), returns the actions fired
+fn run(gestures: &mut Gestures<char>, steps: &[(u32, &[u8])]) -> Vec<(u32, char)> {
+    steps
+        .iter()
+        .filter_map(|&(ms, held)| {
+            gestures
+                .update(Instant::from_millis(ms), set(held))
+                .map(|action| (ms, action))
+        })
+        .collect()
+}
+
+fn chord() -> Gestures<char> {
+    let mut gestures = Gestures::new();
+    gestures
+        .add(Gesture::Chord(set(&[0, 1]), HOLD_MS), 'c')
+        .unwrap();
+    gestures
+}
+
+fn sequence() -> Gestures<char> {
+    let mut gestures = Gestures::new();
+    gestures
+        .add(Gesture::Sequence(Button::new(0), 3, WINDOW_MS), 's')
+        .unwrap();
+    gestures
+}
+
+#[test]
+fn chord_fires_after_the_hold() {
+    let steps: &[(u32, &[u8])] = &[(0, &[0]), (100, &[0, 1]), (1099, &[0, 1]), (1100, &[0, 1])];
+    assert_eq!(run(&mut chord(), steps), [(1100, 'c')]);
+}
+
+#[test]
+fn chord_fires_once_per_hold() {
+    let steps: &[(u32, &[u8])] = &[
+        (0, &[0, 1]),
+        (1000, &[0, 1]),
+        (2000, &[0, 1]),
+        (5000, &[0, 1]),
+        (5100, &[]),
+        (5200, &[0, 1]),
+        (6200, &[0, 1]),
+    ];
+    assert_eq!(run(&mut chord(), steps), [(1000, 'c'), (6200, 'c')]);
+}
+
+#[test]
+fn chord_restarts_when_the_buttons_change() {
+    // Letting go of one button, or adding another, starts the hold over
+    let steps: &[(u32, &[u8])] = &[
+        (0, &[0, 1]),
+        (900, &[0]),
+        (950, &[0, 1]),
+        (1500, &[0, 1, 2]),
+        (1600, &[0, 1]),
+        (2599, &[0, 1]),
+        (2600, &[0, 1]),
+    ];
+    assert_eq!(run(&mut chord(), steps), [(2600, 'c')]);
+}
+
+#[test]
+fn sequence_counts_presses_within_the_window() {
+    let steps: &[(u32, &[u8])] = &[
+        (0, &[0]),
+        (100, &[]),
+        (400, &[0]),
+        (500, &[]),
+        (800, &[0]),
+        (900, &[]),
+    ];
+    assert_eq!(run(&mut sequence(), steps), [(800, 's')]);
+}
+
+#[test]
+fn sequence_starts_over_after_a_slow_press() {
+    let steps: &[(u32, &[u8])] = &[
+        (0, &[0]),
+        (100, &[]),
+        (400, &[0]),
+        (500, &[]),
+        (801, &[0]),
+        (900, &[]),
+        (1000, &[0]),
+        (1100, &[]),
+        (1200, &[0]),
+    ];
+    assert_eq!(run(&mut sequence(), steps), [(1200, 's')]);
+}
+
+#[test]
+fn sequence_ignores_holding_the_button() {
+    // Only the edges count, a held button is one press
+    let steps: &[(u32, &[u8])] = &[(0, &[0]), (100, &[0]), (200, &[0]), (300, &[0])];
+    assert!(run(&mut sequence(), steps).is_empty());
+}
+
+#[test]
+fn first_gesture_completed_wins() {
+    let mut gestures = chord();
+    gestures
+        .add(Gesture::Chord(set(&[0, 1]), HOLD_MS), 'd')
+        .unwrap();
+    let steps: &[(u32, &[u8])] = &[(0, &[0, 1]), (1000, &[0, 1])];
+    assert_eq!(run(&mut gestures, steps), [(1000, 'c')]);
+}
+
+#[test]
+fn full_after_eight() {
+    let mut gestures = Gestures::new();
+    for i in 0..8 {
+        assert!(gestures
+            .add(Gesture::Sequence(Button::new(0), i, WINDOW_MS), i)
+            .is_ok());
+    }
+    assert!(gestures
+        .add(Gesture::Sequence(Button::new(0), 1, WINDOW_MS), 8)
+        .is_err());
+}
+
+fn presses() -> Gestures<char> {
+    let mut gestures = Gestures::new();
+    gestures
+        .add(Gesture::Press(Button::new(0), WINDOW_MS), 'a')
+        .unwrap();
+    gestures
+        .add(Gesture::Press(Button::new(1), WINDOW_MS), 'b')
+        .unwrap();
+    gestures
+        .add(Gesture::Chord(set(&[0, 1]), HOLD_MS), 'c')
+        .unwrap();
+    gestures
+}
+
+#[test]
+fn press_fires_on_release() {
+    let steps: &[(u32, &[u8])] = &[(0, &[0]), (100, &[0]), (150, &[]), (200, &[])];
+    assert_eq!(run(&mut presses(), steps), [(150, 'a')]);
+}
+
+#[test]
+fn press_fires_once_held_past_the_window() {
+    let steps: &[(u32, &[u8])] = &[
+        (0, &[1]),
+        (399, &[1]),
+        (400, &[1]),
+        (2000, &[1]),
+        (2100, &[]),
+        (2200, &[1]),
+        (2300, &[]),
+    ];
+    assert_eq!(run(&mut presses(), steps), [(400, 'b'), (2300, 'b')]);
+}
+
+#[test]
+fn press_that_starts_a_chord_does_nothing() {
+    // Either button first, and either released first
+    let steps: &[(u32, &[u8])] = &[
+        (0, &[1]),
+        (300, &[0, 1]),
+        (1300, &[0, 1]),
+        (1400, &[0]),
+        (1500, &[]),
+        (2000, &[0]),
+        (2100, &[0, 1]),
+        (2200, &[1]),
+        (3000, &[]),
+    ];
+    assert_eq!(run(&mut presses(), steps), [(1300, 'c')]);
+}
+
+#[test]
+fn press_fires_once_until_released() {
+    // Another button coming and going after the window doesn't fire the
+    // press again
+    let steps: &[(u32, &[u8])] = &[
+        (0, &[0]),
+        (500, &[0]),
+        (600, &[0, 1]),
+        (700, &[1]),
+        (800, &[]),
+    ];
+    assert_eq!(run(&mut presses(), steps), [(500, 'a')]);
+}
+
+#[test]
+fn solo_waits_out_the_window() {
+    let mut gestures = presses();
+    let solo = |gestures: &mut Gestures<char>, ms, held: &[u8]| {
+        let now = Instant::from_millis(ms);
+        gestures.update(now, set(held));
+        gestures.solo(now, Button::new(1), WINDOW_MS)
+    };
+
+    assert!(!solo(&mut gestures, 0, &[1]));
+    assert!(!solo(&mut gestures, 399, &[1]));
+    assert!(solo(&mut gestures, 400, &[1]));
+    assert!(!solo(&mut gestures, 500, &[0, 1]));
+
+    // Not again until everything is released
+    assert!(!solo(&mut gestures, 1000, &[1]));
+    assert!(!solo(&mut gestures, 1100, &[]));
+    assert!(!solo(&mut gestures, 1200, &[1]));
+    assert!(solo(&mut gestures, 1600, &[1]));
+}
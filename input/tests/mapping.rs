@@ -0,0 +1,108 @@
+use lmc_input::mapping::{Clamp, Curve, CurveMap, LinearMap, Table, Q16_ONE};
+
+fn maps(map: &LinearMap, xs: &[i32]) -> Vec<i32> {
+    xs.iter().map(|&x| map.map(x)).collect()
+}
+
+#[test]
+fn linear_saturates_outside_the_input() {
+    let map = LinearMap::new((0, 4095), (0, 100), Clamp::Saturate);
+    assert_eq!(maps(&map, &[-5, 0, 2047, 4095, 5000]), [0, 0, 50, 100, 100]);
+}
+
+#[test]
+fn linear_inverted_and_signed_ranges() {
+    let map = LinearMap::new((0, 100), (100, 0), Clamp::Saturate);
+    assert_eq!(maps(&map, &[0, 25, 100]), [100, 75, 0]);
+
+    let map = LinearMap::new((100, -100), (-1000, 1000), Clamp::Saturate);
+    assert_eq!(
+        maps(&map, &[100, 50, -100, -200]),
+        [-1000, -500, 1000, 1000]
+    );
+}
+
+#[test]
+fn linear_rounds_half_away_from_zero() {
+    let map = LinearMap::new((0, 2), (0, 1), Clamp::Saturate);
+    assert_eq!(map.map(1), 1);
+    let map = LinearMap::new((0, 2), (0, -1), Clamp::Saturate);
+    assert_eq!(map.map(1), -1);
+    let map = LinearMap::new((0, 3), (0, 1), Clamp::Saturate);
+    assert_eq!(maps(&map, &[1, 2]), [0, 1]);
+}
+
+#[test]
+fn linear_q16() {
+    let map = LinearMap::new((0, 4), (0, 1), Clamp::Saturate);
+    assert_eq!(map.map_q16(1), Q16_ONE / 4);
+    assert_eq!(map.map_q16(4), Q16_ONE);
+}
+
+#[test]
+fn linear_empty_input_range() {
+    let map = LinearMap::new((5, 5), (10, 20), Clamp::Extrapolate);
+    assert_eq!(maps(&map, &[i32::MIN, 5, i32::MAX]), [10, 10, 10]);
+}
+
+#[test]
+fn linear_extrapolates() {
+    let map = LinearMap::new((0, 10), (0, 100), Clamp::Extrapolate);
+    assert_eq!(maps(&map, &[-5, 20]), [-50, 200]);
+}
+
+#[test]
+fn linear_extremes_saturate() {
+    let full = (i32::MIN, i32::MAX);
+    let identity = LinearMap::new(full, full, Clamp::Extrapolate);
+    let xs = [i32::MIN, -1, 0, 1, i32::MAX];
+    assert_eq!(maps(&identity, &xs), xs);
+
+    let reversed = LinearMap::new(full, (i32::MAX, i32::MIN), Clamp::Saturate);
+    assert_eq!(maps(&reversed, &[i32::MIN, i32::MAX]), [i32::MAX, i32::MIN]);
+
+    // The steepest maps, far past the input range
+    let steep = LinearMap::new((0, 1), full, Clamp::Extrapolate);
+    assert_eq!(maps(&steep, &[0, 1]), [i32::MIN, i32::MAX]);
+    assert_eq!(
+        maps(&steep, &[2, i32::MAX, -1, i32::MIN]),
+        [i32::MAX, i32::MAX, i32::MIN, i32::MIN]
+    );
+    let steep = LinearMap::new((0, -1), full, Clamp::Extrapolate);
+    assert_eq!(
+        maps(&steep, &[-2, i32::MIN, 1, i32::MAX]),
+        [i32::MAX, i32::MAX, i32::MIN, i32::MIN]
+    );
+}
+
+#[test]
+fn linear_wraps() {
+    let map = LinearMap::new((0, 9), (0, 90), Clamp::Wrap);
+    assert_eq!(maps(&map, &[0, 9, 10, -1, 25]), [0, 90, 0, 90, 50]);
+}
+
+#[test]
+fn table_interpolates_and_saturates() {
+    let table = Table::new(&[(0, 0), (10, 100), (20, 100), (30, 0)]);
+    let ys: Vec<_> = [-5, 0, 5, 15, 25, 30, 35]
+        .iter()
+        .map(|&x| table.map(x))
+        .collect();
+    assert_eq!(ys, [0, 0, 50, 100, 50, 0, 0]);
+}
+
+#[test]
+fn curves_span_the_output() {
+    for &curve in [Curve::Linear, Curve::Exp, Curve::Log].iter() {
+        let map = CurveMap::new(curve, (0, 4095), (0, 100));
+        let ys: Vec<_> = (0..=4095).map(|x| map.map(x)).collect();
+        assert_eq!((ys[0], ys[4095]), (0, 100), "{:?}", curve);
+        assert!(ys.windows(2).all(|w| w[0] <= w[1]), "{:?}", curve);
+    }
+
+    // Exp is fine at the low end, log at the high end
+    let mid = |curve| CurveMap::new(curve, (0, 4095), (0, 100)).map(2048);
+    assert_eq!(mid(Curve::Linear), 50);
+    assert_eq!(mid(Curve::Exp), 11);
+    assert_eq!(mid(Curve::Log), 84);
+}
@@ -0,0 +1,133 @@
+use lmc_input::plausibility::{self, Checker, InputFault, Plausibility};
+use lmc_types::fault::FaultLatch;
+use lmc_types::status::Fault;
+
+const MAX: u16 = 4095;
+const GROUP: u8 = 4;
+
+fn checker(config: Plausibility) -> Checker {
+    let mut checker = Checker::new(GROUP, MAX);
+    checker.set_config(config);
+    checker
+}
+
+// Feeds whole groups of one sample each, returns the fault after each group
+fn groups(checker: &mut Checker, means: &[u16]) -> Vec<Option<InputFault>> {
+    means
+        .iter()
+        .map(|&mean| (0..GROUP).map(|_| checker.update(mean)).last().unwrap())
+        .collect()
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut checker = Checker::new(GROUP, MAX);
+    assert_eq!(checker.config(), Plausibility::default());
+    let faults = groups(&mut checker, &[0, MAX, 0, MAX, 0, 0, 0, 0]);
+    assert!(faults.iter().all(Option::is_none));
+}
+
+fn checker_with_rail() -> Checker {
+    checker(Plausibility {
+        rail_margin: 16,
+        rail_groups: 2,
+        ..Plausibility::default()
+    })
+}
+
+#[test]
+fn rail_after_the_groups() {
+    let mut checker = checker_with_rail();
+
+    // Within the margin of either rail, a third group in a row faults
+    let faults = groups(&mut checker, &[16, MAX - 16, 2048, 0, 5, 10]);
+    assert_eq!(
+        faults,
+        [None, None, None, None, None, Some(InputFault::Rail)]
+    );
+
+    // Just outside the margin never does
+    let mut checker = checker_with_rail();
+    let faults = groups(&mut checker, &[17, 17, 17, MAX - 17, MAX - 17]);
+    assert!(faults.iter().all(Option::is_none));
+}
+
+#[test]
+fn step_between_group_averages() {
+    let mut checker = checker(Plausibility {
+        max_step: 1000,
+        ..Plausibility::default()
+    });
+    let faults = groups(&mut checker, &[100, 1100, 2100, 3101]);
+    assert_eq!(faults, [None, None, None, Some(InputFault::Step)]);
+}
+
+#[test]
+fn noise_within_a_group() {
+    let mut checker = checker(Plausibility {
+        max_spread: 100,
+        ..Plausibility::default()
+    });
+
+    let group = |checker: &mut Checker, samples: [u16; 4]| {
+        samples.iter().map(|&s| checker.update(s)).last().unwrap()
+    };
+    assert_eq!(group(&mut checker, [1000, 1100, 1050, 1000]), None);
+    assert_eq!(
+        group(&mut checker, [1000, 1101, 1050, 1000]),
+        Some(InputFault::Noise)
+    );
+}
+
+#[test]
+fn fault_latches_until_cleared() {
+    let mut checker = checker(Plausibility {
+        max_step: 1000,
+        ..Plausibility::default()
+    });
+    groups(&mut checker, &[0, 2000]);
+    assert_eq!(checker.fault(), Some(InputFault::Step));
+    assert_eq!(checker.update(2000), Some(InputFault::Step));
+
+    // Clearing forgets the last average too, so the next group can't step
+    checker.clear();
+    assert_eq!(groups(&mut checker, &[0, 500]), [None, None]);
+}
+
+#[test]
+fn new_config_clears() {
+    let mut checker = checker(Plausibility {
+        max_step: 1000,
+        ..Plausibility::default()
+    });
+    groups(&mut checker, &[0, 2000]);
+    checker.set_config(Plausibility::default());
+    assert_eq!(checker.fault(), None);
+}
+
+#[test]
+fn pot_wiper_stuck_at_a_rail() {
+    // Groups of 8 at 200 Hz, 25 groups a second
+    for &rail in [0, MAX].iter() {
+        let mut checker = Checker::new(8, MAX);
+        checker.set_config(plausibility::POTS);
+        let mut latch = FaultLatch::new();
+
+        // Parked at an end stop, the end resistor keeps it off the rail
+        let end_stop = if rail == 0 { 41 } else { MAX - 41 };
+        for _ in 0..25 * 60 * 8 {
+            assert_eq!(checker.update(end_stop), None);
+        }
+
+        // Within the margin for a second is still fine
+        for _ in 0..25 * 8 {
+            assert_eq!(checker.update(rail), None);
+        }
+        let fault = (0..8).filter_map(|_| checker.update(rail)).last();
+        assert_eq!(fault, Some(InputFault::Rail));
+
+        // The firmware raises it on the LCM, which shuts down by default
+        assert!(latch.raise(Fault::Input));
+        assert_eq!(latch.fault(), Some(Fault::Input));
+    }
+}
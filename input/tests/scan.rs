@@ -0,0 +1,120 @@
+use lmc_input::analog::{AnalogPin, Resolution};
+use lmc_input::scan::{ScanBuffer, ScanPin};
+
+// Four channels, the last four scans kept
+const SEQUENCE: usize = 4;
+const DEPTH: usize = 4;
+
+struct MockScan {
+    buffer: Vec<u16>,
+    write: usize,
+    scans: u16,
+}
+
+impl MockScan {
+    fn new() -> Self {
+        MockScan {
+            buffer: vec![0; SEQUENCE * DEPTH],
+            write: 0,
+            scans: 0,
+        }
+    }
+
+    // Converts the next samples as the DMA would, channel c of scan n reads
+    // c * 1000 + n
+    fn convert(&mut self, samples: usize) {
+        for _ in 0..samples {
+            let channel = (self.write % SEQUENCE) as u16;
+            self.buffer[self.write] = channel * 1000 + self.scans;
+            self.write = (self.write + 1) % self.buffer.len();
+            if self.write.is_multiple_of(SEQUENCE) {
+                self.scans += 1;
+            }
+        }
+    }
+}
+
+impl ScanBuffer for MockScan {
+    fn sequence_len(&self) -> usize {
+        SEQUENCE
+    }
+
+    fn buffer_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn write_index(&self) -> usize {
+        self.write
+    }
+
+    fn read(&self, index: usize) -> u16 {
+        self.buffer[index]
+    }
+
+    fn replace(&mut self, index: usize, value: u16) -> u16 {
+        std::mem::replace(&mut self.buffer[index], value)
+    }
+}
+
+fn read_pending(pin: &mut ScanPin, scan: &mut MockScan) -> Vec<u16> {
+    (0..pin.pending(scan)).map(|_| pin.read(scan)).collect()
+}
+
+#[test]
+fn later_channel_read_twice_within_a_scan() {
+    let mut scan = MockScan::new();
+    let mut pin = ScanPin::new(2, Resolution::bits(12));
+    scan.convert(2 * SEQUENCE);
+
+    assert_eq!(read_pending(&mut pin, &mut scan), [2001]);
+    // Every loop pass until the next scan
+    for _ in 0..3 {
+        assert_eq!(pin.pending(&mut scan), 0);
+    }
+
+    // Partway into the next scan, before and after this channel
+    scan.convert(2);
+    assert_eq!(pin.pending(&mut scan), 0);
+    scan.convert(1);
+    assert_eq!(read_pending(&mut pin, &mut scan), [2002]);
+    assert_eq!(pin.pending(&mut scan), 0);
+    assert_eq!(AnalogPin::<MockScan>::overruns(&pin), 0);
+}
+
+#[test]
+fn every_channel_reads_each_scan_once() {
+    let mut scan = MockScan::new();
+    let mut pins: Vec<ScanPin> = (0..SEQUENCE)
+        .map(|i| ScanPin::new(i, Resolution::bits(12)))
+        .collect();
+    scan.convert(SEQUENCE);
+    for (c, pin) in pins.iter_mut().enumerate() {
+        assert_eq!(read_pending(pin, &mut scan), [c as u16 * 1000]);
+    }
+
+    // Around the buffer several times, a scan or two per update
+    for n in 1..20_u16 {
+        let scans = 1 + n % 2;
+        scan.convert(SEQUENCE * scans as usize);
+        let first = scan.scans - scans;
+        for (c, pin) in pins.iter_mut().enumerate() {
+            let expected: Vec<u16> = (first..scan.scans).map(|s| c as u16 * 1000 + s).collect();
+            assert_eq!(read_pending(pin, &mut scan), expected);
+            assert_eq!(pin.pending(&mut scan), 0);
+        }
+    }
+}
+
+#[test]
+fn lapped_cursor_resumes_at_the_newest_scan() {
+    let mut scan = MockScan::new();
+    let mut pin = ScanPin::new(1, Resolution::bits(12));
+    scan.convert(SEQUENCE);
+    assert_eq!(read_pending(&mut pin, &mut scan), [1000]);
+
+    // More scans than the buffer keeps
+    scan.convert(SEQUENCE * (DEPTH + 1));
+    assert_eq!(read_pending(&mut pin, &mut scan), [1005]);
+    assert_eq!(AnalogPin::<MockScan>::overruns(&pin), 1);
+    assert_eq!(pin.pending(&mut scan), 0);
+}
@@ -1,6 +1,7 @@
 /* Linker script for the STM32F103RBT6 */
 MEMORY
 {
-  FLASH : ORIGIN = 0x08000000, LENGTH = 128K
+  /* The last 1K page holds the settings, see src/storage.rs */
+  FLASH : ORIGIN = 0x08000000, LENGTH = 127K
   RAM : ORIGIN = 0x20000000, LENGTH = 20K
 }
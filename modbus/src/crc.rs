@@ -0,0 +1,16 @@
+// CRC-16/MODBUS, reflected polynomial 0xA001 from 0xFFFF, sent low byte
+// first
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 0x0001 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
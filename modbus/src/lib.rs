@@ -0,0 +1,10 @@
+// Modbus RTU slave for the plant PLCs on RS-485. Frames are split by the
+// silence between them, checked and served against the LCM register map.
+// Writes come out as protocol requests, so they take the same path as the
+// shell and the host tools.
+
+#![no_std]
+
+pub mod crc;
+pub mod rtu;
+pub mod slave;
@@ -0,0 +1,136 @@
+use heapless::consts::U256;
+use heapless::Vec;
+
+// Address, function, data and CRC
+pub type Adu = Vec<u8, U256>;
+
+// The shortest frame, address, function and CRC
+const MIN_ADU: usize = 4;
+
+// Without parity there are two stop bits, so a character is always 11 bits
+const CHAR_BITS: u64 = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Parity {
+    Even,
+    Odd,
+    None,
+}
+
+// The silences that split frames, in ticks of the clock the framer is fed.
+// Fixed above 19200 baud, as the spec recommends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timing {
+    // 1.5 characters, the most allowed within a frame
+    pub char_gap: u32,
+    // 3.5 characters, the least between frames
+    pub frame_gap: u32,
+}
+
+impl Timing {
+    pub fn new(baud: u32, clock_hz: u32) -> Self {
+        let ticks = |half_chars: u64, fixed_us: u64| {
+            let ticks = if baud > 19_200 {
+                clock_hz as u64 * fixed_us / 1_000_000
+            } else {
+                clock_hz as u64 * half_chars * CHAR_BITS / (2 * baud as u64)
+            };
+            ticks as u32
+        };
+
+        Timing {
+            char_gap: ticks(3, 750),
+            frame_gap: ticks(7, 1750),
+        }
+    }
+}
+
+// Splits received characters into frames by the time between them. A frame
+// ends after 3.5 characters of silence, a longer gap within one or a
+// character received with an error discards it.
+pub struct Framer {
+    timing: Timing,
+    frame: Adu,
+    // When the last character arrived, None between frames
+    last: Option<u32>,
+    broken: bool,
+    discarded: u32,
+}
+
+impl Framer {
+    pub fn new(timing: Timing) -> Self {
+        Framer {
+            timing,
+            frame: Vec::new(),
+            last: None,
+            broken: false,
+            discarded: 0,
+        }
+    }
+
+    // Drops a frame in progress
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+        self.frame = Vec::new();
+        self.last = None;
+        self.broken = false;
+    }
+
+    // Frames discarded for timing, errors, overflow or length, wrapping
+    pub fn discarded(&self) -> u32 {
+        self.discarded
+    }
+
+    // A received character, returns the frame it ends when it starts the
+    // next one
+    pub fn push(&mut self, byte: u8, now: u32) -> Option<Adu> {
+        let ended = self.arrive(now);
+        if self.frame.push(byte).is_err() {
+            self.broken = true;
+        }
+        ended
+    }
+
+    // A character received with a parity, framing or noise error
+    pub fn error(&mut self, now: u32) -> Option<Adu> {
+        let ended = self.arrive(now);
+        self.broken = true;
+        ended
+    }
+
+    // Call often, returns the frame once the line has been silent long
+    // enough
+    pub fn poll(&mut self, now: u32) -> Option<Adu> {
+        match self.last {
+            Some(last) if now.wrapping_sub(last) >= self.timing.frame_gap => self.end(),
+            _ => None,
+        }
+    }
+
+    fn arrive(&mut self, now: u32) -> Option<Adu> {
+        let ended = match self.last {
+            Some(last) if now.wrapping_sub(last) >= self.timing.frame_gap => self.end(),
+            Some(last) if now.wrapping_sub(last) > self.timing.char_gap => {
+                self.broken = true;
+                None
+            }
+            _ => None,
+        };
+        self.last = Some(now);
+        ended
+    }
+
+    fn end(&mut self) -> Option<Adu> {
+        let frame = core::mem::replace(&mut self.frame, Vec::new());
+        let broken = self.broken || frame.len() < MIN_ADU;
+        self.last = None;
+        self.broken = false;
+
+        if broken {
+            self.discarded = self.discarded.wrapping_add(1);
+            None
+        } else {
+            Some(frame)
+        }
+    }
+}
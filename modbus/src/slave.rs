@@ -0,0 +1,453 @@
+use crate::crc::crc16;
+use crate::rtu::Adu;
+use heapless::consts::U8;
+use heapless::Vec;
+use lmc_proto::packet::{ErrorCode, Request};
+use lmc_types::fault::FaultCounts;
+use lmc_types::status::{Fault, Freq, Health, State, Status, PWM_MAX};
+
+// Register map, zero based protocol addresses. Writing PWM OE on, by coil or
+// holding register, lasts 500 ms. A master holds it on by writing it again
+// within that time, and it lapses if the master goes quiet.
+//
+// Coils, read 0x01 and write 0x05, 0x0F
+//   0 relay, like B1 and B0, off also clears a fault
+//   1 PWM OE
+//
+// Holding registers, read 0x03 and write 0x06, 0x10. The PWM and strobe rate
+// hold until the pot moves, like the shell's.
+//
+// A write of several coils or registers is refused whole, nothing written,
+// when any value is out of range or the menu is open. The relay and OE are
+// refused during a fault only as they are carried out, in address order, so
+// the values before them stay written.
+//   0 PWM, 0-4095
+//   1 strobe rate (Hz), 0 continuous, reads back rounded
+//   2 relay, 0 or 1
+//   3 PWM OE, 0 or 1
+//   4 preset to apply, reads 0xFFFF
+//
+// Input registers, read 0x04
+//   0 state, 0 error, 1 off, 2 on
+//   1 fault, 0 none, 1 under voltage, 2 over temperature, 3 input
+//   2 PWM
+//   3 strobe rate (mHz) high word, 0 continuous
+//   4 strobe rate (mHz) low word
+//   5 PWM OE
+//   6 relay
+//   7 VDDA (mV), 0 unmeasured
+//   8 die temperature (C), signed, 0x8000 unmeasured
+//   9 display, 0 ok, 1 missing, 2 lost
+//  10 PWM input
+//  11 strobe rate input
+//  12 loop time (us), saturating
+//  13 under voltage faults
+//  14 over temperature faults
+//  15 input faults
+//  16 messages on the bus with a good CRC
+//  17 CRC errors
+//  18 exception responses
+
+pub const BROADCAST: u8 = 0;
+
+const READ_COILS: u8 = 0x01;
+const READ_HOLDING_REGISTERS: u8 = 0x03;
+const READ_INPUT_REGISTERS: u8 = 0x04;
+const WRITE_SINGLE_COIL: u8 = 0x05;
+const WRITE_SINGLE_REGISTER: u8 = 0x06;
+const WRITE_MULTIPLE_COILS: u8 = 0x0F;
+const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+
+const EXCEPTION: u8 = 0x80;
+
+const COIL_RELAY: u16 = 0;
+const COIL_OE: u16 = 1;
+const COILS: u16 = 2;
+
+const HOLDING_PWM: u16 = 0;
+const HOLDING_FREQ: u16 = 1;
+const HOLDING_RELAY: u16 = 2;
+const HOLDING_OE: u16 = 3;
+const HOLDING_PRESET: u16 = 4;
+const HOLDING_REGISTERS: u16 = 5;
+
+const INPUT_REGISTERS: u16 = 19;
+
+// Quantities a single request may cover, from the spec
+const MAX_READ_COILS: u16 = 2000;
+const MAX_READ_REGISTERS: u16 = 125;
+const MAX_WRITE_COILS: u16 = 1968;
+const MAX_WRITE_REGISTERS: u16 = 123;
+
+const COIL_ON: u16 = 0xFF00;
+const COIL_OFF: u16 = 0x0000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Exception {
+    IllegalFunction = 0x01,
+    IllegalDataAddress = 0x02,
+    IllegalDataValue = 0x03,
+    SlaveDeviceFailure = 0x04,
+    SlaveDeviceBusy = 0x06,
+}
+
+impl From<ErrorCode> for Exception {
+    fn from(code: ErrorCode) -> Self {
+        match code {
+            ErrorCode::Malformed | ErrorCode::OutOfRange => Exception::IllegalDataValue,
+            ErrorCode::Busy => Exception::SlaveDeviceBusy,
+            ErrorCode::Version | ErrorCode::Refused => Exception::SlaveDeviceFailure,
+        }
+    }
+}
+
+// What the input registers show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Diagnostics {
+    pub status: Status,
+    // Setpoint inputs, PWM then strobe rate
+    pub inputs: [u16; 2],
+    pub loop_us: u32,
+    pub faults: FaultCounts,
+}
+
+// Since startup, wrapping
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Counters {
+    pub messages: u16,
+    pub crc_errors: u16,
+    pub exceptions: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Received {
+    // Not for this slave, broken, or a broadcast that needs no reply
+    Nothing,
+    // Send it back
+    Reply(Adu),
+    // Carry out its requests in order, then reply with written()
+    Write(Write),
+}
+
+// Coils or holding registers written by the master, as requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Write {
+    address: u8,
+    function: u8,
+    start: u16,
+    // The value of a single write, the quantity of a multiple one
+    data: u16,
+    requests: Vec<Request, U8>,
+    next: usize,
+}
+
+impl Write {
+    // All of them, to check before carrying out any
+    pub fn requests(&self) -> &[Request] {
+        &self.requests
+    }
+
+    pub fn next_request(&mut self) -> Option<Request> {
+        let request = self.requests.get(self.next).copied();
+        self.next += 1;
+        request
+    }
+}
+
+pub struct Slave {
+    address: u8,
+    counters: Counters,
+}
+
+impl Slave {
+    pub fn new(address: u8) -> Self {
+        Slave {
+            address,
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    // 1-247
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    pub fn counters(&self) -> Counters {
+        self.counters
+    }
+
+    // A frame from the framer, CRC included
+    pub fn receive(&mut self, frame: &[u8], diagnostics: &Diagnostics) -> Received {
+        if frame.len() < 4 {
+            return Received::Nothing;
+        }
+        let (adu, crc) = frame.split_at(frame.len() - 2);
+        if crc16(adu) != u16::from_le_bytes([crc[0], crc[1]]) {
+            self.counters.crc_errors = self.counters.crc_errors.wrapping_add(1);
+            return Received::Nothing;
+        }
+        self.counters.messages = self.counters.messages.wrapping_add(1);
+
+        let address = adu[0];
+        if address != self.address && address != BROADCAST {
+            return Received::Nothing;
+        }
+        let function = adu[1];
+        let data = &adu[2..];
+
+        let received = match function {
+            READ_COILS | READ_HOLDING_REGISTERS | READ_INPUT_REGISTERS => {
+                // Only writes can be broadcast
+                if address == BROADCAST {
+                    return Received::Nothing;
+                }
+                self.read(function, data, diagnostics).map(Received::Reply)
+            }
+            WRITE_SINGLE_COIL
+            | WRITE_SINGLE_REGISTER
+            | WRITE_MULTIPLE_COILS
+            | WRITE_MULTIPLE_REGISTERS => write(address, function, data).map(Received::Write),
+            _ => Err(Exception::IllegalFunction),
+        };
+
+        match received {
+            Ok(received) => received,
+            Err(exception) => match self.exception(address, function, exception) {
+                Some(adu) => Received::Reply(adu),
+                None => Received::Nothing,
+            },
+        }
+    }
+
+    // Once the requests of a write were carried out, or at the first one
+    // refused. None for a broadcast.
+    pub fn written(&mut self, write: &Write, result: Result<(), ErrorCode>) -> Option<Adu> {
+        if let Err(code) = result {
+            return self.exception(write.address, write.function, code.into());
+        }
+        if write.address == BROADCAST {
+            return None;
+        }
+
+        // The echo of a single write, the range of a multiple one
+        let mut adu = start(write.address, write.function);
+        adu.extend_from_slice(&write.start.to_be_bytes()).ok();
+        adu.extend_from_slice(&write.data.to_be_bytes()).ok();
+        Some(seal(adu))
+    }
+
+    fn exception(&mut self, address: u8, function: u8, exception: Exception) -> Option<Adu> {
+        if address == BROADCAST {
+            return None;
+        }
+        self.counters.exceptions = self.counters.exceptions.wrapping_add(1);
+
+        let mut adu = start(address, function | EXCEPTION);
+        adu.push(exception as u8).ok();
+        Some(seal(adu))
+    }
+
+    fn read(&self, function: u8, data: &[u8], diagnostics: &Diagnostics) -> Result<Adu, Exception> {
+        let (first, quantity) = range(data)?;
+        let (max, count) = match function {
+            READ_COILS => (MAX_READ_COILS, COILS),
+            READ_HOLDING_REGISTERS => (MAX_READ_REGISTERS, HOLDING_REGISTERS),
+            _ => (MAX_READ_REGISTERS, INPUT_REGISTERS),
+        };
+        check(first, quantity, max, count)?;
+
+        let status = &diagnostics.status;
+        let mut adu = start(self.address, function);
+        if function == READ_COILS {
+            let coils =
+                (status.pwm_relay() as u8) << COIL_RELAY | (status.pwm_oe() as u8) << COIL_OE;
+            adu.push(1).ok();
+            adu.push((coils >> first) & ((1 << quantity) - 1)).ok();
+        } else {
+            adu.push(quantity as u8 * 2).ok();
+            for register in first..first + quantity {
+                let value = match function {
+                    READ_HOLDING_REGISTERS => holding_register(register, status),
+                    _ => self.input_register(register, diagnostics),
+                };
+                adu.extend_from_slice(&value.to_be_bytes()).ok();
+            }
+        }
+        Ok(seal(adu))
+    }
+
+    fn input_register(&self, register: u16, diagnostics: &Diagnostics) -> u16 {
+        let status = &diagnostics.status;
+        let supply = status.supply();
+        let mhz = match status.freq() {
+            Freq::Continuous => 0,
+            Freq::Periodic(mhz) => mhz,
+        };
+
+        match register {
+            0 => match status.state() {
+                State::Error => 0,
+                State::Off => 1,
+                State::On => 2,
+            },
+            1 => match status.fault() {
+                None => 0,
+                Some(Fault::UnderVoltage) => 1,
+                Some(Fault::OverTemperature) => 2,
+                Some(Fault::Input) => 3,
+            },
+            2 => status.pwm(),
+            3 => (mhz >> 16) as u16,
+            4 => mhz as u16,
+            5 => status.pwm_oe() as u16,
+            6 => status.pwm_relay() as u16,
+            7 => supply.map_or(0, |s| s.vdda_mv()),
+            8 => supply.map_or(i16::MIN, |s| s.temperature()) as u16,
+            9 => match status.display() {
+                Health::Ok => 0,
+                Health::Missing => 1,
+                Health::Lost => 2,
+            },
+            10 => diagnostics.inputs[0],
+            11 => diagnostics.inputs[1],
+            12 => diagnostics.loop_us.min(u16::MAX as u32) as u16,
+            13 => diagnostics.faults.under_voltage,
+            14 => diagnostics.faults.over_temperature,
+            15 => diagnostics.faults.input,
+            16 => self.counters.messages,
+            17 => self.counters.crc_errors,
+            _ => self.counters.exceptions,
+        }
+    }
+}
+
+fn holding_register(register: u16, status: &Status) -> u16 {
+    match register {
+        HOLDING_PWM => status.pwm(),
+        HOLDING_FREQ => match status.freq() {
+            Freq::Continuous => 0,
+            Freq::Periodic(mhz) => ((mhz + 500) / 1000) as u16,
+        },
+        HOLDING_RELAY => status.pwm_relay() as u16,
+        HOLDING_OE => status.pwm_oe() as u16,
+        _ => 0xFFFF,
+    }
+}
+
+fn write(address: u8, function: u8, data: &[u8]) -> Result<Write, Exception> {
+    let (start, data_word) = range(data)?;
+    let mut requests = Vec::new();
+
+    match function {
+        WRITE_SINGLE_COIL => {
+            check(start, 1, 1, COILS)?;
+            let on = match data_word {
+                COIL_ON => true,
+                COIL_OFF => false,
+                _ => return Err(Exception::IllegalDataValue),
+            };
+            requests.push(coil_request(start, on)).ok();
+        }
+        WRITE_SINGLE_REGISTER => {
+            check(start, 1, 1, HOLDING_REGISTERS)?;
+            requests.push(holding_request(start, data_word)?).ok();
+        }
+        WRITE_MULTIPLE_COILS => {
+            let values = values(data, data_word, (data_word as usize).div_ceil(8))?;
+            check(start, data_word, MAX_WRITE_COILS, COILS)?;
+            for i in 0..data_word {
+                let on = values[i as usize / 8] & 1 << (i % 8) != 0;
+                requests.push(coil_request(start + i, on)).ok();
+            }
+        }
+        _ => {
+            let values = values(data, data_word, data_word as usize * 2)?;
+            check(start, data_word, MAX_WRITE_REGISTERS, HOLDING_REGISTERS)?;
+            for (i, value) in values.chunks(2).enumerate() {
+                let value = u16::from_be_bytes([value[0], value[1]]);
+                requests
+                    .push(holding_request(start + i as u16, value)?)
+                    .ok();
+            }
+        }
+    }
+
+    Ok(Write {
+        address,
+        function,
+        start,
+        data: data_word,
+        requests,
+        next: 0,
+    })
+}
+
+// The first address and the quantity or value that start every request
+// the slave serves
+fn range(data: &[u8]) -> Result<(u16, u16), Exception> {
+    if data.len() < 4 {
+        return Err(Exception::IllegalDataValue);
+    }
+    Ok((
+        u16::from_be_bytes([data[0], data[1]]),
+        u16::from_be_bytes([data[2], data[3]]),
+    ))
+}
+
+// The values of a multiple write, after the byte count, which must match
+// the quantity
+fn values(data: &[u8], quantity: u16, len: usize) -> Result<&[u8], Exception> {
+    let values = data.get(5..).unwrap_or(&[]);
+    match data.get(4) {
+        Some(&count) if quantity > 0 && count as usize == len && values.len() == len => Ok(values),
+        _ => Err(Exception::IllegalDataValue),
+    }
+}
+
+fn check(first: u16, quantity: u16, max: u16, count: u16) -> Result<(), Exception> {
+    if quantity == 0 || quantity > max {
+        Err(Exception::IllegalDataValue)
+    } else if first as u32 + quantity as u32 > count as u32 {
+        Err(Exception::IllegalDataAddress)
+    } else {
+        Ok(())
+    }
+}
+
+fn coil_request(coil: u16, on: bool) -> Request {
+    match coil {
+        COIL_RELAY => Request::SetRelay(on),
+        _ => Request::SetOe(on),
+    }
+}
+
+fn holding_request(register: u16, value: u16) -> Result<Request, Exception> {
+    match (register, value) {
+        (HOLDING_PWM, pwm) if pwm <= PWM_MAX => Ok(Request::SetPwm(pwm)),
+        (HOLDING_FREQ, hz) => Ok(Request::SetFreq(hz as u32)),
+        (HOLDING_RELAY, 0) | (HOLDING_RELAY, 1) => Ok(Request::SetRelay(value == 1)),
+        (HOLDING_OE, 0) | (HOLDING_OE, 1) => Ok(Request::SetOe(value == 1)),
+        (HOLDING_PRESET, preset) if preset <= u8::MAX as u16 => {
+            Ok(Request::ApplyPreset(preset as u8))
+        }
+        _ => Err(Exception::IllegalDataValue),
+    }
+}
+
+fn start(address: u8, function: u8) -> Adu {
+    let mut adu = Vec::new();
+    adu.push(address).ok();
+    adu.push(function).ok();
+    adu
+}
+
+// Appends the CRC
+fn seal(mut adu: Adu) -> Adu {
+    let crc = crc16(&adu);
+    adu.extend_from_slice(&crc.to_le_bytes()).ok();
+    adu
+}
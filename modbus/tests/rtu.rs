@@ -0,0 +1,132 @@
+use lmc_modbus::crc::crc16;
+use lmc_modbus::rtu::{Framer, Timing};
+
+// Read 10 holding registers from slave 1, built by hand with the CRC checked
+// against the spec's CRC-16 algorithm
+const READ: [u8; 8] = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0xC5, 0xCD];
+
+// A character at 9600 baud, in us
+const CHAR_US: u32 = 1146;
+
+fn at_9600() -> Framer {
+    Framer::new(Timing::new(9600, 1_000_000))
+}
+
+// Pushes the bytes a character apart from the time given, returns the time
+// of the last one and the frames they ended
+fn feed(framer: &mut Framer, bytes: &[u8], mut now: u32) -> (u32, Vec<Vec<u8>>) {
+    let mut ended = Vec::new();
+    for (i, &b) in bytes.iter().enumerate() {
+        if i > 0 {
+            now = now.wrapping_add(CHAR_US);
+        }
+        if let Some(frame) = framer.push(b, now) {
+            ended.push(frame.to_vec());
+        }
+    }
+    (now, ended)
+}
+
+fn poll(framer: &mut Framer, now: u32) -> Option<Vec<u8>> {
+    framer.poll(now).map(|frame| frame.to_vec())
+}
+
+#[test]
+fn crc() {
+    assert_eq!(crc16(b"123456789"), 0x4B37);
+    assert_eq!(crc16(&READ[..6]).to_le_bytes(), [0xC5, 0xCD]);
+    assert_eq!(crc16(&READ), 0);
+}
+
+#[test]
+fn timing() {
+    assert_eq!(
+        Timing::new(9600, 1_000_000),
+        Timing {
+            char_gap: 1718,
+            frame_gap: 4010,
+        }
+    );
+    assert_eq!(
+        Timing::new(19_200, 1_000_000),
+        Timing {
+            char_gap: 859,
+            frame_gap: 2005,
+        }
+    );
+
+    // Fixed above 19200 baud
+    assert_eq!(
+        Timing::new(115_200, 72_000_000),
+        Timing {
+            char_gap: 54_000,
+            frame_gap: 126_000,
+        }
+    );
+}
+
+#[test]
+fn frames_end_after_silence() {
+    let mut framer = at_9600();
+    let (last, ended) = feed(&mut framer, &READ, 0);
+    assert!(ended.is_empty());
+
+    assert_eq!(poll(&mut framer, last + 4009), None);
+    assert_eq!(poll(&mut framer, last + 4010), Some(READ.to_vec()));
+    assert_eq!(poll(&mut framer, last + 9000), None);
+
+    // The next frame ends this one when polling was late
+    let (last, ended) = feed(&mut framer, &READ, 20_000);
+    assert!(ended.is_empty());
+    let (_, ended) = feed(&mut framer, &READ, last + 4010);
+    assert_eq!(ended, vec![READ.to_vec()]);
+    assert_eq!(framer.discarded(), 0);
+
+    // Across the clock wrapping
+    let mut framer = at_9600();
+    let (last, _) = feed(&mut framer, &READ, u32::MAX - 3000);
+    assert_eq!(
+        poll(&mut framer, last.wrapping_add(4010)),
+        Some(READ.to_vec())
+    );
+}
+
+#[test]
+fn broken_frames_are_discarded() {
+    let mut framer = at_9600();
+
+    // A gap of more than 1.5 characters within the frame
+    let (last, _) = feed(&mut framer, &READ[..3], 0);
+    let (last, _) = feed(&mut framer, &READ[3..], last + 1719);
+    assert_eq!(poll(&mut framer, last + 4010), None);
+    assert_eq!(framer.discarded(), 1);
+
+    // Up to 1.5 characters is fine
+    let (last, _) = feed(&mut framer, &READ[..3], 100_000);
+    let (last, _) = feed(&mut framer, &READ[3..], last + 1718);
+    assert_eq!(poll(&mut framer, last + 4010), Some(READ.to_vec()));
+
+    // A character with a parity error
+    let (last, _) = feed(&mut framer, &READ[..3], 200_000);
+    assert_eq!(framer.error(last + CHAR_US), None);
+    let (last, _) = feed(&mut framer, &READ[4..], last + 2 * CHAR_US);
+    assert_eq!(poll(&mut framer, last + 4010), None);
+    assert_eq!(framer.discarded(), 2);
+
+    // Too short, and too long
+    let (last, _) = feed(&mut framer, &READ[..3], 300_000);
+    assert_eq!(poll(&mut framer, last + 4010), None);
+    let (last, _) = feed(&mut framer, &[0x11; 257], 400_000);
+    assert_eq!(poll(&mut framer, last + 4010), None);
+    assert_eq!(framer.discarded(), 4);
+
+    // A new baud rate drops the frame in progress
+    feed(&mut framer, &READ[..3], 800_000);
+    framer.set_timing(Timing::new(19_200, 1_000_000));
+    let mut now = 810_000;
+    for &b in READ.iter() {
+        now += CHAR_US / 2;
+        assert_eq!(framer.push(b, now), None);
+    }
+    assert_eq!(poll(&mut framer, now + 2005), Some(READ.to_vec()));
+}
@@ -0,0 +1,281 @@
+use lmc_modbus::rtu::{Framer, Timing};
+use lmc_modbus::slave::{Diagnostics, Received, Slave};
+use lmc_proto::packet::{ErrorCode, Request};
+use lmc_types::fault::FaultCounts;
+use lmc_types::status::{Freq, State, Status, Supply};
+
+// Frames built by hand from the register map, the slave at address 17, with
+// the CRCs checked against the spec's algorithm
+const READ_HOLDING: &str = "11 03 00 00 00 05 87 59";
+const READ_HOLDING_REPLY: &str = "11 03 0A 08 00 00 0D 00 00 00 01 FF FF 17 7C";
+const READ_INPUT: &str = "11 04 00 00 00 13 B3 57";
+const READ_INPUT_REPLY: &str = "11 04 26 00 02 00 00 08 00 00 00 30 D4 00 01 00 00 0C E5 FF FC \
+                                00 00 07 FF 01 36 03 34 00 01 00 00 00 02 00 01 00 00 00 00 18 0C";
+const READ_COILS: &str = "11 01 00 00 00 02 BF 5B";
+const READ_COILS_REPLY: &str = "11 01 01 02 D4 89";
+const RELAY_ON: &str = "11 05 00 00 FF 00 8E AA";
+const WRITE_PWM_FREQ: &str = "11 10 00 00 00 02 04 0F FF 00 05 54 48";
+const WRITE_PWM_FREQ_REPLY: &str = "11 10 00 00 00 02 43 58";
+const WRITE_COILS: &str = "11 0F 00 00 00 02 01 03 9F 9A";
+const WRITE_COILS_REPLY: &str = "11 0F 00 00 00 02 D6 9A";
+const APPLY_PRESET: &str = "11 06 00 04 00 02 4B 5A";
+const BROADCAST_PRESET: &str = "00 06 00 04 00 02 48 1B";
+const BROADCAST_READ: &str = "00 03 00 00 00 01 85 DB";
+const OTHER_SLAVE: &str = "12 03 00 00 00 01 86 A9";
+
+fn hex(s: &str) -> Vec<u8> {
+    s.split_whitespace()
+        .map(|b| u8::from_str_radix(b, 16).unwrap())
+        .collect()
+}
+
+fn diagnostics() -> Diagnostics {
+    Diagnostics {
+        status: Status::new(State::On, 2048, Freq::Periodic(12_500))
+            .with_outputs(true, false)
+            .with_supply(Some(Supply::new(3301, -4))),
+        inputs: [2047, 310],
+        loop_us: 820,
+        faults: FaultCounts {
+            under_voltage: 1,
+            over_temperature: 0,
+            input: 2,
+        },
+    }
+}
+
+fn receive(slave: &mut Slave, frame: &str) -> Received {
+    slave.receive(&hex(frame), &diagnostics())
+}
+
+fn reply(slave: &mut Slave, frame: &str) -> Option<Vec<u8>> {
+    match receive(slave, frame) {
+        Received::Reply(adu) => Some(adu.to_vec()),
+        Received::Nothing => None,
+        Received::Write(write) => panic!("{:?}", write),
+    }
+}
+
+// The requests of a write, and the reply once they all went through
+fn write(slave: &mut Slave, frame: &str) -> (Vec<Request>, Option<Vec<u8>>) {
+    let mut write = match receive(slave, frame) {
+        Received::Write(write) => write,
+        received => panic!("{:?}", received),
+    };
+    let mut requests = Vec::new();
+    while let Some(request) = write.next_request() {
+        requests.push(request);
+    }
+    let reply = slave.written(&write, Ok(())).map(|adu| adu.to_vec());
+    (requests, reply)
+}
+
+#[test]
+fn reads() {
+    let mut slave = Slave::new(17);
+    assert_eq!(reply(&mut slave, READ_INPUT), Some(hex(READ_INPUT_REPLY)));
+    assert_eq!(
+        reply(&mut slave, READ_HOLDING),
+        Some(hex(READ_HOLDING_REPLY))
+    );
+    assert_eq!(reply(&mut slave, READ_COILS), Some(hex(READ_COILS_REPLY)));
+
+    // Just the OE coil, and the preset register alone
+    assert_eq!(
+        reply(&mut slave, "11 01 00 01 00 01 AE 9A"),
+        Some(hex("11 01 01 01 94 88"))
+    );
+    assert_eq!(
+        reply(&mut slave, "11 03 00 04 00 01 C7 5B"),
+        Some(hex("11 03 02 FF FF 78 37"))
+    );
+    assert_eq!(slave.counters().messages, 5);
+}
+
+#[test]
+fn writes() {
+    let mut slave = Slave::new(17);
+    assert_eq!(
+        write(&mut slave, RELAY_ON),
+        (vec![Request::SetRelay(true)], Some(hex(RELAY_ON)))
+    );
+    assert_eq!(
+        write(&mut slave, WRITE_PWM_FREQ),
+        (
+            vec![Request::SetPwm(4095), Request::SetFreq(5)],
+            Some(hex(WRITE_PWM_FREQ_REPLY))
+        )
+    );
+    assert_eq!(
+        write(&mut slave, WRITE_COILS),
+        (
+            vec![Request::SetRelay(true), Request::SetOe(true)],
+            Some(hex(WRITE_COILS_REPLY))
+        )
+    );
+    assert_eq!(
+        write(&mut slave, APPLY_PRESET),
+        (vec![Request::ApplyPreset(2)], Some(hex(APPLY_PRESET)))
+    );
+
+    // Broadcasts are carried out without a reply, reads are ignored
+    assert_eq!(
+        write(&mut slave, BROADCAST_PRESET),
+        (vec![Request::ApplyPreset(2)], None)
+    );
+    assert_eq!(reply(&mut slave, BROADCAST_READ), None);
+    assert_eq!(reply(&mut slave, OTHER_SLAVE), None);
+    assert_eq!(slave.counters().exceptions, 0);
+}
+
+#[test]
+fn refused_writes() {
+    let mut slave = Slave::new(17);
+
+    let mut write = match receive(&mut slave, WRITE_PWM_FREQ) {
+        Received::Write(write) => write,
+        received => panic!("{:?}", received),
+    };
+    assert_eq!(write.next_request(), Some(Request::SetPwm(4095)));
+    assert_eq!(
+        slave
+            .written(&write, Err(ErrorCode::Busy))
+            .unwrap()
+            .to_vec(),
+        hex("11 90 06 CD C7")
+    );
+
+    let write = match receive(&mut slave, APPLY_PRESET) {
+        Received::Write(write) => write,
+        received => panic!("{:?}", received),
+    };
+    assert_eq!(
+        slave
+            .written(&write, Err(ErrorCode::OutOfRange))
+            .unwrap()
+            .to_vec(),
+        hex("11 86 03 03 A4")
+    );
+
+    let write = match receive(&mut slave, BROADCAST_PRESET) {
+        Received::Write(write) => write,
+        received => panic!("{:?}", received),
+    };
+    assert_eq!(slave.written(&write, Err(ErrorCode::Refused)), None);
+    assert_eq!(slave.counters().exceptions, 2);
+}
+
+#[test]
+fn whole_write_is_listed_before_carrying_any_out() {
+    let mut slave = Slave::new(17);
+
+    // Checked first, a refusal of any one refuses the lot with nothing written
+    let write = match receive(&mut slave, WRITE_COILS) {
+        Received::Write(write) => write,
+        received => panic!("{:?}", received),
+    };
+    assert_eq!(
+        write.requests(),
+        [Request::SetRelay(true), Request::SetOe(true)]
+    );
+    assert_eq!(
+        slave
+            .written(&write, Err(ErrorCode::Busy))
+            .unwrap()
+            .to_vec(),
+        hex("11 8F 06 C5 F7")
+    );
+
+    let mut write = match receive(&mut slave, WRITE_PWM_FREQ) {
+        Received::Write(write) => write,
+        received => panic!("{:?}", received),
+    };
+    assert_eq!(
+        write.requests(),
+        [Request::SetPwm(4095), Request::SetFreq(5)]
+    );
+    assert_eq!(write.next_request(), Some(Request::SetPwm(4095)));
+    assert_eq!(write.requests().len(), 2);
+}
+
+#[test]
+fn exceptions() {
+    let mut slave = Slave::new(17);
+    let exceptions = [
+        // Read device identification isn't supported
+        ("11 2B 0E 01 00 B1 B4", "11 AB 01 9F 35"),
+        // Past the last holding register
+        ("11 03 00 04 00 02 87 5A", "11 83 02 C1 34"),
+        // No registers
+        ("11 03 00 00 00 00 47 5A", "11 83 03 00 F4"),
+        // A coil is FF00 or 0000
+        ("11 05 00 01 12 34 93 ED", "11 85 03 03 54"),
+        // PWM over 4095
+        ("11 06 00 00 10 00 86 9A", "11 86 03 03 A4"),
+        // The byte count doesn't match the quantity
+        ("11 10 00 00 00 02 03 0F FF 00 E5 E0", "11 90 03 0D C4"),
+    ];
+    for &(request, exception) in exceptions.iter() {
+        assert_eq!(
+            reply(&mut slave, request),
+            Some(hex(exception)),
+            "{}",
+            request
+        );
+    }
+    assert_eq!(slave.counters().exceptions, exceptions.len() as u16);
+
+    // Nothing for a bad CRC, or a broadcast
+    assert_eq!(reply(&mut slave, "11 03 00 00 00 05 87 58"), None);
+    assert_eq!(reply(&mut slave, "00 2B 0E 01 00 4D B7"), None);
+    assert_eq!(slave.counters().crc_errors, 1);
+    assert_eq!(slave.counters().exceptions, exceptions.len() as u16);
+}
+
+#[test]
+fn simulated_bus() {
+    // Requests for this slave and another, the other slave's reply, and a
+    // frame corrupted by noise, timed by hand as a bus at 19200 baud would
+    // carry them, in us. The slave doesn't hear its own replies.
+    let bus = [
+        (0, READ_HOLDING),
+        (30_000, OTHER_SLAVE),
+        (40_000, "12 03 02 00 00 3D 87"),
+        (60_000, "11 03 00 00 00 05 87 5D"),
+        (80_000, RELAY_ON),
+    ];
+    let char_us = 573;
+    let mut framer = Framer::new(Timing::new(19_200, 1_000_000));
+    let mut slave = Slave::new(17);
+    let mut received = Vec::new();
+
+    let mut serve = |frame: &[u8]| match slave.receive(frame, &diagnostics()) {
+        Received::Nothing => (),
+        r => received.push(r),
+    };
+    for &(start, frame) in bus.iter() {
+        let mut now = start;
+        for b in hex(frame) {
+            if let Some(frame) = framer.poll(now) {
+                serve(&frame);
+            }
+            if let Some(frame) = framer.push(b, now) {
+                serve(&frame);
+            }
+            now += char_us;
+        }
+    }
+    if let Some(frame) = framer.poll(100_000) {
+        serve(&frame);
+    }
+
+    assert_eq!(received.len(), 2);
+    match &received[0] {
+        Received::Reply(adu) => assert_eq!(adu.to_vec(), hex(READ_HOLDING_REPLY)),
+        r => panic!("{:?}", r),
+    }
+    assert!(matches!(received[1], Received::Write(_)));
+    assert_eq!(slave.counters().messages, 4);
+    assert_eq!(slave.counters().crc_errors, 1);
+    assert_eq!(framer.discarded(), 0);
+}
@@ -0,0 +1,67 @@
+// Consistent Overhead Byte Stuffing, removes the zero bytes from a packet
+// for one byte in every 254
+
+// Longest encoding of len bytes
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len / 254 + 1
+}
+
+// Returns the encoded length, None if dst is too short
+pub fn encode(src: &[u8], dst: &mut [u8]) -> Option<usize> {
+    if dst.len() < max_encoded_len(src.len()) {
+        return None;
+    }
+
+    // Index of the code byte of the current block
+    let mut code_at = 0;
+    let mut code = 1u8;
+    let mut out = 1;
+
+    for &byte in src {
+        if byte != 0 {
+            dst[out] = byte;
+            out += 1;
+            code += 1;
+        }
+
+        if byte == 0 || code == 0xFF {
+            dst[code_at] = code;
+            code_at = out;
+            out += 1;
+            code = 1;
+        }
+    }
+    dst[code_at] = code;
+
+    Some(out)
+}
+
+// Returns the decoded length, None if src isn't valid COBS or dst is too
+// short
+pub fn decode(src: &[u8], dst: &mut [u8]) -> Option<usize> {
+    let mut i = 0;
+    let mut out = 0;
+
+    while i < src.len() {
+        let code = src[i] as usize;
+        if code == 0 || i + code > src.len() {
+            return None;
+        }
+
+        let block = &src[i + 1..i + code];
+        if block.contains(&0) {
+            return None;
+        }
+        dst.get_mut(out..out + block.len())?.copy_from_slice(block);
+        out += block.len();
+        i += code;
+
+        // A zero follows every block but the last, except after a full one
+        if code < 0xFF && i < src.len() {
+            *dst.get_mut(out)? = 0;
+            out += 1;
+        }
+    }
+
+    Some(out)
+}
@@ -0,0 +1,71 @@
+use crate::packet::{Error, Packet};
+use heapless::consts::U64;
+use heapless::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    // Outside a frame, for the text console
+    Text(u8),
+    // Taken by a frame
+    Pending,
+    // A frame ended
+    Packet(Result<Packet, Error>),
+}
+
+// Splits a byte stream into text and frames. A zero byte starts a frame and
+// the next one ends it, zeros in a row are a single delimiter. Text right
+// after a bad frame is taken for the next frame.
+pub struct Decoder {
+    frame: Vec<u8, U64>,
+    in_frame: bool,
+    // Frame longer than the buffer, reported when it ends
+    overflow: bool,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder {
+            frame: Vec::new(),
+            in_frame: false,
+            overflow: false,
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) -> Input {
+        match (self.in_frame, byte) {
+            (false, 0) => {
+                self.in_frame = true;
+                self.frame = Vec::new();
+                self.overflow = false;
+                Input::Pending
+            }
+            (false, _) => Input::Text(byte),
+            (true, 0) if self.frame.is_empty() && !self.overflow => Input::Pending,
+            // A bad frame was likely cut short, its end is then the start
+            // of the next one
+            (true, 0) => {
+                let packet = if self.overflow {
+                    Err(Error::Framing)
+                } else {
+                    Packet::decode(&self.frame)
+                };
+                self.in_frame = packet.is_err();
+                self.frame = Vec::new();
+                self.overflow = false;
+                Input::Packet(packet)
+            }
+            (true, _) => {
+                if self.frame.push(byte).is_err() {
+                    self.overflow = true;
+                }
+                Input::Pending
+            }
+        }
+    }
+}
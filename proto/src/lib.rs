@@ -0,0 +1,11 @@
+// Binary control protocol between the firmware and host tools. Packets are
+// CRC-16 checked, COBS encoded and sent between zero bytes, so they can
+// share the serial line with the text console.
+
+#![no_std]
+
+pub mod cobs;
+pub mod crc;
+pub mod decoder;
+pub mod link;
+pub mod packet;
@@ -0,0 +1,78 @@
+use crate::decoder::{Decoder, Input};
+use crate::packet::{Body, Error, ErrorCode, Event, Frame, Packet, Request, Response};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Received {
+    Nothing,
+    // For the text console
+    Text(u8),
+    // Answer with respond() and the same sequence number
+    Request(u8, Request),
+    // A request that can't be served, answer with the error
+    Invalid(u8, ErrorCode),
+}
+
+// Device end of the protocol: splits what the host sends into console text
+// and requests, and frames the responses and events
+pub struct Link {
+    decoder: Decoder,
+    event_seq: u8,
+    // A host spoke the protocol, events are only sent after that
+    peer: bool,
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Link {
+    pub fn new() -> Self {
+        Link {
+            decoder: Decoder::new(),
+            event_seq: 0,
+            peer: false,
+        }
+    }
+
+    pub fn peer(&self) -> bool {
+        self.peer
+    }
+
+    pub fn push(&mut self, byte: u8) -> Received {
+        let packet = match self.decoder.push(byte) {
+            Input::Text(byte) => return Received::Text(byte),
+            Input::Pending => return Received::Nothing,
+            Input::Packet(packet) => packet,
+        };
+
+        match packet {
+            Ok(Packet {
+                seq,
+                body: Body::Request(request),
+            }) => {
+                self.peer = true;
+                Received::Request(seq, request)
+            }
+            Err(Error::Version(seq)) => Received::Invalid(seq, ErrorCode::Version),
+            Err(Error::Malformed(seq)) => Received::Invalid(seq, ErrorCode::Malformed),
+            // Corrupted, or not meant for the device
+            _ => Received::Nothing,
+        }
+    }
+
+    pub fn respond(&self, seq: u8, response: Response) -> Frame {
+        Packet::new(seq, Body::Response(response)).encode()
+    }
+
+    // None until a host has sent a request, so a terminal doesn't see them
+    pub fn event(&mut self, event: Event) -> Option<Frame> {
+        if !self.peer {
+            return None;
+        }
+
+        self.event_seq = self.event_seq.wrapping_add(1);
+        Some(Packet::new(self.event_seq, Body::Event(event)).encode())
+    }
+}
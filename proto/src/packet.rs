@@ -0,0 +1,384 @@
+use crate::cobs;
+use crate::crc::crc16;
+use lmc_types::status::{Fault, Freq, Health, State, Status, Supply};
+
+pub const VERSION: u8 = 1;
+
+// Version, sequence number and kind, then the body and the CRC of all of
+// it, little endian
+const HEADER_LEN: usize = 3;
+const CRC_LEN: usize = 2;
+pub const MAX_PACKET: usize = 32;
+
+// A packet COBS encoded between two zero bytes
+pub const MAX_FRAME: usize = cobs::max_encoded_len(MAX_PACKET) + 2;
+
+const PING: u8 = 0x01;
+const GET_STATUS: u8 = 0x02;
+const SET_PWM: u8 = 0x03;
+const SET_FREQ: u8 = 0x04;
+const SET_RELAY: u8 = 0x05;
+const SET_OE: u8 = 0x06;
+const APPLY_PRESET: u8 = 0x07;
+
+const OK: u8 = 0x80;
+const PONG: u8 = 0x81;
+const STATUS: u8 = 0x82;
+const ERROR: u8 = 0x8F;
+
+const STATUS_EVENT: u8 = 0xC0;
+
+// From the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Request {
+    Ping,
+    GetStatus,
+    SetPwm(u16),
+    // Strobe rate in Hz, 0 for continuous
+    SetFreq(u32),
+    SetRelay(bool),
+    SetOe(bool),
+    ApplyPreset(u8),
+}
+
+// To a request, with its sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Response {
+    Ok,
+    Pong,
+    Status(Status),
+    Error(ErrorCode),
+}
+
+// Sent by the device on its own, numbered separately from the requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    // The state or fault changed
+    Status(Status),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    // The request used another protocol version, the response header has
+    // the device's
+    Version,
+    // Unknown kind or bad body
+    Malformed,
+    // The menu is open on the device
+    Busy,
+    // Interlocked, clear the fault first
+    Refused,
+    // SetPwm or SetFreq above the device's PWM MAX or FREQ MAX, or no such
+    // preset
+    OutOfRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Body {
+    Request(Request),
+    Response(Response),
+    Event(Event),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Packet {
+    pub seq: u8,
+    pub body: Body,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Error {
+    // Not valid COBS, or too long
+    Framing,
+    Crc,
+    // Another protocol version, with the sequence number to answer with
+    // ErrorCode::Version
+    Version(u8),
+    // Unknown kind or bad body, with the sequence number
+    Malformed(u8),
+}
+
+// Encoded packet, delimiters included
+#[derive(Clone, Copy)]
+pub struct Frame {
+    buf: [u8; MAX_FRAME],
+    len: usize,
+}
+
+impl Frame {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl Packet {
+    pub fn new(seq: u8, body: Body) -> Self {
+        Packet { seq, body }
+    }
+
+    pub fn encode(&self) -> Frame {
+        let mut packet = [0; MAX_PACKET];
+        let mut w = Writer {
+            buf: &mut packet,
+            len: 0,
+        };
+
+        w.u8(VERSION);
+        w.u8(self.seq);
+        match self.body {
+            Body::Request(request) => match request {
+                Request::Ping => w.u8(PING),
+                Request::GetStatus => w.u8(GET_STATUS),
+                Request::SetPwm(pwm) => {
+                    w.u8(SET_PWM);
+                    w.u16(pwm);
+                }
+                Request::SetFreq(hz) => {
+                    w.u8(SET_FREQ);
+                    w.u32(hz);
+                }
+                Request::SetRelay(on) => {
+                    w.u8(SET_RELAY);
+                    w.u8(on as u8);
+                }
+                Request::SetOe(on) => {
+                    w.u8(SET_OE);
+                    w.u8(on as u8);
+                }
+                Request::ApplyPreset(i) => {
+                    w.u8(APPLY_PRESET);
+                    w.u8(i);
+                }
+            },
+            Body::Response(response) => match response {
+                Response::Ok => w.u8(OK),
+                Response::Pong => w.u8(PONG),
+                Response::Status(status) => {
+                    w.u8(STATUS);
+                    w.status(&status);
+                }
+                Response::Error(code) => {
+                    w.u8(ERROR);
+                    w.u8(code as u8);
+                }
+            },
+            Body::Event(Event::Status(status)) => {
+                w.u8(STATUS_EVENT);
+                w.status(&status);
+            }
+        }
+
+        let crc = crc16(&w.buf[..w.len]);
+        w.u16(crc);
+
+        let len = w.len;
+        let mut frame = Frame {
+            buf: [0; MAX_FRAME],
+            len: 0,
+        };
+        // Always fits, MAX_FRAME is sized for MAX_PACKET
+        let encoded = cobs::encode(&packet[..len], &mut frame.buf[1..]).unwrap();
+        frame.len = encoded + 2;
+        frame
+    }
+
+    // From the COBS encoded bytes between the delimiters
+    pub fn decode(encoded: &[u8]) -> Result<Self, Error> {
+        let mut packet = [0; MAX_PACKET];
+        let len = cobs::decode(encoded, &mut packet).ok_or(Error::Framing)?;
+        if len < HEADER_LEN + CRC_LEN {
+            return Err(Error::Framing);
+        }
+
+        let (data, crc) = packet[..len].split_at(len - CRC_LEN);
+        if crc16(data) != u16::from_le_bytes([crc[0], crc[1]]) {
+            return Err(Error::Crc);
+        }
+
+        let (version, seq, kind) = (data[0], data[1], data[2]);
+        if version != VERSION {
+            return Err(Error::Version(seq));
+        }
+
+        let mut r = Reader {
+            buf: &data[HEADER_LEN..],
+        };
+        match r.body(kind) {
+            Some(body) if r.buf.is_empty() => Ok(Packet { seq, body }),
+            _ => Err(Error::Malformed(seq)),
+        }
+    }
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn i16(&mut self, v: i16) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    // 15 bytes, the strobe rate in mHz with 0 for continuous
+    fn status(&mut self, status: &Status) {
+        self.u8(match status.state() {
+            State::Error => 0,
+            State::Off => 1,
+            State::On => 2,
+        });
+        self.u8(match status.fault() {
+            None => 0,
+            Some(Fault::UnderVoltage) => 1,
+            Some(Fault::OverTemperature) => 2,
+            Some(Fault::Input) => 3,
+        });
+        self.u16(status.pwm());
+        self.u32(match status.freq() {
+            Freq::Continuous => 0,
+            Freq::Periodic(mhz) => mhz,
+        });
+        self.u8(status.pwm_oe() as u8
+            | (status.pwm_relay() as u8) << 1
+            | (status.supply().is_some() as u8) << 2);
+        let supply = status.supply().unwrap_or_else(|| Supply::new(0, 0));
+        self.u16(supply.vdda_mv());
+        self.i16(supply.temperature());
+        self.u8(match status.display() {
+            Health::Ok => 0,
+            Health::Missing => 1,
+            Health::Lost => 2,
+        });
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn body(&mut self, kind: u8) -> Option<Body> {
+        Some(match kind {
+            PING => Body::Request(Request::Ping),
+            GET_STATUS => Body::Request(Request::GetStatus),
+            SET_PWM => Body::Request(Request::SetPwm(self.u16()?)),
+            SET_FREQ => Body::Request(Request::SetFreq(self.u32()?)),
+            SET_RELAY => Body::Request(Request::SetRelay(self.bool()?)),
+            SET_OE => Body::Request(Request::SetOe(self.bool()?)),
+            APPLY_PRESET => Body::Request(Request::ApplyPreset(self.u8()?)),
+            OK => Body::Response(Response::Ok),
+            PONG => Body::Response(Response::Pong),
+            STATUS => Body::Response(Response::Status(self.status()?)),
+            ERROR => Body::Response(Response::Error(self.error_code()?)),
+            STATUS_EVENT => Body::Event(Event::Status(self.status()?)),
+            _ => return None,
+        })
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        if self.buf.len() < N {
+            return None;
+        }
+        let (head, tail) = self.buf.split_at(N);
+        self.buf = tail;
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(head);
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        Some(i16::from_le_bytes(self.bytes()?))
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn error_code(&mut self) -> Option<ErrorCode> {
+        Some(match self.u8()? {
+            0 => ErrorCode::Version,
+            1 => ErrorCode::Malformed,
+            2 => ErrorCode::Busy,
+            3 => ErrorCode::Refused,
+            4 => ErrorCode::OutOfRange,
+            _ => return None,
+        })
+    }
+
+    fn status(&mut self) -> Option<Status> {
+        let state = match self.u8()? {
+            0 => State::Error,
+            1 => State::Off,
+            2 => State::On,
+            _ => return None,
+        };
+        let fault = match self.u8()? {
+            0 => None,
+            1 => Some(Fault::UnderVoltage),
+            2 => Some(Fault::OverTemperature),
+            3 => Some(Fault::Input),
+            _ => return None,
+        };
+        let pwm = self.u16()?;
+        let freq = match self.u32()? {
+            0 => Freq::Continuous,
+            mhz => Freq::Periodic(mhz),
+        };
+        let flags = self.u8()?;
+        let vdda_mv = self.u16()?;
+        let temperature = self.i16()?;
+        let display = match self.u8()? {
+            0 => Health::Ok,
+            1 => Health::Missing,
+            2 => Health::Lost,
+            _ => return None,
+        };
+
+        let supply = if flags & 0b100 != 0 {
+            Some(Supply::new(vdda_mv, temperature))
+        } else {
+            None
+        };
+
+        Some(
+            Status::new(state, pwm, freq)
+                .with_outputs(flags & 0b001 != 0, flags & 0b010 != 0)
+                .with_supply(supply)
+                .with_fault(fault)
+                .with_display(display),
+        )
+    }
+}
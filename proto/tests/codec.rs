@@ -0,0 +1,288 @@
+use lmc_proto::cobs;
+use lmc_proto::crc::crc16;
+use lmc_proto::decoder::{Decoder, Input};
+use lmc_proto::link::{Link, Received};
+use lmc_proto::packet::{
+    Body, Error, ErrorCode, Event, Packet, Request, Response, MAX_FRAME, VERSION,
+};
+use lmc_types::status::{Fault, Freq, Health, State, Status, Supply};
+
+fn status() -> Status {
+    Status::new(State::On, 2048, Freq::Periodic(12_500))
+        .with_outputs(true, true)
+        .with_supply(Some(Supply::new(3301, -12)))
+        .with_display(Health::Lost)
+}
+
+fn frame(packet: &Packet) -> Vec<u8> {
+    packet.encode().as_bytes().to_vec()
+}
+
+fn feed(decoder: &mut Decoder, bytes: &[u8]) -> Vec<Input> {
+    bytes
+        .iter()
+        .map(|&b| decoder.push(b))
+        .filter(|input| *input != Input::Pending)
+        .collect()
+}
+
+// The packet bytes of a frame, to tamper with
+fn unframe(frame: &[u8]) -> Vec<u8> {
+    let mut packet = [0; MAX_FRAME];
+    let len = cobs::decode(&frame[1..frame.len() - 1], &mut packet).unwrap();
+    packet[..len].to_vec()
+}
+
+fn reframe(packet: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0; cobs::max_encoded_len(packet.len()) + 2];
+    let len = cobs::encode(packet, &mut frame[1..]).unwrap();
+    frame.truncate(len + 2);
+    frame[len + 1] = 0;
+    frame
+}
+
+#[test]
+fn crc_check_value() {
+    assert_eq!(crc16(b"123456789"), 0x29B1);
+}
+
+#[test]
+fn cobs_round_trips() {
+    let long: Vec<u8> = (0..600).map(|i| (i % 256) as u8).collect();
+    let cases: [&[u8]; 6] = [
+        &[],
+        &[0],
+        &[0, 0],
+        &[0x11, 0x22, 0x00, 0x33],
+        &[0xFF; 254],
+        &long,
+    ];
+
+    for src in cases.iter() {
+        let mut encoded = vec![0; cobs::max_encoded_len(src.len())];
+        let len = cobs::encode(src, &mut encoded).unwrap();
+        assert!(!encoded[..len].contains(&0), "{:?}", src);
+
+        let mut decoded = vec![0; src.len()];
+        assert_eq!(cobs::decode(&encoded[..len], &mut decoded), Some(src.len()));
+        assert_eq!(&decoded[..], *src);
+    }
+
+    let mut encoded = [0; 8];
+    assert_eq!(
+        cobs::encode(&[0x11, 0x22, 0x00, 0x33], &mut encoded),
+        Some(5)
+    );
+    assert_eq!(&encoded[..5], &[0x03, 0x11, 0x22, 0x02, 0x33]);
+    assert_eq!(cobs::encode(&[0; 8], &mut [0; 8]), None);
+}
+
+#[test]
+fn cobs_rejects_bad_input() {
+    let mut dst = [0; 8];
+    assert_eq!(cobs::decode(&[0x05, 0x11, 0x22], &mut dst), None);
+    assert_eq!(cobs::decode(&[0x03, 0x11, 0x00], &mut dst), None);
+    assert_eq!(cobs::decode(&[0x00], &mut dst), None);
+    assert_eq!(cobs::decode(&[0x04, 1, 2, 3], &mut [0; 2]), None);
+}
+
+#[test]
+fn packets_round_trip() {
+    let bodies = [
+        Body::Request(Request::Ping),
+        Body::Request(Request::GetStatus),
+        Body::Request(Request::SetPwm(4095)),
+        Body::Request(Request::SetFreq(0)),
+        Body::Request(Request::SetFreq(120)),
+        Body::Request(Request::SetRelay(true)),
+        Body::Request(Request::SetOe(false)),
+        Body::Request(Request::ApplyPreset(3)),
+        Body::Response(Response::Ok),
+        Body::Response(Response::Pong),
+        Body::Response(Response::Status(status())),
+        Body::Response(Response::Status(
+            Status::new(State::Error, 0, Freq::Continuous).with_fault(Some(Fault::OverTemperature)),
+        )),
+        Body::Response(Response::Error(ErrorCode::Refused)),
+        Body::Response(Response::Error(ErrorCode::OutOfRange)),
+        Body::Event(Event::Status(status())),
+    ];
+
+    for (seq, body) in bodies.iter().enumerate() {
+        let packet = Packet::new(seq as u8 * 17, *body);
+        let frame = frame(&packet);
+        assert_eq!(frame[0], 0);
+        assert_eq!(frame[frame.len() - 1], 0);
+        assert!(!frame[1..frame.len() - 1].contains(&0));
+        assert_eq!(Packet::decode(&frame[1..frame.len() - 1]), Ok(packet));
+    }
+}
+
+#[test]
+fn status_wire_format() {
+    let packet = unframe(&frame(&Packet::new(
+        7,
+        Body::Response(Response::Status(status())),
+    )));
+    assert_eq!(
+        &packet[..packet.len() - 2],
+        &[
+            VERSION, 7, 0x82, // header
+            2, 0, // state, fault
+            0x00, 0x08, // pwm
+            0xD4, 0x30, 0x00, 0x00,  // freq mHz
+            0b111, // oe, relay, supply
+            0xE5, 0x0C, // vdda
+            0xF4, 0xFF, // temperature
+            2,    // display
+        ][..]
+    );
+}
+
+#[test]
+fn bad_frames_are_rejected() {
+    let good = unframe(&frame(&Packet::new(1, Body::Request(Request::SetPwm(100)))));
+
+    let mut corrupt = good.clone();
+    corrupt[4] ^= 0x01;
+    let f = reframe(&corrupt);
+    assert_eq!(Packet::decode(&f[1..f.len() - 1]), Err(Error::Crc));
+
+    let mut version = good.clone();
+    version[0] = VERSION + 1;
+    let crc = crc16(&version[..version.len() - 2]).to_le_bytes();
+    let n = version.len();
+    version[n - 2..].copy_from_slice(&crc);
+    let f = reframe(&version);
+    assert_eq!(Packet::decode(&f[1..f.len() - 1]), Err(Error::Version(1)));
+
+    // Right CRC around an unknown kind and a body that's too short
+    for packet in [vec![VERSION, 2, 0x7E], vec![VERSION, 2, 0x03, 0x01]].iter() {
+        let mut packet = packet.clone();
+        packet.extend_from_slice(&crc16(&packet).to_le_bytes());
+        let f = reframe(&packet);
+        assert_eq!(Packet::decode(&f[1..f.len() - 1]), Err(Error::Malformed(2)));
+    }
+
+    assert_eq!(Packet::decode(&[0x02, 0x01]), Err(Error::Framing));
+    assert_eq!(Packet::decode(&[0x09, 0x01]), Err(Error::Framing));
+}
+
+#[test]
+fn decoder_separates_text_and_frames() {
+    let ping = Packet::new(9, Body::Request(Request::Ping));
+    let pwm = Packet::new(10, Body::Request(Request::SetPwm(0)));
+
+    let mut stream = b"st".to_vec();
+    stream.extend(frame(&ping));
+    stream.extend(b"atus\r");
+    // Back to back, sharing nothing
+    stream.extend(frame(&pwm));
+    stream.extend(frame(&ping));
+
+    let mut decoder = Decoder::new();
+    assert_eq!(
+        feed(&mut decoder, &stream),
+        vec![
+            Input::Text(b's'),
+            Input::Text(b't'),
+            Input::Packet(Ok(ping)),
+            Input::Text(b'a'),
+            Input::Text(b't'),
+            Input::Text(b'u'),
+            Input::Text(b's'),
+            Input::Text(b'\r'),
+            Input::Packet(Ok(pwm)),
+            Input::Packet(Ok(ping)),
+        ]
+    );
+}
+
+#[test]
+fn decoder_recovers_after_garbage() {
+    let ping = Packet::new(1, Body::Request(Request::Ping));
+    let mut decoder = Decoder::new();
+
+    // A truncated frame runs into the next one's leading zero
+    let mut stream = frame(&ping);
+    stream.truncate(4);
+    stream.extend(frame(&ping));
+    let inputs = feed(&mut decoder, &stream);
+    assert_eq!(inputs.len(), 2);
+    assert!(matches!(inputs[0], Input::Packet(Err(_))));
+    assert_eq!(inputs[1], Input::Packet(Ok(ping)));
+
+    // Text after a good frame
+    assert_eq!(feed(&mut decoder, b"x"), vec![Input::Text(b'x')]);
+
+    // Way too long
+    let mut stream = vec![0];
+    stream.extend(vec![0x42; 200]);
+    stream.push(0);
+    stream.extend(frame(&ping));
+    assert_eq!(
+        feed(&mut decoder, &stream),
+        vec![Input::Packet(Err(Error::Framing)), Input::Packet(Ok(ping))]
+    );
+}
+
+#[test]
+fn link_answers_requests_only() {
+    let mut link = Link::new();
+    let mut received = Vec::new();
+    let mut push = |link: &mut Link, bytes: &[u8]| {
+        for &b in bytes {
+            match link.push(b) {
+                Received::Nothing => (),
+                r => received.push(r),
+            }
+        }
+    };
+
+    push(&mut link, b"?");
+    assert!(!link.peer());
+    assert!(link.event(Event::Status(status())).is_none());
+
+    // Replies from another device are ignored
+    push(
+        &mut link,
+        &frame(&Packet::new(3, Body::Response(Response::Ok))),
+    );
+    assert!(!link.peer());
+
+    push(
+        &mut link,
+        &frame(&Packet::new(4, Body::Request(Request::SetOe(true)))),
+    );
+    assert!(link.peer());
+
+    let mut unknown = vec![VERSION, 5, 0x70];
+    unknown.extend_from_slice(&crc16(&unknown).to_le_bytes());
+    push(&mut link, &reframe(&unknown));
+
+    assert_eq!(
+        received,
+        vec![
+            Received::Text(b'?'),
+            Received::Request(4, Request::SetOe(true)),
+            Received::Invalid(5, ErrorCode::Malformed),
+        ]
+    );
+
+    let reply = link.respond(4, Response::Ok);
+    let bytes = reply.as_bytes();
+    assert_eq!(
+        Packet::decode(&bytes[1..bytes.len() - 1]),
+        Ok(Packet::new(4, Body::Response(Response::Ok)))
+    );
+
+    // Numbered on their own
+    for seq in 1..3 {
+        let event = link.event(Event::Status(status())).unwrap();
+        let bytes = event.as_bytes();
+        assert_eq!(
+            Packet::decode(&bytes[1..bytes.len() - 1]),
+            Ok(Packet::new(seq, Body::Event(Event::Status(status()))))
+        );
+    }
+}
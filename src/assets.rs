@@ -0,0 +1,4 @@
+// Images converted from the files in assets/ by the build script
+use lmc_ui::bitmap::Bitmap;
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
@@ -0,0 +1,36 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::peripheral::syst::SystClkSource;
+use cortex_m::peripheral::SYST;
+use stm32f1xx_hal::rcc::Clocks;
+
+pub use lmc_types::time::Instant;
+
+pub const TICK_HZ: u32 = 1000;
+
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+// Millisecond time base driven by the SysTick exception
+pub struct Clock {
+    _syst: SYST,
+}
+
+impl Clock {
+    pub fn new(mut syst: SYST, clocks: Clocks) -> Self {
+        syst.set_clock_source(SystClkSource::Core);
+        syst.set_reload(clocks.sysclk().0 / TICK_HZ - 1);
+        syst.clear_current();
+        syst.enable_counter();
+        syst.enable_interrupt();
+
+        Clock { _syst: syst }
+    }
+
+    pub fn now(&self) -> Instant {
+        Instant::from_millis(TICKS.load(Ordering::Relaxed))
+    }
+}
+
+// Called from the SysTick exception handler
+pub fn tick() {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
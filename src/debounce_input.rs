@@ -9,7 +9,7 @@ pub trait DebounceInput {
 
 impl<T> DebounceInput for T
 where
-    T: InputPin,
+    T: InputPin + ?Sized,
 {
     fn is_low_debounce(&self) -> bool {
         if self.is_low() {
@@ -1,113 +1,279 @@
-use core::fmt::Write;
-use crate::lcm::{Freq, State, Status};
-use embedded_graphics::fonts::Font6x8;
-use embedded_graphics::prelude::*;
+use crate::clock::Instant;
+use crate::lcm::{Health, Status};
+use embedded_graphics::coord::Coord;
 use embedded_hal::blocking;
-use heapless::consts::U32;
-use heapless::String;
+use lmc_ui::bitmap::Bitmap;
+use lmc_ui::canvas::Translate;
+use lmc_ui::history::{History, Sample, Trace};
+use lmc_ui::menu::Menu;
+use lmc_ui::screen;
+use lmc_ui::widgets::StrobeIndicator;
+use heapless::ArrayLength;
 use ssd1306::mode::GraphicsMode;
 use ssd1306::prelude::*;
 use ssd1306::Builder;
 
 const SLAVE_ADDRESS: u8 = 0x3C;
 
+// Time between attempts to (re-)attach the display
+const RETRY_MS: u32 = 2000;
+
+pub const CONTRAST_DEFAULT: u8 = 0x8F;
+const CONTRAST_DIM: u8 = 0x01;
+
+// The status and trend screens step through these offsets against burn-in,
+// their layouts leave enough room on the right and bottom
+const SHIFT_OFFSETS: [(i32, i32); 8] = [
+    (0, 0),
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (3, 1),
+    (2, 1),
+    (1, 1),
+    (0, 1),
+];
+const SHIFT_MS: u32 = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Power {
+    On,
+    // Lowest contrast
+    Dim,
+    // Panel asleep, drawing is skipped
+    Off,
+}
+
+// What the panel shows, to skip redrawing an unchanged screen
+#[derive(Clone, Copy, PartialEq)]
+enum Shown {
+    // Status, strobe state and offset on screen
+    Status(Status, bool, (i32, i32)),
+    // Trace, History::recorded() and offset on screen
+    Trend(Trace, u32, (i32, i32)),
+}
+
 pub struct Display<I2C>
 where
     I2C: blocking::i2c::Write,
 {
     drv: GraphicsMode<I2cInterface<I2C>>,
+    // None when showing a screen that is always redrawn
+    shown: Option<Shown>,
+    health: Health,
+    contrast: u8,
+    power: Power,
+    pixel_shift: bool,
+    // Time of the last attach attempt while not Ok, None until polled
+    attempt: Option<Instant>,
+    // False for a panel size the screens can't lay out on, never attached
+    supported: bool,
 }
 
 impl<I2C> Display<I2C>
 where
     I2C: embedded_hal::blocking::i2c::Write,
 {
-    // TODO - error/result handling
-    pub fn new(i2c: I2C) -> Self {
-        let mut drv: GraphicsMode<I2cInterface<I2C>> = Builder::new()
-            .with_size(DisplaySize::Display128x32)
-            .with_rotation(DisplayRotation::Rotate180)
+    // Runs headless if the display doesn't respond, poll() retries. The
+    // screens need a landscape 128x32 or 128x64 panel, with any other size
+    // it runs headless for good.
+    pub fn new(i2c: I2C, size: DisplaySize, rotation: DisplayRotation) -> Self {
+        let drv: GraphicsMode<I2cInterface<I2C>> = Builder::new()
+            .with_size(size)
+            .with_rotation(rotation)
             .with_i2c_addr(SLAVE_ADDRESS)
             .connect_i2c(i2c)
             .into();
 
-        drv.init().unwrap();
-        drv.clear();
-
         let (w, h) = drv.get_dimensions();
-        assert_eq!(w, 128);
-        assert_eq!(h, 32);
+        let supported = w == 128 && (h == 32 || h == 64);
 
-        drv.flush().unwrap();
+        let mut disp = Display {
+            drv,
+            shown: None,
+            health: Health::Missing,
+            contrast: CONTRAST_DEFAULT,
+            power: Power::On,
+            pixel_shift: false,
+            attempt: None,
+            supported,
+        };
 
-        Display { drv }
+        if supported && disp.attach() {
+            disp.health = Health::Ok;
+        }
+
+        disp
     }
 
-    pub fn draw_lcm_status(&mut self, status: &Status) {
-        // TODO - custom fmt for Status
+    pub fn health(&self) -> Health {
+        self.health
+    }
 
-        self.drv.clear();
+    // Periodically tries to re-attach a missing or lost display, call every
+    // loop
+    pub fn poll(&mut self, now: Instant) -> Health {
+        if self.health == Health::Ok || !self.supported {
+            return self.health;
+        }
 
-        let mut value_str: String<U32> = String::new();
+        match self.attempt {
+            Some(attempt) if now.since(attempt) < RETRY_MS => (),
+            Some(_) => {
+                self.attempt = Some(now);
+                if self.attach() {
+                    self.health = Health::Ok;
+                }
+            }
+            None => self.attempt = Some(now),
+        }
 
-        value_str.clear();
-        write!(value_str, " PWM: {}", status.pwm()).ok();
+        self.health
+    }
 
-        self.drv.draw(
-            Font6x8::render_str(&value_str)
-                .translate(Coord::new(0, 0))
-                .into_iter(),
-        );
+    pub fn set_contrast(&mut self, contrast: u8) {
+        if contrast != self.contrast {
+            self.contrast = contrast;
+            self.apply_power();
+        }
+    }
 
-        value_str.clear();
-        match status.state() {
-            State::Error => write!(value_str, "STAT: ERR").ok(),
-            State::Off => write!(value_str, "STAT: OFF").ok(),
-            State::On => write!(value_str, "STAT: ON").ok(),
-        };
+    pub fn set_power(&mut self, power: Power) {
+        if power != self.power {
+            self.power = power;
+            self.apply_power();
+        }
+    }
 
-        self.drv.draw(
-            Font6x8::render_str(&value_str)
-                .translate(Coord::new(68, 0))
-                .into_iter(),
-        );
+    // Only Rotate0 and Rotate180, the next draw sends the whole frame again
+    pub fn set_rotation(&mut self, rotation: DisplayRotation) {
+        if rotation == self.drv.get_rotation() {
+            return;
+        }
 
-        value_str.clear();
-        match status.freq() {
-            Freq::Continuous => write!(value_str, "FREQ: CONT").ok(),
-            Freq::Periodic(freq) => write!(value_str, "FREQ: {}", freq.0).ok(),
-        };
+        self.shown = None;
+        if self.drv.set_rotation(rotation).is_err() && self.health == Health::Ok {
+            self.lost();
+        }
+    }
 
-        self.drv.draw(
-            Font6x8::render_str(&value_str)
-                .translate(Coord::new(0, 12))
-                .into_iter(),
-        );
+    pub fn set_pixel_shift(&mut self, enabled: bool) {
+        self.pixel_shift = enabled;
+    }
 
-        value_str.clear();
-        match status.pwm_oe() {
-            true => write!(value_str, "  OE: ON").ok(),
-            false => write!(value_str, "  OE: OFF").ok(),
-        };
+    pub fn draw_splash(&mut self, logo: &Bitmap, lines: &[&str]) {
+        if !self.visible() {
+            return;
+        }
 
-        self.drv.draw(
-            Font6x8::render_str(&value_str)
-                .translate(Coord::new(0, 24))
-                .into_iter(),
-        );
+        self.shown = None;
+        screen::draw_splash(&mut self.drv, logo, lines);
+        self.flush();
+    }
 
-        value_str.clear();
-        match status.pwm_relay() {
-            true => write!(value_str, " RLY: ON").ok(),
-            false => write!(value_str, " RLY: OFF").ok(),
-        };
+    pub fn draw_prompt(&mut self, lines: &[&str]) {
+        if !self.visible() {
+            return;
+        }
 
-        self.drv.draw(
-            Font6x8::render_str(&value_str)
-                .translate(Coord::new(68, 24))
-                .into_iter(),
-        );
+        self.shown = None;
+        screen::draw_prompt(&mut self.drv, lines);
+        self.flush();
+    }
+
+    pub fn draw_menu<K>(&mut self, menu: &Menu<K>)
+    where
+        K: Copy + PartialEq,
+    {
+        if !self.visible() {
+            return;
+        }
+
+        self.shown = None;
+        screen::draw_menu(&mut self.drv, menu);
+        self.flush();
+    }
+
+    pub fn draw_lcm_status(&mut self, status: &Status, now: Instant) {
+        let offset = self.offset(now);
+        let shown = Some(Shown::Status(
+            *status,
+            StrobeIndicator::lit(status, now),
+            offset,
+        ));
+        if !self.visible() || self.shown == shown {
+            return;
+        }
+        self.shown = shown;
+
+        let mut canvas = Translate::new(&mut self.drv, Coord::new(offset.0, offset.1));
+        screen::draw_status(&mut canvas, status, now);
+        self.flush();
+    }
+
+    pub fn draw_trend<N>(&mut self, history: &History<N>, trace: Trace, now: Instant)
+    where
+        N: ArrayLength<Sample>,
+    {
+        let offset = self.offset(now);
+        let shown = Some(Shown::Trend(trace, history.recorded(), offset));
+        if !self.visible() || self.shown == shown {
+            return;
+        }
+        self.shown = shown;
+
+        let mut canvas = Translate::new(&mut self.drv, Coord::new(offset.0, offset.1));
+        screen::draw_trend(&mut canvas, history, trace);
+        self.flush();
+    }
+
+    // Pixel shift of the status and trend screens
+    fn offset(&self, now: Instant) -> (i32, i32) {
+        if self.pixel_shift {
+            SHIFT_OFFSETS[(now.millis() / SHIFT_MS) as usize % SHIFT_OFFSETS.len()]
+        } else {
+            (0, 0)
+        }
+    }
+
+    fn visible(&self) -> bool {
+        self.health == Health::Ok && self.power != Power::Off
+    }
+
+    fn attach(&mut self) -> bool {
+        self.shown = None;
+        GraphicsMode::clear(&mut self.drv);
+        self.drv
+            .init()
+            .and_then(|_| self.drv.flush())
+            .and_then(|_| self.send_power())
+            .is_ok()
+    }
+
+    fn apply_power(&mut self) {
+        if self.health == Health::Ok && self.send_power().is_err() {
+            self.lost();
+        }
+    }
+
+    fn send_power(&mut self) -> Result<(), ()> {
+        match self.power {
+            Power::On => self.drv.set_contrast(self.contrast),
+            Power::Dim => self.drv.set_contrast(CONTRAST_DIM),
+            Power::Off => return self.drv.display_on(false),
+        }?;
+        self.drv.display_on(true)
+    }
+
+    fn flush(&mut self) {
+        if self.drv.flush_changed().is_err() {
+            self.lost();
+        }
+    }
 
-        self.drv.flush().unwrap();
+    fn lost(&mut self) {
+        self.health = Health::Lost;
+        self.attempt = None;
+        self.shown = None;
     }
 }
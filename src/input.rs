@@ -1,59 +1,149 @@
 use core::cmp;
 use crate::debounce_input::DebounceInput;
-use embedded_hal::adc::{Channel, OneShot};
+use crate::supply::VDDA_NOMINAL_MV;
 use embedded_hal::digital::InputPin;
-use nb::block;
-use stm32f1xx_hal::adc::Adc;
-use stm32f1xx_hal::pac::ADC1;
+use heapless::consts::U8;
+use heapless::Vec;
+use lmc_input::calibration::Calibration;
+use lmc_input::filter::{Filter, FilterConfig};
+use lmc_input::plausibility::{Checker, InputFault, Plausibility};
+use lmc_input::scan::ScanBuffer;
+use stm32f1xx_hal::adc::Scan;
+
+pub use lmc_input::analog::{AnalogPin, Resolution};
+pub use lmc_input::button::{Button, ButtonSet};
+pub use lmc_input::scan::ScanPin;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Button {
-    B0,
-    B1,
-    B2,
+pub enum Error {
+    Full,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum AIn {
-    AIN0,
-    AIN1,
-}
+pub struct AIn(u8);
 
-pub struct Input<BTN0, BTN1, BTN2, AIN0, AIN1> {
-    btn0: BTN0,
-    btn1: BTN1,
-    btn2: BTN2,
-    adc: Adc<ADC1>,
-    ain0: AIN0,
-    ain1: AIN1,
-}
+// The HAL's DMA driven ADC1 scan, as the buffer ScanPins read from
+pub struct AdcScan<B: 'static>(pub Scan<B>);
 
-impl<BTN0, BTN1, BTN2, AIN0, AIN1> Input<BTN0, BTN1, BTN2, AIN0, AIN1>
+impl<B> ScanBuffer for AdcScan<B>
 where
-    BTN0: InputPin,
-    BTN1: InputPin,
-    BTN2: InputPin,
-    // TODO - make ADC generic
-    AIN0: Channel<ADC1, ID = u8>,
-    AIN1: Channel<ADC1, ID = u8>,
+    B: AsRef<[u16]> + AsMut<[u16]> + 'static,
 {
-    pub fn new(btn0: BTN0, btn1: BTN1, btn2: BTN2, adc: Adc<ADC1>, ain0: AIN0, ain1: AIN1) -> Self {
+    fn sequence_len(&self) -> usize {
+        self.0.sequence_len()
+    }
+
+    fn buffer_len(&self) -> usize {
+        self.0.buffer_len()
+    }
+
+    fn write_index(&self) -> usize {
+        self.0.write_index()
+    }
+
+    fn read(&self, index: usize) -> u16 {
+        self.0.read(index)
+    }
+
+    fn replace(&mut self, index: usize, value: u16) -> u16 {
+        self.0.replace(index, value)
+    }
+}
+
+struct ButtonEntry<'a> {
+    name: &'static str,
+    pin: &'a dyn InputPin,
+}
+
+// Button states as of the last update
+#[derive(Debug, Clone, Copy, Default)]
+struct ButtonStates {
+    held: ButtonSet,
+    pressed: ButtonSet,
+}
+
+struct AInEntry<'a, ADC> {
+    name: &'static str,
+    pin: &'a mut dyn AnalogPin<ADC>,
+    filter: Filter,
+    checker: Checker,
+    calibration: Calibration,
+    vdda_correction: bool,
+    value: u16,
+}
+
+pub struct Input<'a, ADC> {
+    buttons: Vec<ButtonEntry<'a>, U8>,
+    button_states: ButtonStates,
+    adc: ADC,
+    // The setpoints and the internal TEMP and VREF channels, with room for
+    // more inputs
+    ains: Vec<AInEntry<'a, ADC>, U8>,
+    vdda_mv: u16,
+}
+
+impl<'a, ADC> Input<'a, ADC> {
+    pub fn new(adc: ADC) -> Self {
         Input {
-            btn0,
-            btn1,
-            btn2,
+            buttons: Vec::new(),
+            button_states: ButtonStates::default(),
             adc,
-            ain0,
-            ain1,
+            ains: Vec::new(),
+            vdda_mv: VDDA_NOMINAL_MV,
         }
     }
 
+    pub fn add_button(
+        &mut self,
+        name: &'static str,
+        pin: &'a dyn InputPin,
+    ) -> Result<Button, Error> {
+        self.buttons
+            .push(ButtonEntry { name, pin })
+            .map_err(|_| Error::Full)?;
+        Ok(Button::new(self.buttons.len() as u8 - 1))
+    }
+
+    pub fn add_ain(
+        &mut self,
+        name: &'static str,
+        pin: &'a mut dyn AnalogPin<ADC>,
+        filter: FilterConfig,
+    ) -> Result<AIn, Error> {
+        let ain = AIn(self.ains.len() as u8);
+        let max = pin.resolution().max();
+        self.ains
+            .push(AInEntry {
+                name,
+                pin,
+                filter: Filter::new(filter, max),
+                checker: Checker::new(filter.oversample, max),
+                calibration: Calibration::full_scale(max),
+                vdda_correction: false,
+                value: 0,
+            })
+            .map_err(|_| Error::Full)?;
+        Ok(ain)
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = Button> {
+        (0..self.buttons.len() as u8).map(Button::new)
+    }
+
+    pub fn ains(&self) -> impl Iterator<Item = AIn> {
+        (0..self.ains.len() as u8).map(AIn)
+    }
+
+    pub fn button_name(&self, btn: Button) -> &'static str {
+        self.buttons[btn.index()].name
+    }
+
+    pub fn ain_name(&self, ain: AIn) -> &'static str {
+        self.ains[ain.0 as usize].name
+    }
+
     pub fn button(&self, btn: Button) -> bool {
-        match btn {
-            Button::B0 => self.btn0.is_low_debounce(),
-            Button::B1 => self.btn1.is_low_debounce(),
-            Button::B2 => self.btn2.is_low_debounce(),
-        }
+        self.buttons[btn.index()].pin.is_low_debounce()
     }
 
     pub fn button_wait(&self, btn: Button) -> bool {
@@ -65,18 +155,108 @@ where
         }
     }
 
-    pub fn ain(&mut self, ain: AIn) -> u16 {
-        match ain {
-            AIn::AIN0 => block!(self.adc.read(&mut self.ain0)).unwrap(),
-            AIn::AIN1 => block!(self.adc.read(&mut self.ain1)).unwrap(),
+    // Buttons held at the last update
+    pub fn held(&self) -> ButtonSet {
+        self.button_states.held
+    }
+
+    // True if the button went down between the last two updates
+    pub fn pressed(&self, btn: Button) -> bool {
+        self.button_states.pressed.contains(btn)
+    }
+
+    // Samples the buttons, and runs every sample taken since the last update
+    // through the filters
+    pub fn update(&mut self) {
+        let held = self
+            .buttons()
+            .filter(|&btn| self.button(btn))
+            .fold(ButtonSet::empty(), ButtonSet::with);
+        self.button_states = ButtonStates {
+            held,
+            pressed: held.difference(self.button_states.held),
+        };
+
+        let adc = &mut self.adc;
+        for entry in self.ains.iter_mut() {
+            for _ in 0..entry.pin.pending(adc) {
+                let sample = entry.pin.read(adc);
+
+                // Hold the last good value while faulted
+                if entry.checker.update(sample).is_some() {
+                    continue;
+                }
+
+                if let Some(value) = entry.filter.update(sample) {
+                    entry.value = value;
+                }
+            }
         }
     }
 
-    pub fn ain_map(&mut self, ain: AIn, out_min: u32, out_max: u32) -> u32 {
-        let in_min: u32 = 0;
-        let in_max: u32 = 4095;
-        let x = cmp::min(self.ain(ain) as u32, in_max);
+    // Latest filtered value, calibrated onto the full scale of the channel
+    pub fn ain(&self, ain: AIn) -> u16 {
+        let entry = &self.ains[ain.0 as usize];
+        entry
+            .calibration
+            .apply(self.ain_raw(ain), entry.pin.resolution().max())
+    }
+
+    // Latest filtered value, without calibration
+    pub fn ain_raw(&self, ain: AIn) -> u16 {
+        let entry = &self.ains[ain.0 as usize];
+        if !entry.vdda_correction {
+            return entry.value;
+        }
+
+        let max = entry.pin.resolution().max() as u32;
+        let value = entry.value as u32 * self.vdda_mv as u32 / VDDA_NOMINAL_MV as u32;
+        cmp::min(value, max) as u16
+    }
+
+    // Measured VDDA, the reference of the ADC
+    pub fn set_vdda(&mut self, vdda_mv: u16) {
+        self.vdda_mv = vdda_mv;
+    }
+
+    // Rescales the channel from the measured VDDA onto the nominal, for
+    // inputs referenced to a fixed voltage rather than VDDA itself
+    pub fn set_vdda_correction(&mut self, ain: AIn, enabled: bool) {
+        self.ains[ain.0 as usize].vdda_correction = enabled;
+    }
+
+    pub fn set_plausibility(&mut self, ain: AIn, plausibility: Plausibility) {
+        self.ains[ain.0 as usize].checker.set_config(plausibility);
+    }
+
+    pub fn plausibility(&self, ain: AIn) -> Plausibility {
+        self.ains[ain.0 as usize].checker.config()
+    }
+
+    pub fn ain_fault(&self, ain: AIn) -> Option<InputFault> {
+        self.ains[ain.0 as usize].checker.fault()
+    }
+
+    // Resumes faulted channels, they fault again if still implausible
+    pub fn clear_ain_faults(&mut self) {
+        for entry in self.ains.iter_mut() {
+            entry.checker.clear();
+        }
+    }
+
+    pub fn set_calibration(&mut self, ain: AIn, calibration: Calibration) {
+        self.ains[ain.0 as usize].calibration = calibration;
+    }
+
+    pub fn ain_overruns(&self, ain: AIn) -> u32 {
+        self.ains[ain.0 as usize].pin.overruns()
+    }
+
+    pub fn ain_errors(&self, ain: AIn) -> u32 {
+        self.ains[ain.0 as usize].pin.errors()
+    }
 
-        (x - in_min) * (out_max - out_min) / (in_max - in_min) + out_min
+    pub fn ain_max(&self, ain: AIn) -> u16 {
+        self.ains[ain.0 as usize].pin.resolution().max()
     }
 }
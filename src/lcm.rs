@@ -1,37 +1,17 @@
 use core::cmp;
 use crate::hal::pac::TIM2;
-use crate::hal::time::Hertz;
 use crate::hal::timer::Timer;
+use crate::supply::Limits;
 use embedded_hal::timer::CountDown;
 use embedded_hal::{blocking, digital};
 use pwm_pca9685::{Channel, OutputLogicState, Pca9685, SlaveAddr};
-// use crate::hal::timer::{Event as TimerEvent, Timer};
-// use crate::hal::pac::interrupt;
 
-const PWM_MAX: u16 = 4095;
-
-#[derive(Debug, Clone, Copy)]
-pub enum Freq {
-    Continuous,
-    Periodic(Hertz),
-}
+use lmc_types::fault::FaultLatch;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum State {
-    Error,
-    Off,
-    On,
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct Status {
-    // TODO - is state useful, what fails? low-level hw bits
-    state: State,
-    pwm: u16,
-    pwm_oe: bool,
-    pwm_relay: bool,
-    freq: Freq,
-}
+pub use lmc_types::fault::InputFaultAction;
+pub use lmc_types::status::{Fault, Freq, Health, State, Status, Supply, PWM_MAX};
+// use crate::hal::timer::{Event as TimerEvent, Timer};
+// use crate::hal::pac::interrupt;
 
 pub struct Lcm<I2C, OE, RLY> {
     pwm_drv: Pca9685<I2C>,
@@ -40,6 +20,10 @@ pub struct Lcm<I2C, OE, RLY> {
     timer: Timer<TIM2>,
     pwm: u16,
     freq: Freq,
+    supply: Option<Supply>,
+    limits: Limits,
+    faults: FaultLatch,
+    display: Health,
 }
 
 // TODO - make TIM/TIMER generic, but listen()/etc are not traits (yet)?
@@ -59,6 +43,10 @@ where
             timer,
             pwm: 0,
             freq: Freq::Continuous,
+            supply: None,
+            limits: Limits::default(),
+            faults: FaultLatch::new(),
+            display: Health::Missing,
         };
 
         lcm.relay_disable();
@@ -81,31 +69,75 @@ where
 
     pub fn status(&self) -> Status {
         // TODO
-        let state = if self.relay_enabled() {
+        let state = if self.fault().is_some() {
+            State::Error
+        } else if self.relay_enabled() {
             State::On
         } else {
             State::Off
         };
 
-        Status {
-            state,
-            pwm: self.pwm(),
-            pwm_oe: self.pwm_enabled(),
-            pwm_relay: self.relay_enabled(),
-            freq: self.freq(),
+        Status::new(state, self.pwm(), self.freq())
+            .with_outputs(self.pwm_enabled(), self.relay_enabled())
+            .with_supply(self.supply)
+            .with_fault(self.fault())
+            .with_display(self.display)
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    // Shuts the output down if the supply is outside the limits, the fault
+    // latches until cleared
+    pub fn set_supply(&mut self, supply: Option<Supply>) {
+        self.supply = supply;
+
+        if let Some(fault) = supply.and_then(|s| self.limits.check(&s)) {
+            self.raise(fault);
         }
     }
 
-    pub fn set_freq(&mut self, freq: Freq) {
-        self.freq = freq;
+    // Reported in the Status, the output runs regardless
+    pub fn set_display(&mut self, health: Health) {
+        self.display = health;
+    }
 
-        if let Freq::Periodic(_f) = self.freq {
-            // self.timer.start(f);
-            // self.timer.listen(TimerEvent::Update);
-        } else {
-            // self.timer.cancel();
-            // self.timer.unlisten(TimerEvent::Update);
+    pub fn set_input_fault_action(&mut self, action: InputFaultAction) {
+        if self.faults.set_input_action(action) {
+            self.pwm_disable();
+            self.relay_disable();
+        }
+    }
+
+    pub fn input_fault(&mut self) {
+        self.raise(Fault::Input);
+    }
+
+    fn raise(&mut self, fault: Fault) {
+        if self.faults.raise(fault) {
+            self.pwm_disable();
+            self.relay_disable();
+        }
+    }
+
+    fn shut_down(&self) -> bool {
+        self.faults.shut_down()
+    }
+
+    pub fn fault(&self) -> Option<Fault> {
+        self.faults.fault()
+    }
+
+    pub fn clear_fault(&mut self) {
+        self.faults.clear();
+    }
+
+    pub fn set_freq(&mut self, freq: Freq) {
+        if self.fault() == Some(Fault::Input) {
+            return;
         }
+        self.freq = freq;
     }
 
     pub fn freq(&self) -> Freq {
@@ -113,6 +145,9 @@ where
     }
 
     pub fn set_pwm(&mut self, pwm: u16) {
+        if self.fault() == Some(Fault::Input) {
+            return;
+        }
         self.pwm = cmp::min(pwm, PWM_MAX);
 
         self.pwm_drv.set_channel_on(Channel::All, 0).unwrap();
@@ -134,6 +169,9 @@ where
     }
 
     pub fn pwm_enable(&mut self) {
+        if self.shut_down() {
+            return;
+        }
         self.pwm_oe.set_low();
     }
 
@@ -146,28 +184,9 @@ where
     }
 
     pub fn relay_enable(&mut self) {
+        if self.shut_down() {
+            return;
+        }
         self.pwm_relay.set_high();
     }
 }
-
-impl Status {
-    pub fn state(&self) -> State {
-        self.state
-    }
-
-    pub fn pwm(&self) -> u16 {
-        self.pwm
-    }
-
-    pub fn pwm_oe(&self) -> bool {
-        self.pwm_oe
-    }
-
-    pub fn pwm_relay(&self) -> bool {
-        self.pwm_relay
-    }
-
-    pub fn freq(&self) -> Freq {
-        self.freq
-    }
-}
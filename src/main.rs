@@ -4,46 +4,258 @@
 extern crate cortex_m_rt as rt;
 extern crate stm32f1xx_hal as hal;
 
+mod assets;
+mod clock;
 mod debounce_input;
 mod display;
 mod input;
 mod lcm;
+mod rs485;
+mod serial;
+mod setpoint;
+mod storage;
+mod supply;
 
 use core::fmt::Write;
-use crate::display::Display;
-use crate::hal::adc::Adc;
+use crate::clock::{Clock, Instant};
+use crate::display::{Display, Power, CONTRAST_DEFAULT};
+use crate::hal::adc::{Adc, SampleTime, Trigger, CHANNEL_TEMPERATURE, CHANNEL_VREFINT};
 use crate::hal::gpio::State;
 use crate::hal::i2c::{BlockingI2c, Mode};
 use crate::hal::iwdg::{Iwdg, IwdgConfig, WatchdogTimeout};
 use crate::hal::pac as stm32;
-use crate::hal::pac::{TIM2, USART2};
+use crate::hal::pac::{IWDG, TIM2};
 use crate::hal::prelude::*;
-use crate::hal::serial::{Rx, Serial, Tx};
-use crate::hal::time::Hertz;
+use crate::hal::serial::Serial;
 use crate::hal::timer::Timer;
-use crate::input::{AIn, Button, Input};
-use crate::lcm::{Freq, Lcm};
+use crate::input::{AIn, AdcScan, Button, ButtonSet, Input, Resolution, ScanPin};
+use crate::lcm::{Freq, InputFaultAction, Lcm, PWM_MAX};
+use crate::rs485::Rs485;
 use crate::rt::{entry, exception, ExceptionFrame};
-use nb::block;
+use crate::serial::BufferedSerial;
+use crate::setpoint::Setpoints;
+use crate::storage::{ModbusSettings, Settings, Storage};
+use crate::supply::{Limits, TEMPERATURE_MAX, VDDA_MIN_MV};
+use cortex_m::peripheral::DWT;
+use cortex_m::singleton;
+use embedded_hal::blocking;
+use heapless::consts::{U128, U16, U32};
+use heapless::{String, Vec};
+use lmc_input::calibration::{Calibration, Calibrator, Step};
+use lmc_input::filter::FilterConfig;
+use lmc_input::gesture::{Gesture, Gestures};
+use lmc_input::mapping::{Clamp, Curve, CurveMap, LinearMap};
+use lmc_input::plausibility::Plausibility;
+use lmc_modbus::rtu::Parity;
+use lmc_modbus::slave::{self, Diagnostics, Slave};
+use lmc_proto::link::{Link, Received};
+use lmc_proto::packet::{self, Frame, Response};
+use lmc_ui::history::{History, Sample, Trace};
+use lmc_ui::menu::{Event, Field, Menu};
+use lmc_ui::shell::{Command, Level, Refusal, Shell};
+use lmc_ui::telemetry::Publisher;
 use panic_semihosting;
+use ssd1306::prelude::{DisplayRotation, DisplaySize};
 // use crate::hal::pac::{interrupt, Interrupt, TIM2, USART2};
 
+// ADC1 scans the setpoint channels, then AIN_INTERNAL, each sampled at
+// AIN_SAMPLE_RATE with the last AIN_SCAN_DEPTH scans kept in the DMA buffer.
+// A loop slower than the buffer (320 ms) loses samples, counted as overruns.
+const AIN_INTERNAL: [u8; 2] = [CHANNEL_TEMPERATURE, CHANNEL_VREFINT];
+const AIN_SAMPLE_RATE: u32 = 200;
+const AIN_SCAN_DEPTH: usize = 64;
+const AIN_BUFFER_LEN: usize = (setpoint::CHANNELS.len() + AIN_INTERNAL.len()) * AIN_SCAN_DEPTH;
+
+// What to do when a setpoint input faults, the first is the default
+const INPUT_FAULT_OPTIONS: [&str; 2] = ["OFF", "HOLD"];
+const INPUT_FAULT_ACTIONS: [InputFaultAction; 2] = [InputFaultAction::Off, InputFaultAction::Hold];
+
+// Without button activity the display dims, then turns off (s, 0 never)
+const DISPLAY_DIM_S: u32 = 60;
+const DISPLAY_OFF_S: u32 = 600;
+
+const VERSION: &str = concat!("LMC ", env!("CARGO_PKG_VERSION"));
+const BUILD: &str = concat!("BUILD ", env!("LMC_BUILD"));
+
+const ON_OFF: [&str; 2] = ["OFF", "ON"];
+
+// 128x32 panel unless built with the oled-128x64 feature
+#[cfg(not(feature = "oled-128x64"))]
+const DISPLAY_SIZE: DisplaySize = DisplaySize::Display128x32;
+#[cfg(feature = "oled-128x64")]
+const DISPLAY_SIZE: DisplaySize = DisplaySize::Display128x64;
+
+// How the panel is mounted, the first is the default
+const ROTATION_OPTIONS: [&str; 2] = ["180", "0"];
+const ROTATIONS: [DisplayRotation; 2] = [DisplayRotation::Rotate180, DisplayRotation::Rotate0];
+
+// The home page shows the status or a trend graph of one trace. The graph
+// keeps the last 128 samples, a minute at the default interval (ms).
+const HOME_OPTIONS: [&str; 2] = ["STATUS", "TREND"];
+const TRACE_OPTIONS: [&str; 4] = ["PWM", "PWM IN", "FREQ IN", "TEMP"];
+const TRACES: [Trace; 4] = [
+    Trace::Pwm,
+    Trace::PwmInput,
+    Trace::FreqInput,
+    Trace::Temperature,
+];
+const TREND_INTERVAL_MS: u32 = 500;
+
+// Modbus RTU slave on RS-485, the first baud rate and parity are the
+// defaults. Without parity there are two stop bits.
+const MODBUS_ADDRESS: u8 = 1;
+const MODBUS_BAUD_OPTIONS: [&str; 5] = ["19200", "9600", "38400", "57600", "115200"];
+const MODBUS_BAUDS: [u32; 5] = [19_200, 9600, 38_400, 57_600, 115_200];
+const MODBUS_PARITY_OPTIONS: [&str; 3] = ["EVEN", "ODD", "NONE"];
+const MODBUS_PARITIES: [Parity; 3] = [Parity::Even, Parity::Odd, Parity::None];
+
+// OE from the shell, the link or Modbus lapses unless it's sent again
+// within this long (ms), so a host that goes away doesn't leave it on
+const REMOTE_OE_MS: u32 = 500;
+
+// Telemetry records, once enabled from the shell (ms)
+const TELEMETRY_INTERVAL_MS: u32 = 1000;
+
+// Logo and version shown at boot (ms)
+const SPLASH_MS: u32 = 2000;
+
+// Top of the strobe rate taper (Hz)
+const FREQ_MAX: u32 = 100;
+
+// Name, PWM and strobe rate (Hz, 0 is continuous). Presets, and setpoints
+// from the shell, hold until either setpoint input moves by more than
+// 1/PRESET_RELEASE of its range.
+const PRESETS: [(&str, u16, u32); 4] = [
+    ("DIM", 410, 0),
+    ("HALF", 2048, 0),
+    ("FULL", PWM_MAX, 0),
+    ("STROBE 10HZ", PWM_MAX, 10),
+];
+const PRESET_RELEASE: i32 = 32;
+
+// Chords are held for CHORD_HOLD_MS, each press of a sequence follows the
+// previous within SEQUENCE_WINDOW_MS. A single button acts when released, or
+// once held on its own for CHORD_WINDOW_MS, so it can start a chord instead.
+const CHORD_HOLD_MS: u32 = 3000;
+const CHORD_WINDOW_MS: u32 = 250;
+const MENU_HOLD_MS: u32 = 1000;
+const SEQUENCE_WINDOW_MS: u32 = 400;
+
+// Back to the status page after this long without a button press
+const MENU_TIMEOUT_MS: u32 = 30_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Press(Button),
+    ServiceMode,
+    Menu,
+    Calibrate,
+    FactoryReset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    PwmMax,
+    FreqMax,
+    InputFault,
+    Preset(u8),
+    Diagnostics(u8),
+    VddaMin,
+    TemperatureMax,
+    Calibrate,
+    FactoryReset,
+    Contrast,
+    DimAfter,
+    OffAfter,
+    PixelShift,
+    Rotation,
+    Home,
+    Trace,
+    TrendInterval,
+    ModbusAddress,
+    ModbusBaud,
+    ModbusParity,
+    About,
+}
+
+// Where a command came from, protocol requests carry their sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Shell,
+    Link(u8),
+    Modbus,
+}
+
 // TODO - bsp.rs with pin type mappings for the nucleo-64 board
 // use crate::hal::gpioa::{PA2, PA3};
 // type PwmI2c = BlockingI2c<I2C1, (PB8<Alternate<OpenDrain>>,
 // PB9<Alternate<OpenDrain>>)>; type PwmOePin = PB3<Output<PushPull>>;
 // type PwmRelayPin = PB5<Output<PushPull>>;
 
+// writeln! to the console if it shows messages at the level
+macro_rules! log {
+    ($out:expr, $level:expr, $($arg:tt)*) => {
+        if $out.enabled($level) {
+            writeln!($out, $($arg)*).ok();
+        }
+    };
+}
+
 // struct DebugConsole(Serial<stm32::USART2, (PA2, PA3)>);
 struct DebugConsole {
-    tx: Tx<USART2>,
-    _rx: Rx<USART2>,
+    serial: BufferedSerial,
+    level: Level,
+    // Text is sent a line at a time, so a full TX ring drops whole lines.
+    // Lines longer than this are sent in pieces, any of which may be dropped.
+    line: String<U128>,
+}
+
+impl DebugConsole {
+    fn enabled(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    // Sent whole or not at all, returns which
+    fn write_whole(&mut self, bytes: &[u8]) -> bool {
+        self.send_line();
+        self.serial.write(bytes)
+    }
+
+    fn write_frame(&mut self, frame: &Frame) {
+        self.write_whole(frame.as_bytes());
+    }
+
+    // Next received byte, None when there are no more. Sends a partial line
+    // first, such as the shell's echo and prompt.
+    fn read(&mut self) -> Option<u8> {
+        self.send_line();
+        self.serial.read()
+    }
+
+    // Waits until everything written is on the line
+    fn flush(&mut self) {
+        self.send_line();
+        self.serial.flush();
+    }
+
+    fn send_line(&mut self) {
+        if !self.line.is_empty() {
+            self.serial.write(self.line.as_bytes());
+            self.line.clear();
+        }
+    }
 }
 
 impl Write for DebugConsole {
     fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
-        for &b in s.as_bytes() {
-            block!(self.tx.write(b as _)).ok();
+        for c in s.chars() {
+            if self.line.push(c).is_err() {
+                self.send_line();
+                self.line.push(c).ok();
+            }
+            if c == '\n' {
+                self.send_line();
+            }
         }
         Ok(())
     }
@@ -51,8 +263,7 @@ impl Write for DebugConsole {
 
 #[entry]
 fn main() -> ! {
-    // let cp = cortex_m::peripheral::Peripherals::take().expect("Failed to take
-    // cm::Peripherals");
+    let cp = cortex_m::Peripherals::take().expect("Failed to take cortex_m::Peripherals");
     let p = stm32::Peripherals::take().expect("Failed to take stm32::Peripherals");
 
     let mut flash = p.FLASH.constrain();
@@ -64,6 +275,17 @@ fn main() -> ! {
         .pclk1(32.mhz())
         .freeze(&mut flash.acr);
 
+    let clock = Clock::new(cp.SYST, clocks);
+    let mut scb = cp.SCB;
+    let mut nvic = cp.NVIC;
+
+    // Cycle counter for the loop time
+    let mut dcb = cp.DCB;
+    dcb.enable_trace();
+    let mut dwt = cp.DWT;
+    dwt.enable_cycle_counter();
+    let cycles_per_us = clocks.sysclk().0 / 1_000_000;
+
     let mut wdt = Iwdg::new(p.IWDG, IwdgConfig::from(WatchdogTimeout::Wdto500ms));
 
     let mut afio = p.AFIO.constrain(&mut rcc.apb2);
@@ -87,9 +309,78 @@ fn main() -> ! {
         &mut rcc.apb1,
     );
 
-    let (tx, rx) = serial.split();
-    let mut stdout = DebugConsole { tx, _rx: rx };
+    let mut stdout = DebugConsole {
+        serial: BufferedSerial::new(serial, &mut nvic),
+        level: Level::Info,
+        line: String::new(),
+    };
+
+    // USART3 on PC10 and PC11, RS-485 DE on PC12
+    let mut gpioc = p.GPIOC.split(&mut rcc.apb2);
+    let rs485_tx = gpioc.pc10.into_alternate_push_pull(&mut gpioc.crh);
+    let rs485_rx = gpioc.pc11;
+    let rs485_de = gpioc
+        .pc12
+        .into_push_pull_output_with_state(&mut gpioc.crh, State::Low);
+
+    let rs485_serial = Serial::usart3(
+        p.USART3,
+        (rs485_tx, rs485_rx),
+        &mut afio.mapr,
+        MODBUS_BAUDS[0].bps(),
+        clocks,
+        &mut rcc.apb1,
+    );
+    let mut modbus_format = (MODBUS_BAUDS[0], MODBUS_PARITIES[0]);
+    let mut rs485 = Rs485::new(
+        rs485_serial,
+        rs485_de,
+        modbus_format.0,
+        modbus_format.1,
+        clocks,
+        &mut nvic,
+    );
+
+    // TIM3 paces the ADC1 scan of the setpoint channels and the internal
+    // temperature sensor and VREFINT channels, DMA1 channel 1
+    // streams the conversions into a circular buffer
+    let mut ain_timer = Timer::tim3(p.TIM3, AIN_SAMPLE_RATE.hz(), clocks, &mut rcc.apb1);
+    ain_timer.trigger_on_update();
+
+    let dma1 = p.DMA1.split(&mut rcc.ahb);
+    let ain_buffer = singleton!(: [u16; AIN_BUFFER_LEN] = [0; AIN_BUFFER_LEN]).unwrap();
+
+    let ain_sequence: Vec<u8, U16> = setpoint::CHANNELS
+        .iter()
+        .chain(AIN_INTERNAL.iter())
+        .cloned()
+        .collect();
+
+    let adc = Adc::adc1(p.ADC1, &mut rcc.apb2).scan(
+        &ain_sequence,
+        SampleTime::Cycles239_5,
+        Trigger::Tim3Trgo,
+        dma1.1,
+        ain_buffer,
+    );
+
+    let mut temp_in = ScanPin::new(setpoint::CHANNELS.len(), Resolution::bits(12));
+    let mut vref_in = ScanPin::new(setpoint::CHANNELS.len() + 1, Resolution::bits(12));
+
+    // PA10, D2
+    // PA8, D7
+    // PA9, D8
+    let btn0_in = gpioa.pa10.into_pull_up_input(&mut gpioa.crh);
+    let btn1_in = gpioa.pa8.into_pull_up_input(&mut gpioa.crh);
+    let btn2_in = gpioa.pa9.into_pull_up_input(&mut gpioa.crh);
+
+    let mut input = Input::new(AdcScan(adc));
 
+    let btn_off = input.add_button("B0", &btn0_in).unwrap();
+    let btn_on = input.add_button("B1", &btn1_in).unwrap();
+    let btn_oe = input.add_button("B2", &btn2_in).unwrap();
+
+    // The pots, or the encoder which takes PB6 and moves PWM OE to PB12
     // PB4, D5
     // PB5, D4
     // PB3, D3
@@ -99,9 +390,36 @@ fn main() -> ! {
     //    let pwm_oe = gpiob
     //        .pb3
     //        .into_push_pull_output_with_state(&mut gpiob.crl, State::High);
-    let pwm_oe = gpiob
-        .pb6
-        .into_push_pull_output_with_state(&mut gpiob.crl, State::High);
+    #[cfg(not(feature = "encoder"))]
+    let (mut setpoints, pwm_oe) = (
+        setpoint::Pots::new(gpioa.pa0, gpioa.pa1, &mut gpioa.crl, &mut input),
+        gpiob
+            .pb6
+            .into_push_pull_output_with_state(&mut gpiob.crl, State::High),
+    );
+    #[cfg(feature = "encoder")]
+    let (mut setpoints, pwm_oe) = (
+        setpoint::Knob::new(
+            p.TIM4,
+            (gpiob.pb6, gpiob.pb7),
+            gpioa.pa6,
+            &mut gpioa.crl,
+            &mut afio.mapr,
+            &mut rcc.apb1,
+            &mut input,
+        ),
+        gpiob
+            .pb12
+            .into_push_pull_output_with_state(&mut gpiob.crh, State::High),
+    );
+    let [ain_pwm, ain_freq] = setpoints.ains();
+
+    let ain_temp = input
+        .add_ain("TEMP", &mut temp_in, FilterConfig::default())
+        .expect("No room for the TEMP input");
+    let ain_vref = input
+        .add_ain("VREF", &mut vref_in, FilterConfig::default())
+        .expect("No room for the VREF input");
 
     let pwm_relay = gpiob
         .pb5
@@ -146,75 +464,878 @@ fn main() -> ! {
         1000,
     );
 
-    let mut disp = Display::new(disp_i2c);
-
-    // ADC_0, PA0, A0
-    // ADC_1, PA1, A1
-    // ADC_4, PA4, A2
-    let ain0 = gpioa.pa0.into_analog(&mut gpioa.crl);
-    let ain1 = gpioa.pa1.into_analog(&mut gpioa.crl);
+    let mut disp = Display::new(disp_i2c, DISPLAY_SIZE, ROTATIONS[0]);
+    log!(stdout, Level::Info, "Display: {}", disp.health());
+    disp.draw_splash(&assets::LOGO, &[VERSION, BUILD]);
+    let mut splash = Some(clock.now());
+    lcm.set_display(disp.health());
 
-    let adc = Adc::adc1(p.ADC1, &mut rcc.apb2);
+    log!(stdout, Level::Info, "Starting");
 
-    // PA10, D2
-    // PA8, D7
-    // PA9, D8
-    let btn0_in = gpioa.pa10.into_pull_up_input(&mut gpioa.crh);
-    let btn1_in = gpioa.pa8.into_pull_up_input(&mut gpioa.crh);
-    let btn2_in = gpioa.pa9.into_pull_up_input(&mut gpioa.crh);
+    let mut storage = Storage::new(flash.writer);
+    let mut settings = storage.load().unwrap_or_default();
+    log!(stdout, Level::Debug, "Settings: {:?}", settings);
 
-    let mut input = Input::new(btn0_in, btn1_in, btn2_in, adc, ain0, ain1);
+    for btn in input.buttons() {
+        log!(stdout, Level::Debug, "Button: {}", input.button_name(btn));
+    }
 
-    writeln!(stdout, "Starting").ok();
+    for ain in input.ains() {
+        log!(
+            stdout,
+            Level::Debug,
+            "Analog input: {}",
+            input.ain_name(ain)
+        );
+    }
 
     // TODO
-    // let mut nvic = cp.NVIC;
     // nvic.enable(Interrupt::TIM2);
     // unsafe { nvic.set_priority(Interrupt::TIM2, 1) };
     // cortex_m::peripheral::NVIC::unpend(Interrupt::TIM2);
 
+    // Hold B0 through power up to calibrate the end stops of each pot
+    if setpoints.calibrates() {
+        if let Some([pwm, freq]) = settings.calibration {
+            input.set_calibration(ain_pwm, pwm);
+            input.set_calibration(ain_freq, freq);
+        }
+
+        input.update();
+        if input.button(btn_off) {
+            settings.calibration = Some(calibrate(
+                &mut input,
+                [ain_pwm, ain_freq],
+                btn_on,
+                &mut disp,
+                &wdt,
+                &mut stdout,
+            ));
+            save(&mut storage, &settings, &mut stdout);
+        }
+    }
+
+    // B0 three times toggles service mode, which shows the service page and
+    // unlocks the calibration and factory reset chords. B1+B2 opens the menu.
+    // Buttons that start a chord do not act on their own.
+    let chord = |a, b| ButtonSet::empty().with(a).with(b);
+    let mut gestures = Gestures::new();
+    for &btn in [btn_off, btn_on, btn_oe].iter() {
+        gestures
+            .add(Gesture::Press(btn, CHORD_WINDOW_MS), Action::Press(btn))
+            .unwrap();
+    }
+    gestures
+        .add(
+            Gesture::Sequence(btn_off, 3, SEQUENCE_WINDOW_MS),
+            Action::ServiceMode,
+        )
+        .unwrap();
+    gestures
+        .add(
+            Gesture::Chord(chord(btn_on, btn_oe), MENU_HOLD_MS),
+            Action::Menu,
+        )
+        .unwrap();
+    if setpoints.calibrates() {
+        gestures
+            .add(
+                Gesture::Chord(chord(btn_off, btn_on), CHORD_HOLD_MS),
+                Action::Calibrate,
+            )
+            .unwrap();
+    }
+    gestures
+        .add(
+            Gesture::Chord(chord(btn_off, btn_oe), CHORD_HOLD_MS),
+            Action::FactoryReset,
+        )
+        .unwrap();
+
+    // Status page buttons drive the LCM, on the other pages B0 and B2 move
+    // the cursor and B1 selects
+    let mut menu = Menu::new();
+    let page_status = menu.add_page("STATUS").unwrap();
+
+    let page_settings = menu.add_page("SETTINGS").unwrap();
+    menu.add_item(
+        page_settings,
+        Key::PwmMax,
+        "PWM MAX",
+        Field::Number {
+            value: PWM_MAX as i32,
+            min: 0,
+            max: PWM_MAX as i32,
+            step: 64,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_settings,
+        Key::FreqMax,
+        "FREQ MAX",
+        Field::Number {
+            value: FREQ_MAX as i32,
+            min: 1,
+            max: FREQ_MAX as i32,
+            step: 1,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_settings,
+        Key::InputFault,
+        "IN FAULT",
+        Field::Choice {
+            index: 0,
+            options: &INPUT_FAULT_OPTIONS,
+        },
+    )
+    .unwrap();
+
+    let page_display = menu.add_page("DISPLAY").unwrap();
+    menu.add_item(
+        page_display,
+        Key::Contrast,
+        "CONTRAST",
+        Field::Number {
+            value: CONTRAST_DEFAULT as i32,
+            min: 1,
+            max: 255,
+            step: 16,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_display,
+        Key::DimAfter,
+        "DIM S",
+        Field::Number {
+            value: DISPLAY_DIM_S as i32,
+            min: 0,
+            max: 3600,
+            step: 30,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_display,
+        Key::OffAfter,
+        "OFF S",
+        Field::Number {
+            value: DISPLAY_OFF_S as i32,
+            min: 0,
+            max: 3600,
+            step: 60,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_display,
+        Key::PixelShift,
+        "PIXEL SHIFT",
+        Field::Choice {
+            index: 1,
+            options: &ON_OFF,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_display,
+        Key::Rotation,
+        "ROTATION",
+        Field::Choice {
+            index: 0,
+            options: &ROTATION_OPTIONS,
+        },
+    )
+    .unwrap();
+
+    let page_trend = menu.add_page("TREND").unwrap();
+    menu.add_item(
+        page_trend,
+        Key::Home,
+        "HOME",
+        Field::Choice {
+            index: 0,
+            options: &HOME_OPTIONS,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_trend,
+        Key::Trace,
+        "TRACE",
+        Field::Choice {
+            index: 0,
+            options: &TRACE_OPTIONS,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_trend,
+        Key::TrendInterval,
+        "SAMPLE MS",
+        Field::Number {
+            value: TREND_INTERVAL_MS as i32,
+            min: 100,
+            max: 2000,
+            step: 100,
+        },
+    )
+    .unwrap();
+
+    let page_modbus = menu.add_page("MODBUS").unwrap();
+    menu.add_item(
+        page_modbus,
+        Key::ModbusAddress,
+        "ADDRESS",
+        Field::Number {
+            value: MODBUS_ADDRESS as i32,
+            min: 1,
+            max: 247,
+            step: 1,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_modbus,
+        Key::ModbusBaud,
+        "BAUD",
+        Field::Choice {
+            index: 0,
+            options: &MODBUS_BAUD_OPTIONS,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_modbus,
+        Key::ModbusParity,
+        "PARITY",
+        Field::Choice {
+            index: 0,
+            options: &MODBUS_PARITY_OPTIONS,
+        },
+    )
+    .unwrap();
+    if let Some(modbus) = settings.modbus {
+        let baud = MODBUS_BAUDS.iter().position(|&b| b == modbus.baud);
+        let parity = MODBUS_PARITIES.iter().position(|&p| p == modbus.parity);
+        menu.set_number(Key::ModbusAddress, modbus.address as i32);
+        menu.set_choice(Key::ModbusBaud, baud.unwrap_or(0));
+        menu.set_choice(Key::ModbusParity, parity.unwrap_or(0));
+    }
+
+    let page_presets = menu.add_page("PRESETS").unwrap();
+    for (i, preset) in PRESETS.iter().enumerate() {
+        menu.add_item(page_presets, Key::Preset(i as u8), preset.0, Field::Action)
+            .unwrap();
+    }
+
+    let page_diagnostics = menu.add_page("DIAGNOSTICS").unwrap();
+    for i in 0..3 {
+        menu.add_item(
+            page_diagnostics,
+            Key::Diagnostics(i),
+            "",
+            Field::Text(String::new()),
+        )
+        .unwrap();
+    }
+
+    let page_service = menu.add_page("SERVICE").unwrap();
+    menu.set_hidden(page_service, true);
+    menu.add_item(
+        page_service,
+        Key::VddaMin,
+        "UV MV",
+        Field::Number {
+            value: VDDA_MIN_MV as i32,
+            min: 2000,
+            max: 3600,
+            step: 50,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page_service,
+        Key::TemperatureMax,
+        "OT C",
+        Field::Number {
+            value: TEMPERATURE_MAX as i32,
+            min: 40,
+            max: 100,
+            step: 5,
+        },
+    )
+    .unwrap();
+    if setpoints.calibrates() {
+        menu.add_item(page_service, Key::Calibrate, "CALIBRATE", Field::Action)
+            .unwrap();
+    }
+    menu.add_item(
+        page_service,
+        Key::FactoryReset,
+        "FACTORY RESET",
+        Field::Action,
+    )
+    .unwrap();
+
+    let page_about = menu.add_page("ABOUT").unwrap();
+    for line in [VERSION, BUILD, "LCM CONTROLLER", "STM32F103"].iter() {
+        menu.add_item(page_about, Key::About, "", Field::Text(String::from(*line)))
+            .unwrap();
+    }
+
+    let mut service = false;
+    let mut menu_activity = clock.now();
+    // PWM and strobe rate (Hz) held, and the inputs when they were set
+    let mut hold: Option<(u16, u32, i32, i32)> = None;
+    // When OE was last asked for remotely
+    let mut remote_oe: Option<Instant> = None;
+    let mut shell = Shell::new();
+    let mut link = Link::new();
+    let mut modbus = Slave::new(MODBUS_ADDRESS);
+    // Being carried out, all of its requests in the same pass
+    let mut modbus_write: Option<slave::Write> = None;
+    let mut logged_state = None;
+    let mut activity = clock.now();
+    let mut history: History<U128> = History::new(TREND_INTERVAL_MS);
+    let mut telemetry = Publisher::new(TELEMETRY_INTERVAL_MS);
+
     // Wait for all buttons
-    let _ = input.button_wait(Button::B0);
-    let _ = input.button_wait(Button::B1);
-    let _ = input.button_wait(Button::B2);
+    for btn in input.buttons() {
+        let _ = input.button_wait(btn);
+    }
     cortex_m::asm::delay(2000);
 
+    shell.prompt(&mut stdout);
+
     led.set_low();
+    let mut loop_start = DWT::get_cycle_count();
     loop {
         wdt.refresh();
 
-        if input.button(Button::B2) {
-            // if input.button_wait(Button::B2) {
-            // if lcm.pwm_enabled() {
-            //    lcm.pwm_disable();
-            // } else {
-            //    lcm.pwm_enable();
-            // }
+        let cycles = DWT::get_cycle_count();
+        let loop_us = cycles.wrapping_sub(loop_start) / cycles_per_us;
+        loop_start = cycles;
+
+        let turned = setpoints.update(clock.now(), &input);
+
+        input.update();
+
+        let held = input.held();
+        let home = menu.page() == page_status;
+
+        // Any button wakes the display, the buttons keep working while it
+        // sleeps
+        if !held.is_empty() || turned {
+            activity = clock.now();
+        }
+
+        let supply = supply::measure(
+            input.ain(ain_vref),
+            input.ain(ain_temp),
+            input.ain_max(ain_vref),
+        );
+        if let Some(supply) = supply {
+            input.set_vdda(supply.vdda_mv());
+        }
+        lcm.set_supply(supply);
+
+        let health = disp.poll(clock.now());
+        if health != lcm.status().display() {
+            log!(stdout, Level::Info, "Display: {}", health);
+        }
+        lcm.set_display(health);
+
+        for &ain in [ain_pwm, ain_freq].iter() {
+            if let Some(fault) = input.ain_fault(ain) {
+                if lcm.fault().is_none() {
+                    log!(stdout, Level::Error, "{}: {:?}", input.ain_name(ain), fault);
+                }
+                lcm.input_fault();
+            }
+        }
+
+        // Menu actions and their gesture shortcuts
+        let mut selected = None;
+
+        // Single buttons only act once they can't be the start of a chord
+        let gesture = gestures.update(clock.now(), held);
+        let press = |btn| gesture == Some(Action::Press(btn));
+
+        match gesture {
+            Some(Action::ServiceMode) => {
+                service = !service;
+                menu.set_hidden(page_service, !service);
+                menu.set_page(if service { page_service } else { page_status });
+                menu_activity = clock.now();
+                log!(stdout, Level::Info, "Service mode: {}", service);
+            }
+            Some(Action::Menu) if home => {
+                menu.set_page(page_settings);
+                menu_activity = clock.now();
+            }
+            Some(Action::Calibrate) if service => selected = Some(Key::Calibrate),
+            Some(Action::FactoryReset) if service => selected = Some(Key::FactoryReset),
+            _ => (),
+        }
 
-            lcm.pwm_enable();
+        // Remote OE lasts until it isn't refreshed, a button is pressed, the
+        // menu opens or the LCM faults
+        let lapsed = remote_oe.is_some_and(|t| clock.now().since(t) > REMOTE_OE_MS);
+        if lapsed || !held.is_empty() || !home || lcm.fault().is_some() {
+            remote_oe = None;
+        }
+
+        if home {
+            if gestures.solo(clock.now(), btn_oe, CHORD_WINDOW_MS) || remote_oe.is_some() {
+                // if input.button_wait(Button::B2) {
+                // if lcm.pwm_enabled() {
+                //    lcm.pwm_disable();
+                // } else {
+                //    lcm.pwm_enable();
+                // }
+
+                lcm.pwm_enable();
+            } else {
+                lcm.pwm_disable();
+            }
+
+            if press(btn_on) {
+                lcm.pwm_disable();
+                lcm.relay_enable();
+                led.set_high();
+            }
+
+            if press(btn_off) {
+                led.set_low();
+                lcm.pwm_disable();
+                lcm.relay_disable();
+                lcm.clear_fault();
+                input.clear_ain_faults();
+            }
         } else {
             lcm.pwm_disable();
+
+            let event = if press(btn_off) {
+                Some(Event::Up)
+            } else if press(btn_oe) {
+                Some(Event::Down)
+            } else if press(btn_on) {
+                Some(Event::Select)
+            } else {
+                None
+            };
+
+            match event {
+                Some(event) => {
+                    menu_activity = clock.now();
+                    selected = selected.or(menu.handle(event));
+                }
+                None if clock.now().since(menu_activity) > MENU_TIMEOUT_MS => {
+                    menu.set_page(page_status);
+                }
+                None => (),
+            }
         }
 
-        if input.button_wait(Button::B1) {
-            lcm.pwm_disable();
-            lcm.relay_enable();
-            led.set_high();
+        // Shell commands, protocol requests and Modbus writes go through the
+        // same interlocks as the buttons
+        let pwm_max = menu.number(Key::PwmMax).max(0) as u16;
+        let freq_max = menu.number(Key::FreqMax).max(0) as u32;
+        let check = |command: Command| command.check(!home, pwm_max, freq_max, PRESETS.len());
+        loop {
+            let (source, command) = if let Some(write) = modbus_write.as_mut() {
+                match write.next_request().and_then(Command::from_request) {
+                    Some(command) => (Source::Modbus, command),
+                    // All carried out
+                    None => {
+                        if let Some(reply) = modbus.written(write, Ok(())) {
+                            rs485.send(reply);
+                        }
+                        modbus_write = None;
+                        continue;
+                    }
+                }
+            } else if let Some(frame) = rs485.receive() {
+                let diagnostics = Diagnostics {
+                    status: lcm.status(),
+                    inputs: [input.ain(ain_pwm), input.ain(ain_freq)],
+                    loop_us,
+                    faults: telemetry.faults(),
+                };
+                match modbus.receive(&frame, &diagnostics) {
+                    slave::Received::Nothing => (),
+                    slave::Received::Reply(reply) => rs485.send(reply),
+                    // Refused whole if any of it would be, before any is
+                    // carried out
+                    slave::Received::Write(write) => {
+                        let refused = write
+                            .requests()
+                            .iter()
+                            .filter_map(|&request| Command::from_request(request))
+                            .find_map(|command| check(command).err());
+                        match refused {
+                            Some(refusal) => {
+                                if let Some(reply) = modbus.written(&write, Err(refusal.code())) {
+                                    rs485.send(reply);
+                                }
+                            }
+                            None => modbus_write = Some(write),
+                        }
+                    }
+                }
+                continue;
+            } else {
+                let byte = match stdout.read() {
+                    Some(byte) => byte,
+                    None => break,
+                };
+                match link.push(byte) {
+                    Received::Nothing => continue,
+                    Received::Text(byte) => match shell.feed(byte, &mut stdout) {
+                        Some(line) => match Command::parse(&line) {
+                            Ok(command) => (Source::Shell, command),
+                            Err(usage) => {
+                                writeln!(stdout, "{}", usage).ok();
+                                shell.prompt(&mut stdout);
+                                continue;
+                            }
+                        },
+                        None => continue,
+                    },
+                    Received::Request(seq, request) => match Command::from_request(request) {
+                        Some(command) => (Source::Link(seq), command),
+                        None => {
+                            stdout.write_frame(&link.respond(seq, Response::Pong));
+                            continue;
+                        }
+                    },
+                    Received::Invalid(seq, code) => {
+                        stdout.write_frame(&link.respond(seq, Response::Error(code)));
+                        continue;
+                    }
+                }
+            };
+            let to_shell = source == Source::Shell;
+
+            let freq_now = match lcm.freq() {
+                Freq::Continuous => 0,
+                Freq::Periodic(mhz) => mhz / 1000,
+            };
+            let inputs = (input.ain(ain_pwm) as i32, input.ain(ain_freq) as i32);
+
+            // Above the menu's PWM MAX / FREQ MAX is refused rather than clamped
+            let checked = check(command);
+            let result = match command {
+                _ if checked.is_err() => checked,
+                Command::Help => {
+                    stdout.write_str(Command::help()).ok();
+                    Ok(())
+                }
+                Command::Status => {
+                    if to_shell {
+                        writeln!(stdout, "{}", lcm.status()).ok();
+                        for &ain in [ain_pwm, ain_freq].iter() {
+                            writeln!(
+                                stdout,
+                                "{}: {}, {} overruns, {} errors",
+                                input.ain_name(ain),
+                                input.ain(ain),
+                                input.ain_overruns(ain),
+                                input.ain_errors(ain)
+                            )
+                            .ok();
+                        }
+                        if hold.is_some() {
+                            writeln!(stdout, "setpoints held").ok();
+                        }
+                        writeln!(
+                            stdout,
+                            "serial: {} writes dropped, {} bytes lost",
+                            stdout.serial.dropped(),
+                            stdout.serial.rx_lost()
+                        )
+                        .ok();
+                        let counters = modbus.counters();
+                        writeln!(
+                            stdout,
+                            "modbus {}: {} messages, {} CRC errors, {} exceptions, {} discarded",
+                            modbus.address(),
+                            counters.messages,
+                            counters.crc_errors,
+                            counters.exceptions,
+                            rs485.discarded()
+                        )
+                        .ok();
+                    }
+                    Ok(())
+                }
+                Command::Pwm(pwm) => {
+                    hold = Some((pwm, freq_now, inputs.0, inputs.1));
+                    if to_shell {
+                        writeln!(stdout, "pwm {}", pwm).ok();
+                    }
+                    Ok(())
+                }
+                Command::Freq(hz) => {
+                    let hz = hz.unwrap_or(0);
+                    hold = Some((lcm.pwm(), hz, inputs.0, inputs.1));
+                    if to_shell {
+                        match hz {
+                            0 => writeln!(stdout, "freq cont"),
+                            hz => writeln!(stdout, "freq {}", hz),
+                        }
+                        .ok();
+                    }
+                    Ok(())
+                }
+                Command::Relay(true) => {
+                    lcm.pwm_disable();
+                    lcm.relay_enable();
+                    if lcm.relay_enabled() {
+                        led.set_high();
+                        Ok(())
+                    } else {
+                        Err(Refusal::Faulted)
+                    }
+                }
+                Command::Relay(false) => {
+                    led.set_low();
+                    remote_oe = None;
+                    lcm.pwm_disable();
+                    lcm.relay_disable();
+                    lcm.clear_fault();
+                    input.clear_ain_faults();
+                    Ok(())
+                }
+                Command::Oe(true) => {
+                    lcm.pwm_enable();
+                    if lcm.pwm_enabled() {
+                        remote_oe = Some(clock.now());
+                        Ok(())
+                    } else {
+                        Err(Refusal::Faulted)
+                    }
+                }
+                Command::Oe(false) => {
+                    remote_oe = None;
+                    lcm.pwm_disable();
+                    Ok(())
+                }
+                Command::Presets => {
+                    for (i, (name, pwm, freq)) in PRESETS.iter().enumerate() {
+                        writeln!(stdout, "{}: {} pwm {} freq {}", i, name, pwm, freq).ok();
+                    }
+                    Ok(())
+                }
+                Command::Preset(i) => {
+                    let (_, pwm, freq) = PRESETS[i];
+                    hold = Some((pwm, freq, inputs.0, inputs.1));
+                    Ok(())
+                }
+                Command::Log(None) => {
+                    let level = stdout.level;
+                    writeln!(stdout, "log {}", level).ok();
+                    Ok(())
+                }
+                Command::Log(Some(level)) => {
+                    stdout.level = level;
+                    Ok(())
+                }
+                Command::Telemetry(None) => {
+                    match telemetry.format() {
+                        Some(format) => {
+                            writeln!(stdout, "telemetry {} {}", format, telemetry.interval())
+                        }
+                        None => writeln!(stdout, "telemetry off"),
+                    }
+                    .ok();
+                    Ok(())
+                }
+                Command::Telemetry(Some((format, interval))) => {
+                    if let Some(interval) = interval {
+                        telemetry.set_interval(interval);
+                    }
+                    telemetry.set_format(format);
+                    Ok(())
+                }
+                Command::Reboot => {
+                    lcm.pwm_disable();
+                    lcm.relay_disable();
+                    writeln!(stdout, "rebooting").ok();
+                    stdout.flush();
+                    scb.system_reset();
+                }
+            };
+
+            match source {
+                Source::Shell => {
+                    if let Err(refusal) = result {
+                        writeln!(stdout, "{}", refusal).ok();
+                    }
+                    shell.prompt(&mut stdout);
+                }
+                Source::Link(seq) => {
+                    let response = match (result, command) {
+                        (Err(refusal), _) => Response::Error(refusal.code()),
+                        (Ok(()), Command::Status) => Response::Status(lcm.status()),
+                        (Ok(()), _) => Response::Ok,
+                    };
+                    stdout.write_frame(&link.respond(seq, response));
+                }
+                Source::Modbus => {
+                    if let Err(refusal) = result {
+                        if let Some(write) = modbus_write.take() {
+                            if let Some(reply) = modbus.written(&write, Err(refusal.code())) {
+                                rs485.send(reply);
+                            }
+                        }
+                    }
+                }
+            }
         }
 
-        if input.button_wait(Button::B0) {
-            led.set_low();
-            lcm.pwm_disable();
-            lcm.relay_disable();
+        match selected {
+            Some(Key::Preset(i)) => {
+                let (_, pwm, freq) = PRESETS[i as usize];
+                hold = Some((
+                    pwm,
+                    freq,
+                    input.ain(ain_pwm) as i32,
+                    input.ain(ain_freq) as i32,
+                ));
+                menu.set_page(page_status);
+            }
+            Some(Key::Calibrate) => {
+                led.set_low();
+                lcm.pwm_disable();
+                lcm.relay_disable();
+                settings.calibration = Some(calibrate(
+                    &mut input,
+                    [ain_pwm, ain_freq],
+                    btn_on,
+                    &mut disp,
+                    &wdt,
+                    &mut stdout,
+                ));
+                save(&mut storage, &settings, &mut stdout);
+            }
+            Some(Key::FactoryReset) => {
+                settings = Settings::default();
+                save(&mut storage, &settings, &mut stdout);
+                for &ain in [ain_pwm, ain_freq].iter() {
+                    input.set_calibration(ain, Calibration::full_scale(input.ain_max(ain)));
+                }
+                menu.set_number(Key::PwmMax, PWM_MAX as i32);
+                menu.set_number(Key::FreqMax, FREQ_MAX as i32);
+                menu.set_choice(Key::InputFault, 0);
+                menu.set_number(Key::VddaMin, VDDA_MIN_MV as i32);
+                menu.set_number(Key::TemperatureMax, TEMPERATURE_MAX as i32);
+                menu.set_number(Key::Contrast, CONTRAST_DEFAULT as i32);
+                menu.set_number(Key::DimAfter, DISPLAY_DIM_S as i32);
+                menu.set_number(Key::OffAfter, DISPLAY_OFF_S as i32);
+                menu.set_choice(Key::PixelShift, 1);
+                menu.set_choice(Key::Rotation, 0);
+                menu.set_choice(Key::Home, 0);
+                menu.set_choice(Key::Trace, 0);
+                menu.set_number(Key::TrendInterval, TREND_INTERVAL_MS as i32);
+                menu.set_number(Key::ModbusAddress, MODBUS_ADDRESS as i32);
+                menu.set_choice(Key::ModbusBaud, 0);
+                menu.set_choice(Key::ModbusParity, 0);
+                lcm.clear_fault();
+                input.clear_ain_faults();
+                service = false;
+                menu.set_hidden(page_service, true);
+                menu.set_page(page_status);
+                log!(stdout, Level::Info, "Factory reset");
+            }
+            _ => (),
+        }
+
+        // Settings apply as soon as they are edited
+        lcm.set_limits(Limits {
+            vdda_min_mv: menu.number(Key::VddaMin) as u16,
+            temperature_max: menu.number(Key::TemperatureMax) as i16,
+        });
+        lcm.set_input_fault_action(INPUT_FAULT_ACTIONS[menu.choice(Key::InputFault)]);
+        modbus.set_address(menu.number(Key::ModbusAddress) as u8);
+        let format = (
+            MODBUS_BAUDS[menu.choice(Key::ModbusBaud)],
+            MODBUS_PARITIES[menu.choice(Key::ModbusParity)],
+        );
+        if format != modbus_format {
+            rs485.configure(format.0, format.1);
+            modbus_format = format;
         }
 
-        let pwm_sp = input.ain(AIn::AIN0);
+        // Saved once an edit is done, rather than on every step
+        let modbus_settings = ModbusSettings {
+            address: modbus.address(),
+            baud: format.0,
+            parity: format.1,
+        };
+        let saved = settings.modbus.unwrap_or(ModbusSettings {
+            address: MODBUS_ADDRESS,
+            baud: MODBUS_BAUDS[0],
+            parity: MODBUS_PARITIES[0],
+        });
+        if !menu.editing() && modbus_settings != saved {
+            settings.modbus = Some(modbus_settings);
+            save(&mut storage, &settings, &mut stdout);
+        }
+
+        let idle = clock.now().since(activity);
+        let after = |key| match menu.number(key) as u32 {
+            0 => false,
+            s => idle >= s * 1000,
+        };
+        disp.set_power(if after(Key::OffAfter) {
+            Power::Off
+        } else if after(Key::DimAfter) {
+            Power::Dim
+        } else {
+            Power::On
+        });
+        disp.set_contrast(menu.number(Key::Contrast) as u8);
+        disp.set_pixel_shift(menu.choice(Key::PixelShift) == 1);
+        disp.set_rotation(ROTATIONS[menu.choice(Key::Rotation)]);
+
+        // PWM range across the setpoint input, and 0 (continuous) to FREQ MAX
+        // on an exponential taper for finer control of the slow strobe rates
+        let pwm_map = LinearMap::new(
+            (0, input.ain_max(ain_pwm) as i32),
+            (0, menu.number(Key::PwmMax)),
+            Clamp::Saturate,
+        );
+        let freq_map = CurveMap::new(
+            Curve::Exp,
+            (0, input.ain_max(ain_freq) as i32),
+            (0, menu.number(Key::FreqMax)),
+        );
+
+        let pwm_in = input.ain(ain_pwm) as i32;
+        let freq_in = input.ain(ain_freq) as i32;
+
+        if let Some((_, _, pwm_at, freq_at)) = hold {
+            if (pwm_in - pwm_at).abs() > input.ain_max(ain_pwm) as i32 / PRESET_RELEASE
+                || (freq_in - freq_at).abs() > input.ain_max(ain_freq) as i32 / PRESET_RELEASE
+            {
+                hold = None;
+            }
+        }
+
+        let (pwm_sp, raw_freq) = match hold {
+            Some((pwm, freq, _, _)) => (pwm, freq),
+            None => (pwm_map.map(pwm_in) as u16, freq_map.map(freq_in) as u32),
+        };
 
-        let raw_freq = input.ain_map(AIn::AIN1, 0, 100) as u16;
         let freq_sp = if raw_freq == 0 {
             Freq::Continuous
         } else {
-            Freq::Periodic(Hertz(raw_freq as u32))
+            Freq::Periodic(raw_freq.saturating_mul(1000))
         };
 
         lcm.set_pwm(pwm_sp);
@@ -223,15 +1344,151 @@ fn main() -> ! {
 
         let status = lcm.status();
 
-        disp.draw_lcm_status(&status);
+        history.set_interval(menu.number(Key::TrendInterval) as u32);
+        history.update(
+            clock.now(),
+            Sample {
+                pwm: status.pwm(),
+                inputs: [input.ain(ain_pwm), input.ain(ain_freq)],
+                temperature: status.supply().map(|s| s.temperature()),
+            },
+        );
+
+        if let Some(line) = telemetry.update(
+            clock.now(),
+            &status,
+            [input.ain(ain_pwm), input.ain(ain_freq)],
+            loop_us,
+            stdout.serial.dropped(),
+        ) {
+            stdout.write_whole(line.as_bytes());
+        }
+
+        // Log state changes on the console
+        let state = Some((status.state(), status.fault()));
+        if logged_state != state {
+            let level = match status.fault() {
+                Some(_) => Level::Error,
+                None => Level::Info,
+            };
+            log!(stdout, level, "Status: {}", status);
+            if let Some(event) = link.event(packet::Event::Status(status)) {
+                stdout.write_frame(&event);
+            }
+            logged_state = state;
+        }
+
+        if menu.page() == page_diagnostics {
+            if let Some(text) = menu.text_mut(Key::Diagnostics(0)) {
+                text.clear();
+                if let Some(supply) = status.supply() {
+                    write!(text, "{} ", supply.compact()).ok();
+                }
+                match status.fault() {
+                    Some(fault) => write!(text, "{}", fault.compact()),
+                    None => write!(text, "OK"),
+                }
+                .ok();
+            }
+
+            for (i, &ain) in [ain_pwm, ain_freq].iter().enumerate() {
+                let name = input.ain_name(ain);
+                let raw = input.ain_raw(ain);
+                let fault = input.ain_fault(ain);
+                if let Some(text) = menu.text_mut(Key::Diagnostics(i as u8 + 1)) {
+                    text.clear();
+                    write!(text, "{} {}", name, raw).ok();
+                    if let Some(fault) = fault {
+                        write!(text, " {:?}", fault).ok();
+                    }
+                }
+            }
+        }
+
+        if let Some(start) = splash {
+            if clock.now().since(start) >= SPLASH_MS {
+                splash = None;
+            }
+        }
+
+        if splash.is_some() {
+            // Keep the splash up
+        } else if menu.page() == page_status && menu.choice(Key::Home) == 1 {
+            disp.draw_trend(&history, TRACES[menu.choice(Key::Trace)], clock.now());
+        } else if menu.page() == page_status {
+            disp.draw_lcm_status(&status, clock.now());
+        } else {
+            disp.draw_menu(&menu);
+        }
     }
 }
 
+// Settings still apply until power off when they can't be written
+fn save(storage: &mut Storage, settings: &Settings, out: &mut DebugConsole) {
+    match storage.save(settings) {
+        Ok(()) => log!(out, Level::Info, "Settings saved"),
+        Err(e) => log!(out, Level::Error, "Settings not saved: {:?}", e),
+    }
+}
+
+// Walks the user through the end stops of each pot, B1 captures
+fn calibrate<ADC, I2C, W>(
+    input: &mut Input<ADC>,
+    ains: [AIn; 2],
+    btn_set: Button,
+    disp: &mut Display<I2C>,
+    wdt: &Iwdg<IWDG>,
+    out: &mut W,
+) -> [Calibration; 2]
+where
+    I2C: blocking::i2c::Write,
+    W: Write,
+{
+    let mut cals = [Calibration::full_scale(0); 2];
+    for (&ain, captured) in ains.iter().zip(cals.iter_mut()) {
+        // The end stops can look like a stuck input
+        let plausibility = input.plausibility(ain);
+        input.set_plausibility(ain, Plausibility::default());
+
+        let mut calibrator = Calibrator::new();
+        let mut heading: String<U32> = String::new();
+        write!(heading, "CAL {}", input.ain_name(ain)).ok();
+
+        loop {
+            wdt.refresh();
+            input.update();
+
+            let prompt = match calibrator.step() {
+                Step::Min => "TURN TO MIN",
+                Step::Max => "TURN TO MAX",
+            };
+            disp.draw_prompt(&[&heading, prompt, "B1: SET"]);
+
+            if input.pressed(btn_set) {
+                if let Some(cal) = calibrator.capture(input.ain_raw(ain)) {
+                    writeln!(out, "{}: {:?}", heading, cal).ok();
+                    input.set_calibration(ain, cal);
+                    *captured = cal;
+                    break;
+                }
+            }
+        }
+
+        input.set_plausibility(ain, plausibility);
+    }
+    cals
+}
+
 #[exception]
 fn HardFault(ef: &ExceptionFrame) -> ! {
     panic!("HardFault at {:#?}", ef);
 }
 
+#[exception]
+fn SysTick() {
+    clock::tick();
+}
+
 #[exception]
 fn DefaultHandler(irqn: i16) {
     panic!("Unhandled exception (IRQn = {})", irqn);
@@ -0,0 +1,195 @@
+use core::cell::RefCell;
+use cortex_m::interrupt::{self as cs, Mutex};
+use cortex_m::peripheral::{DWT, NVIC};
+use crate::hal::gpio::gpioc::PC12;
+use crate::hal::gpio::{Output, PushPull};
+use crate::hal::pac::{interrupt, Interrupt, USART3};
+use crate::hal::prelude::*;
+use crate::hal::rcc::Clocks;
+use crate::hal::serial::{Event, Rx, Serial, Tx};
+use lmc_modbus::rtu::{Adu, Framer, Parity, Timing};
+
+// Half duplex RS-485 on USART3 for the Modbus RTU slave, driven by its
+// interrupt. Characters are timestamped with the cycle counter as they
+// arrive to split the frames. The transceiver's DE is only raised while a
+// reply is sent, and the receiver is ignored meanwhile.
+struct Port {
+    tx: Tx<USART3>,
+    rx: Rx<USART3>,
+    de: PC12<Output<PushPull>>,
+    framer: Framer,
+    // Ended by the start of the next one before it was polled
+    frame: Option<Adu>,
+    reply: Adu,
+    // Bytes of the reply written, None when not sending
+    sent: Option<usize>,
+}
+
+static PORT: Mutex<RefCell<Option<Port>>> = Mutex::new(RefCell::new(None));
+
+pub struct Rs485 {
+    // USART3 and the cycle counter clocks
+    pclk1: u32,
+    sysclk: u32,
+}
+
+impl Rs485 {
+    // Takes over the USART3 interrupt, the cycle counter must be running
+    pub fn new<PINS>(
+        mut serial: Serial<USART3, PINS>,
+        mut de: PC12<Output<PushPull>>,
+        baud: u32,
+        parity: Parity,
+        clocks: Clocks,
+        nvic: &mut NVIC,
+    ) -> Self {
+        de.set_low();
+        serial.listen(Event::Rxne);
+        let (tx, rx) = serial.split();
+
+        let sysclk = clocks.sysclk().0;
+        cs::free(|cs| {
+            PORT.borrow(cs).replace(Some(Port {
+                tx,
+                rx,
+                de,
+                framer: Framer::new(Timing::new(baud, sysclk)),
+                frame: None,
+                reply: Adu::new(),
+                sent: None,
+            }))
+        });
+
+        let mut rs485 = Rs485 {
+            pclk1: clocks.pclk1().0,
+            sysclk,
+        };
+        rs485.configure(baud, parity);
+        nvic.enable(Interrupt::USART3);
+        rs485
+    }
+
+    // 8 data bits, without parity two stop bits. A frame in progress and a
+    // reply being sent are dropped.
+    pub fn configure(&mut self, baud: u32, parity: Parity) {
+        with_port(|port| {
+            port.framer.set_timing(Timing::new(baud, self.sysclk));
+            port.frame = None;
+            port.sent = None;
+            port.de.set_low();
+        });
+
+        // NOTE(unsafe) read-modify-write of USART3, which the interrupt also
+        // modifies, inside a critical section. The frame format can only
+        // change with the USART disabled.
+        cs::free(|_| unsafe {
+            let usart = &*USART3::ptr();
+            usart.cr1.modify(|_, w| w.ue().clear_bit());
+            usart.brr.write(|w| w.bits(self.pclk1 / baud));
+            usart.cr1.modify(|_, w| {
+                w.txeie()
+                    .clear_bit()
+                    .tcie()
+                    .clear_bit()
+                    .m()
+                    .bit(parity != Parity::None)
+                    .pce()
+                    .bit(parity != Parity::None)
+                    .ps()
+                    .bit(parity == Parity::Odd)
+            });
+            usart.cr2.modify(|_, w| {
+                w.stop()
+                    .bits(if parity == Parity::None { 0b10 } else { 0b00 })
+            });
+            usart.cr1.modify(|_, w| w.ue().set_bit());
+        });
+    }
+
+    // A whole frame once the line has been silent for 3.5 characters
+    pub fn receive(&mut self) -> Option<Adu> {
+        with_port(|port| {
+            port.frame
+                .take()
+                .or_else(|| port.framer.poll(DWT::get_cycle_count()))
+        })
+    }
+
+    // Replaces a reply still being sent, the master gave up on it
+    pub fn send(&mut self, reply: Adu) {
+        with_port(|port| {
+            port.de.set_high();
+            port.reply = reply;
+            port.sent = Some(0);
+        });
+        listen(true, false);
+    }
+
+    // Received frames discarded for timing, errors or length, wrapping
+    pub fn discarded(&self) -> u32 {
+        with_port(|port| port.framer.discarded())
+    }
+}
+
+fn with_port<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut Port) -> R,
+{
+    cs::free(|cs| f(PORT.borrow(cs).borrow_mut().as_mut().unwrap()))
+}
+
+// The HAL can only toggle the TX interrupts on the unsplit Serial
+fn listen(txe: bool, tc: bool) {
+    // NOTE(unsafe) atomic read-modify-write of USART3 CR1, which the
+    // interrupt also modifies, inside a critical section
+    cs::free(|_| unsafe {
+        (*USART3::ptr())
+            .cr1
+            .modify(|_, w| w.txeie().bit(txe).tcie().bit(tc));
+    });
+}
+
+#[interrupt]
+fn USART3() {
+    cs::free(|cs| {
+        let mut port = PORT.borrow(cs).borrow_mut();
+        let port = match port.as_mut() {
+            Some(port) => port,
+            None => return,
+        };
+
+        let now = DWT::get_cycle_count();
+        loop {
+            let ended = match port.rx.read() {
+                Ok(_) if port.sent.is_some() => None,
+                Ok(b) => port.framer.push(b, now),
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(_)) if port.sent.is_some() => None,
+                Err(nb::Error::Other(_)) => port.framer.error(now),
+            };
+            if ended.is_some() {
+                port.frame = ended;
+            }
+        }
+
+        if let Some(sent) = port.sent.as_mut() {
+            while let Some(&b) = port.reply.get(*sent) {
+                if port.tx.write(b).is_err() {
+                    break;
+                }
+                *sent += 1;
+            }
+
+            // Hold DE until the last stop bit is out
+            if *sent == port.reply.len() {
+                if port.tx.flush().is_ok() {
+                    port.de.set_low();
+                    port.sent = None;
+                    listen(false, false);
+                } else {
+                    listen(false, true);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,137 @@
+use core::cell::RefCell;
+use cortex_m::interrupt::{self as cs, Mutex};
+use cortex_m::peripheral::NVIC;
+use crate::hal::pac::{interrupt, Interrupt, USART2};
+use crate::hal::prelude::*;
+use crate::hal::serial::{Event, Rx, Serial, Tx};
+use heapless::consts::{U1024, U512};
+use heapless::spsc::Queue;
+
+// USART2 driven by its interrupt, received bytes and bytes to send wait in
+// rings so neither side blocks the loop. A write that doesn't fit the room
+// left in the TX ring is dropped whole, so a frame is never cut. Console text
+// is written a line at a time, see DebugConsole, longer lines go in pieces.
+struct Rings {
+    tx: Tx<USART2>,
+    rx: Rx<USART2>,
+    tx_ring: Queue<u8, U1024>,
+    // 512 bytes is ~44 ms at 115200 baud, enough to ride out a slow loop
+    // pass such as a display redraw or a flash write
+    rx_ring: Queue<u8, U512>,
+    // Received with an error or with the RX ring full
+    rx_lost: u32,
+}
+
+static RINGS: Mutex<RefCell<Option<Rings>>> = Mutex::new(RefCell::new(None));
+
+pub struct BufferedSerial {
+    // Writes that didn't fit
+    dropped: u32,
+}
+
+impl BufferedSerial {
+    // Takes over the USART2 interrupt
+    pub fn new<PINS>(mut serial: Serial<USART2, PINS>, nvic: &mut NVIC) -> Self {
+        serial.listen(Event::Rxne);
+        let (tx, rx) = serial.split();
+
+        cs::free(|cs| {
+            RINGS.borrow(cs).replace(Some(Rings {
+                tx,
+                rx,
+                tx_ring: Queue::new(),
+                rx_ring: Queue::new(),
+                rx_lost: 0,
+            }))
+        });
+        nvic.enable(Interrupt::USART2);
+
+        BufferedSerial { dropped: 0 }
+    }
+
+    pub fn read(&mut self) -> Option<u8> {
+        with_rings(|rings| rings.rx_ring.dequeue())
+    }
+
+    // Queues all the bytes or, without room for them, none, returns which
+    pub fn write(&mut self, bytes: &[u8]) -> bool {
+        let room = with_rings(|rings| rings.tx_ring.capacity() - rings.tx_ring.len());
+        if bytes.len() > room {
+            self.dropped = self.dropped.wrapping_add(1);
+            return false;
+        }
+
+        // Byte by byte to keep the RX interrupt waiting as little as
+        // possible, the room can only grow meanwhile
+        for &b in bytes {
+            with_rings(|rings| rings.tx_ring.enqueue(b).ok());
+        }
+        listen_txe(true);
+        true
+    }
+
+    // Waits until everything queued is on the line
+    pub fn flush(&mut self) {
+        while !with_rings(|rings| rings.tx_ring.is_empty()) {}
+        with_rings(|rings| nb::block!(rings.tx.flush()).ok());
+    }
+
+    // Writes dropped for want of room, wrapping
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    // Received bytes lost to errors or a full RX ring, wrapping
+    pub fn rx_lost(&self) -> u32 {
+        with_rings(|rings| rings.rx_lost)
+    }
+}
+
+fn with_rings<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut Rings) -> R,
+{
+    cs::free(|cs| f(RINGS.borrow(cs).borrow_mut().as_mut().unwrap()))
+}
+
+// The HAL can only toggle the TXE interrupt on the unsplit Serial
+fn listen_txe(enabled: bool) {
+    // NOTE(unsafe) atomic read-modify-write of USART2 CR1, which the
+    // interrupt also modifies, inside a critical section
+    cs::free(|_| unsafe {
+        (*USART2::ptr()).cr1.modify(|_, w| w.txeie().bit(enabled));
+    });
+}
+
+#[interrupt]
+fn USART2() {
+    cs::free(|cs| {
+        let mut rings = RINGS.borrow(cs).borrow_mut();
+        let rings = match rings.as_mut() {
+            Some(rings) => rings,
+            None => return,
+        };
+
+        loop {
+            match rings.rx.read() {
+                Ok(b) => {
+                    if rings.rx_ring.enqueue(b).is_err() {
+                        rings.rx_lost = rings.rx_lost.wrapping_add(1);
+                    }
+                }
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(_)) => rings.rx_lost = rings.rx_lost.wrapping_add(1),
+            }
+        }
+
+        while let Some(&b) = rings.tx_ring.peek() {
+            if rings.tx.write(b).is_err() {
+                break;
+            }
+            rings.tx_ring.dequeue();
+        }
+        if rings.tx_ring.is_empty() {
+            listen_txe(false);
+        }
+    });
+}
@@ -0,0 +1,26 @@
+use crate::clock::Instant;
+use crate::input::{AIn, Input};
+
+#[cfg(feature = "encoder")]
+mod knob;
+#[cfg(not(feature = "encoder"))]
+mod pots;
+
+#[cfg(feature = "encoder")]
+pub use self::knob::{Knob, CHANNELS};
+#[cfg(not(feature = "encoder"))]
+pub use self::pots::{Pots, CHANNELS};
+
+// The PWM and strobe rate setpoints come from two pots, or with the encoder
+// feature from a detented encoder. main builds one of the two front ends,
+// everything after that goes through Setpoints.
+pub trait Setpoints {
+    // The PWM and strobe rate channels of the Input
+    fn ains(&self) -> [AIn; 2];
+
+    // Whether there are end stops to calibrate
+    fn calibrates(&self) -> bool;
+
+    // Before each Input update, true when the setpoints were adjusted
+    fn update<ADC>(&mut self, now: Instant, input: &Input<ADC>) -> bool;
+}
@@ -0,0 +1,98 @@
+use super::Setpoints;
+use crate::clock::Instant;
+use crate::hal::afio::MAPR;
+use crate::hal::gpio::gpioa::{CRL, PA6};
+use crate::hal::gpio::gpiob::{PB6, PB7};
+use crate::hal::gpio::{self, Floating, PullUp};
+use crate::hal::pac::TIM4;
+use crate::hal::qei::Qei;
+use crate::hal::rcc::APB1;
+use crate::input::{AIn, Button, Input, Resolution};
+use cortex_m::singleton;
+use lmc_input::encoder::{Encoder, Setpoint, SetpointPin};
+use lmc_input::filter::FilterConfig;
+
+type QeiPins = (PB6<gpio::Input<Floating>>, PB7<gpio::Input<Floating>>);
+
+// Nothing for the ADC to scan
+pub const CHANNELS: [u8; 0] = [];
+
+// TIM4 encoder, PB6 (A), PB7 (B), external pull-ups
+// Push button, PA6, D12, selects the setpoint to adjust
+// PWM in fine steps of 16, up to 8x faster on quick spins. The frequency
+// setpoint goes through the same taper as the pot, 128 detents of travel.
+pub struct Knob {
+    encoder: Encoder<'static, Qei<TIM4, QeiPins>>,
+    button: Button,
+    ains: [AIn; 2],
+}
+
+impl Knob {
+    pub fn new<ADC>(
+        tim: TIM4,
+        pins: QeiPins,
+        pa6: PA6<gpio::Input<Floating>>,
+        crl: &mut CRL,
+        mapr: &mut MAPR,
+        apb: &mut APB1,
+        input: &mut Input<ADC>,
+    ) -> Self {
+        let setpoints: &'static [Setpoint; 2] = singleton!(: [Setpoint; 2] = [
+            Setpoint::new(0, 16, Resolution::bits(12)),
+            Setpoint::new(0, 1, Resolution::bits(7)),
+        ])
+        .unwrap();
+        let setpoint_pins = singleton!(: [SetpointPin<'static>; 2] = [
+            setpoints[0].pin(),
+            setpoints[1].pin(),
+        ])
+        .unwrap();
+        let [pwm_pin, freq_pin] = setpoint_pins;
+
+        let mut encoder = Encoder::new(Qei::tim4(tim, pins, mapr, apb));
+        for setpoint in setpoints.iter() {
+            encoder.add_setpoint(setpoint).unwrap();
+        }
+
+        let button_pin =
+            singleton!(: PA6<gpio::Input<PullUp>> = pa6.into_pull_up_input(crl)).unwrap();
+        let button = input.add_button("ENC", button_pin).unwrap();
+
+        // Encoder setpoints are exact, nothing to filter or rescale
+        let unfiltered = FilterConfig {
+            oversample: 1,
+            median: 1,
+            iir_shift: 0,
+            hysteresis: 0,
+        };
+        let pwm = input.add_ain("PWM", pwm_pin, unfiltered).unwrap();
+        let freq = input.add_ain("FREQ", freq_pin, unfiltered).unwrap();
+        for &ain in [pwm, freq].iter() {
+            input.set_vdda_correction(ain, false);
+        }
+
+        Knob {
+            encoder,
+            button,
+            ains: [pwm, freq],
+        }
+    }
+}
+
+impl Setpoints for Knob {
+    fn ains(&self) -> [AIn; 2] {
+        self.ains
+    }
+
+    fn calibrates(&self) -> bool {
+        false
+    }
+
+    // A push from the last Input update selects the next setpoint
+    fn update<ADC>(&mut self, now: Instant, input: &Input<ADC>) -> bool {
+        if input.pressed(self.button) {
+            self.encoder.select_next();
+        }
+        self.encoder.update(now) != 0
+    }
+}
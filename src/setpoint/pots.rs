@@ -0,0 +1,87 @@
+use super::Setpoints;
+use crate::clock::Instant;
+use crate::hal::gpio::gpioa::{CRL, PA0, PA1};
+use crate::hal::gpio::{self, Analog, Floating};
+use crate::input::{AIn, AdcScan, Input, Resolution, ScanPin};
+use cortex_m::singleton;
+use lmc_input::filter::FilterConfig;
+use lmc_input::plausibility;
+
+// ADC1 channels of the pots, at the start of the scan
+pub const CHANNELS: [u8; 2] = [0, 1];
+
+// The pots are fed from VDDA, which is also the ADC reference, so their
+// readings don't move with the supply. Only pots fed from a fixed reference
+// need rescaling by the measured VDDA.
+const VDDA_CORRECTION: bool = false;
+
+// ADC_0, PA0, A0 sets the PWM
+// ADC_1, PA1, A1 sets the strobe rate
+// Both need end resistors, a wiper at a rail faults, see plausibility::POTS
+pub struct Pots {
+    _pins: (PA0<Analog>, PA1<Analog>),
+    ains: [AIn; 2],
+}
+
+impl Pots {
+    pub fn new<B>(
+        pa0: PA0<gpio::Input<Floating>>,
+        pa1: PA1<gpio::Input<Floating>>,
+        crl: &mut CRL,
+        input: &mut Input<AdcScan<B>>,
+    ) -> Self
+    where
+        B: AsRef<[u16]> + AsMut<[u16]> + 'static,
+    {
+        let pins = (pa0.into_analog(crl), pa1.into_analog(crl));
+
+        let scan_pins = singleton!(: [ScanPin; 2] = [
+            ScanPin::new(0, Resolution::bits(12)),
+            ScanPin::new(1, Resolution::bits(12)),
+        ])
+        .unwrap();
+        let [pwm_pin, freq_pin] = scan_pins;
+
+        let pwm = input
+            .add_ain("AIN0", pwm_pin, FilterConfig::default())
+            .unwrap();
+
+        // Wider band, the frequency setpoint only has 100 steps across the
+        // travel
+        let freq = input
+            .add_ain(
+                "AIN1",
+                freq_pin,
+                FilterConfig {
+                    hysteresis: 24,
+                    ..FilterConfig::default()
+                },
+            )
+            .unwrap();
+
+        for &ain in [pwm, freq].iter() {
+            input.set_vdda_correction(ain, VDDA_CORRECTION);
+            input.set_plausibility(ain, plausibility::POTS);
+        }
+
+        Pots {
+            _pins: pins,
+            ains: [pwm, freq],
+        }
+    }
+}
+
+impl Setpoints for Pots {
+    fn ains(&self) -> [AIn; 2] {
+        self.ains
+    }
+
+    fn calibrates(&self) -> bool {
+        true
+    }
+
+    // Input reads the pots on its own
+    fn update<ADC>(&mut self, _now: Instant, _input: &Input<ADC>) -> bool {
+        false
+    }
+}
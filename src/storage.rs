@@ -0,0 +1,170 @@
+use core::slice;
+use crate::hal::flash::{Error, FlashWriter, PAGE_SIZE};
+use heapless::consts::{U32, U64};
+use heapless::Vec;
+use lmc_input::calibration::Calibration;
+use lmc_modbus::rtu::Parity;
+use lmc_proto::crc::crc16;
+
+// The last page of flash, left out of the FLASH region in memory.x
+const PAGE: u32 = 0x0801_FC00;
+
+// A record is the magic, the length of the fields, the fields and a CRC-16 of
+// everything before it. Fields added later go at the end, so an older record
+// leaves them at their defaults.
+const MAGIC: [u8; 2] = *b"LM";
+
+type Record = Vec<u8, U64>;
+
+// Settings kept across power cycles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Settings {
+    // End stops of the PWM and frequency pots, None until calibrated
+    pub calibration: Option<[Calibration; 2]>,
+    // None until changed from the defaults in the menu
+    pub modbus: Option<ModbusSettings>,
+}
+
+// RS-485 slave address and format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModbusSettings {
+    pub address: u8,
+    pub baud: u32,
+    pub parity: Parity,
+}
+
+impl Settings {
+    fn encode(&self, record: &mut Record) {
+        let cals = self.calibration.unwrap_or([Calibration::full_scale(0); 2]);
+        record.push(self.calibration.is_some() as u8).unwrap();
+        for cal in cals.iter() {
+            for &x in [cal.min, cal.max, cal.dead_bottom, cal.dead_top].iter() {
+                record.extend_from_slice(&x.to_le_bytes()).unwrap();
+            }
+            record.push(cal.reverse as u8).unwrap();
+        }
+
+        let modbus = self.modbus.unwrap_or(ModbusSettings {
+            address: 0,
+            baud: 0,
+            parity: Parity::Even,
+        });
+        record.push(self.modbus.is_some() as u8).unwrap();
+        record.push(modbus.address).unwrap();
+        record
+            .extend_from_slice(&modbus.baud.to_le_bytes())
+            .unwrap();
+        record
+            .push(match modbus.parity {
+                Parity::Even => 0,
+                Parity::Odd => 1,
+                Parity::None => 2,
+            })
+            .unwrap();
+    }
+
+    fn decode(fields: &[u8]) -> Self {
+        let mut fields = Fields(fields);
+        let calibrated = fields.u8() == Some(1);
+        let cals = [fields.calibration(), fields.calibration()];
+        let modbus = fields.u8() == Some(1);
+        let format = fields.modbus();
+
+        Settings {
+            calibration: match cals {
+                [Some(pwm), Some(freq)] if calibrated => Some([pwm, freq]),
+                _ => None,
+            },
+            modbus: format.filter(|_| modbus),
+        }
+    }
+}
+
+// Reads the fields in order, None past the end of an older record
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn u8(&mut self) -> Option<u8> {
+        let (&x, rest) = self.0.split_first()?;
+        self.0 = rest;
+        Some(x)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from(self.u16()?) | u32::from(self.u16()?) << 16)
+    }
+
+    fn calibration(&mut self) -> Option<Calibration> {
+        Some(Calibration {
+            min: self.u16()?,
+            max: self.u16()?,
+            dead_bottom: self.u16()?,
+            dead_top: self.u16()?,
+            reverse: self.u8()? != 0,
+        })
+    }
+
+    fn modbus(&mut self) -> Option<ModbusSettings> {
+        Some(ModbusSettings {
+            address: self.u8()?,
+            baud: self.u32()?,
+            parity: match self.u8()? {
+                0 => Parity::Even,
+                1 => Parity::Odd,
+                2 => Parity::None,
+                _ => return None,
+            },
+        })
+    }
+}
+
+pub struct Storage {
+    writer: FlashWriter,
+}
+
+impl Storage {
+    pub fn new(writer: FlashWriter) -> Self {
+        Storage { writer }
+    }
+
+    // None when the page is erased or its record is corrupt
+    pub fn load(&self) -> Option<Settings> {
+        // NOTE(unsafe) the page is outside the FLASH region, and only written
+        // through the writer this owns
+        let page = unsafe { slice::from_raw_parts(PAGE as *const u8, PAGE_SIZE as usize) };
+        if page[..2] != MAGIC {
+            return None;
+        }
+
+        let end = 3 + page[2] as usize;
+        let crc = u16::from_le_bytes([page[end], page[end + 1]]);
+        if crc16(&page[..end]) != crc {
+            return None;
+        }
+
+        Some(Settings::decode(&page[3..end]))
+    }
+
+    // Erases the page and writes the settings, a few tens of ms
+    pub fn save(&mut self, settings: &Settings) -> Result<(), Error> {
+        let mut record = Record::new();
+        record.extend_from_slice(&MAGIC).unwrap();
+        record.push(0).unwrap();
+        settings.encode(&mut record);
+        record[2] = (record.len() - 3) as u8;
+        let crc = crc16(&record);
+        record.extend_from_slice(&crc.to_le_bytes()).unwrap();
+
+        let halfwords: Vec<u16, U32> = record
+            .chunks(2)
+            .map(|b| u16::from_le_bytes([b[0], *b.get(1).unwrap_or(&0xFF)]))
+            .collect();
+
+        self.writer.erase_page(PAGE)?;
+        self.writer.program(PAGE, &halfwords)
+    }
+}
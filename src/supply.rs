@@ -0,0 +1,57 @@
+use core::cmp;
+use crate::lcm::{Fault, Supply};
+
+// Supply voltage and die temperature from the ADC1 internal channels, using
+// the typical datasheet values, the F1 has no factory calibration
+const VREFINT_MV: u32 = 1200;
+const TEMP_V25_UV: i32 = 1_430_000;
+const TEMP_SLOPE_UV: i32 = 4300;
+
+pub const VDDA_NOMINAL_MV: u16 = 3300;
+
+// The LCM is shut down below this VDDA or above this die temperature (C),
+// the defaults of the menu settings
+pub const VDDA_MIN_MV: u16 = 3000;
+pub const TEMPERATURE_MAX: i16 = 70;
+
+// From raw VREFINT and temperature sensor readings on a 0..=max scale, None
+// until the first VREFINT conversion
+pub fn measure(vrefint: u16, temp_sense: u16, max: u16) -> Option<Supply> {
+    if vrefint == 0 {
+        return None;
+    }
+
+    let vdda_mv = cmp::min(VREFINT_MV * max as u32 / vrefint as u32, u16::MAX as u32);
+    let sense_uv = (temp_sense as u64 * vdda_mv as u64 * 1000 / max as u64) as i32;
+    let temperature = 25 + (TEMP_V25_UV - sense_uv) / TEMP_SLOPE_UV;
+
+    Some(Supply::new(vdda_mv as u16, temperature as i16))
+}
+
+// Shutdown thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    pub vdda_min_mv: u16,
+    pub temperature_max: i16,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            vdda_min_mv: VDDA_MIN_MV,
+            temperature_max: TEMPERATURE_MAX,
+        }
+    }
+}
+
+impl Limits {
+    pub fn check(&self, supply: &Supply) -> Option<Fault> {
+        if supply.vdda_mv() < self.vdda_min_mv {
+            Some(Fault::UnderVoltage)
+        } else if supply.temperature() > self.temperature_max {
+            Some(Fault::OverTemperature)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::status::Fault;
+
+// What to do with the output on an input fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InputFaultAction {
+    // Keep running at the last good setpoints
+    Hold,
+    Off,
+}
+
+// Holds the first fault until cleared. Faults keep the output off, unless
+// set to hold on an input fault. A held input fault gives way to any fault
+// that shuts the output down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaultLatch {
+    fault: Option<Fault>,
+    input_action: InputFaultAction,
+}
+
+impl Default for FaultLatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FaultLatch {
+    pub fn new() -> Self {
+        FaultLatch {
+            fault: None,
+            input_action: InputFaultAction::Off,
+        }
+    }
+
+    // True when the output has to be shut down now
+    pub fn set_input_action(&mut self, action: InputFaultAction) -> bool {
+        let was_shut_down = self.shut_down();
+        self.input_action = action;
+        self.shut_down() && !was_shut_down
+    }
+
+    // True when the output has to be shut down now
+    pub fn raise(&mut self, fault: Fault) -> bool {
+        if self.shut_down() || self.fault == Some(fault) {
+            return false;
+        }
+
+        self.fault = Some(fault);
+        self.shut_down()
+    }
+
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
+    }
+
+    pub fn clear(&mut self) {
+        self.fault = None;
+    }
+
+    pub fn shut_down(&self) -> bool {
+        match self.fault {
+            None => false,
+            Some(Fault::Input) => self.input_action == InputFaultAction::Off,
+            Some(_) => true,
+        }
+    }
+}
+
+// Raised since startup, wrapping
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FaultCounts {
+    pub under_voltage: u16,
+    pub over_temperature: u16,
+    pub input: u16,
+}
+
+impl FaultCounts {
+    pub fn count(&mut self, fault: Fault) {
+        let n = match fault {
+            Fault::UnderVoltage => &mut self.under_voltage,
+            Fault::OverTemperature => &mut self.over_temperature,
+            Fault::Input => &mut self.input,
+        };
+        *n = n.wrapping_add(1);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::status::{Fault, Freq, Health, State, Status, Supply, PWM_MAX};
+use core::fmt;
+
+// Display on the status types is the verbose form for the console and
+// logs, compact() gives the short form for the OLED and telemetry columns
+
+// Short form of T
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Compact<T>(pub T);
+
+// PWM duty as a percent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Duty(pub u16);
+
+impl Duty {
+    pub fn compact(self) -> Compact<Self> {
+        Compact(self)
+    }
+
+    // Rounded to the nearest tenth of a percent
+    fn per_mille(self) -> u32 {
+        (self.0 as u32 * 1000 + PWM_MAX as u32 / 2) / PWM_MAX as u32
+    }
+}
+
+impl fmt::Display for Duty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pm = self.per_mille();
+        write!(f, "{}.{}%", pm / 10, pm % 10)
+    }
+}
+
+impl fmt::Display for Compact<Duty> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}%", (self.0.per_mille() + 5) / 10)
+    }
+}
+
+impl Freq {
+    pub fn compact(self) -> Compact<Self> {
+        Compact(self)
+    }
+}
+
+// Hz with one decimal, kHz from 1 kHz, or the period below 1 Hz
+fn write_freq(f: &mut fmt::Formatter, mhz: u32, sep: &str) -> fmt::Result {
+    match mhz {
+        0 => write!(f, "0{}Hz", sep),
+        1..=999 => {
+            let ms = 1_000_000 / mhz;
+            write!(f, "{}.{}{}s", ms / 1000, ms % 1000 / 100, sep)
+        }
+        1000..=999_999 => match mhz % 1000 / 100 {
+            0 => write!(f, "{}{}Hz", mhz / 1000, sep),
+            tenths => write!(f, "{}.{}{}Hz", mhz / 1000, tenths, sep),
+        },
+        _ => {
+            let tenths = mhz / 100_000;
+            write!(f, "{}.{}{}kHz", tenths / 10, tenths % 10, sep)
+        }
+    }
+}
+
+impl fmt::Display for Freq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Freq::Continuous => f.write_str("continuous"),
+            Freq::Periodic(mhz) if mhz > 0 && mhz < 1000 => {
+                f.write_str("period ")?;
+                write_freq(f, mhz, " ")
+            }
+            Freq::Periodic(mhz) => write_freq(f, mhz, " "),
+        }
+    }
+}
+
+impl fmt::Display for Compact<Freq> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Freq::Continuous => f.write_str("CONT"),
+            Freq::Periodic(mhz) => write_freq(f, mhz, ""),
+        }
+    }
+}
+
+impl State {
+    pub fn compact(self) -> Compact<Self> {
+        Compact(self)
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            State::Error => "error",
+            State::Off => "off",
+            State::On => "on",
+        })
+    }
+}
+
+impl fmt::Display for Compact<State> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self.0 {
+            State::Error => "ERR",
+            State::Off => "OFF",
+            State::On => "ON",
+        })
+    }
+}
+
+impl Fault {
+    pub fn compact(self) -> Compact<Self> {
+        Compact(self)
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Fault::UnderVoltage => "supply under voltage",
+            Fault::OverTemperature => "over temperature",
+            Fault::Input => "implausible input",
+        })
+    }
+}
+
+impl fmt::Display for Compact<Fault> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self.0 {
+            Fault::UnderVoltage => "UV",
+            Fault::OverTemperature => "OT",
+            Fault::Input => "IN",
+        })
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Health::Ok => "ok",
+            Health::Missing => "missing",
+            Health::Lost => "lost",
+        })
+    }
+}
+
+impl Supply {
+    pub fn compact(self) -> Compact<Self> {
+        Compact(self)
+    }
+}
+
+impl fmt::Display for Supply {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mv = self.vdda_mv();
+        write!(
+            f,
+            "{}.{:03} V, {} C",
+            mv / 1000,
+            mv % 1000,
+            self.temperature()
+        )
+    }
+}
+
+impl fmt::Display for Compact<Supply> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mv = self.0.vdda_mv();
+        write!(
+            f,
+            "{}.{:02}V {}C",
+            mv / 1000,
+            mv % 1000 / 10,
+            self.0.temperature()
+        )
+    }
+}
+
+impl Status {
+    pub fn compact(self) -> Compact<Self> {
+        Compact(self)
+    }
+}
+
+// on, 50.0% continuous, OE on, relay on, 3.300 V, 31 C, display ok
+// error: supply under voltage, 0.0% continuous, OE off, ...
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.fault() {
+            Some(fault) => write!(f, "{}: {}", self.state(), fault)?,
+            None => write!(f, "{}", self.state())?,
+        }
+        write!(
+            f,
+            ", {} {}, OE {}, relay {}",
+            Duty(self.pwm()),
+            self.freq(),
+            on_off(self.pwm_oe()),
+            on_off(self.pwm_relay())
+        )?;
+        if let Some(supply) = self.supply() {
+            write!(f, ", {}", supply)?;
+        }
+        write!(f, ", display {}", self.display())
+    }
+}
+
+// ON 50% CONT, or ERR UV 50% CONT
+impl fmt::Display for Compact<Status> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = self.0;
+        match status.fault() {
+            Some(fault) => write!(f, "{} {}", status.state().compact(), fault.compact())?,
+            None => write!(f, "{}", status.state().compact())?,
+        }
+        write!(
+            f,
+            " {} {}",
+            Duty(status.pwm()).compact(),
+            status.freq().compact()
+        )
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
@@ -0,0 +1,9 @@
+// The LCM status model and the other types shared by the firmware, its
+// protocols, the screens and the host tools.
+
+#![no_std]
+
+pub mod fault;
+pub mod format;
+pub mod status;
+pub mod time;
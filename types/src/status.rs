@@ -0,0 +1,141 @@
+pub const PWM_MAX: u16 = 4095;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Freq {
+    Continuous,
+    // Strobe rate in mHz, down to sub-Hz rates
+    Periodic(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum State {
+    Error,
+    Off,
+    On,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fault {
+    UnderVoltage,
+    OverTemperature,
+    // Implausible setpoint input
+    Input,
+}
+
+// Display health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Health {
+    Ok,
+    // Didn't respond at startup
+    Missing,
+    // Stopped responding
+    Lost,
+}
+
+// Supply voltage and die temperature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Supply {
+    vdda_mv: u16,
+    temperature: i16,
+}
+
+impl Supply {
+    pub fn new(vdda_mv: u16, temperature: i16) -> Self {
+        Supply {
+            vdda_mv,
+            temperature,
+        }
+    }
+
+    pub fn vdda_mv(&self) -> u16 {
+        self.vdda_mv
+    }
+
+    // Degrees Celsius
+    pub fn temperature(&self) -> i16 {
+        self.temperature
+    }
+}
+
+// Snapshot of the LCM, built with new() and the with_* methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Status {
+    // TODO - is state useful, what fails? low-level hw bits
+    state: State,
+    pwm: u16,
+    pwm_oe: bool,
+    pwm_relay: bool,
+    freq: Freq,
+    supply: Option<Supply>,
+    fault: Option<Fault>,
+    display: Health,
+}
+
+impl Status {
+    // Outputs off, no supply reading, no fault, display Ok
+    pub fn new(state: State, pwm: u16, freq: Freq) -> Self {
+        Status {
+            state,
+            pwm,
+            pwm_oe: false,
+            pwm_relay: false,
+            freq,
+            supply: None,
+            fault: None,
+            display: Health::Ok,
+        }
+    }
+
+    pub fn with_outputs(mut self, pwm_oe: bool, pwm_relay: bool) -> Self {
+        self.pwm_oe = pwm_oe;
+        self.pwm_relay = pwm_relay;
+        self
+    }
+
+    pub fn with_supply(mut self, supply: Option<Supply>) -> Self {
+        self.supply = supply;
+        self
+    }
+
+    pub fn with_fault(mut self, fault: Option<Fault>) -> Self {
+        self.fault = fault;
+        self
+    }
+
+    pub fn with_display(mut self, display: Health) -> Self {
+        self.display = display;
+        self
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn pwm(&self) -> u16 {
+        self.pwm
+    }
+
+    pub fn pwm_oe(&self) -> bool {
+        self.pwm_oe
+    }
+
+    pub fn pwm_relay(&self) -> bool {
+        self.pwm_relay
+    }
+
+    pub fn freq(&self) -> Freq {
+        self.freq
+    }
+
+    pub fn supply(&self) -> Option<Supply> {
+        self.supply
+    }
+
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
+    }
+
+    pub fn display(&self) -> Health {
+        self.display
+    }
+}
@@ -0,0 +1,18 @@
+// Milliseconds since the clock was started, wraps after ~49 days
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instant(u32);
+
+impl Instant {
+    pub fn from_millis(ms: u32) -> Self {
+        Instant(ms)
+    }
+
+    pub fn millis(self) -> u32 {
+        self.0
+    }
+
+    // Milliseconds from `earlier` to self, valid across the wraparound
+    pub fn since(self, earlier: Instant) -> u32 {
+        self.0.wrapping_sub(earlier.0)
+    }
+}
@@ -0,0 +1,70 @@
+use lmc_types::fault::{FaultLatch, InputFaultAction};
+use lmc_types::status::Fault;
+
+fn holding() -> FaultLatch {
+    let mut latch = FaultLatch::new();
+    latch.set_input_action(InputFaultAction::Hold);
+    latch
+}
+
+#[test]
+fn first_fault_latches_until_cleared() {
+    let mut latch = FaultLatch::new();
+    assert!(latch.raise(Fault::UnderVoltage));
+    assert!(!latch.raise(Fault::OverTemperature));
+    assert!(!latch.raise(Fault::UnderVoltage));
+    assert_eq!(latch.fault(), Some(Fault::UnderVoltage));
+    assert!(latch.shut_down());
+
+    latch.clear();
+    assert_eq!(latch.fault(), None);
+    assert!(!latch.shut_down());
+}
+
+#[test]
+fn input_fault_shuts_down_by_default() {
+    let mut latch = FaultLatch::new();
+    assert!(latch.raise(Fault::Input));
+    assert!(latch.shut_down());
+
+    // Nothing replaces it
+    assert!(!latch.raise(Fault::OverTemperature));
+    assert_eq!(latch.fault(), Some(Fault::Input));
+}
+
+#[test]
+fn held_input_fault_keeps_running() {
+    let mut latch = holding();
+    assert!(!latch.raise(Fault::Input));
+    assert!(!latch.raise(Fault::Input));
+    assert_eq!(latch.fault(), Some(Fault::Input));
+    assert!(!latch.shut_down());
+}
+
+#[test]
+fn shutdown_fault_replaces_a_held_input_fault() {
+    let mut latch = holding();
+    latch.raise(Fault::Input);
+
+    assert!(latch.raise(Fault::OverTemperature));
+    assert_eq!(latch.fault(), Some(Fault::OverTemperature));
+    assert!(latch.shut_down());
+
+    // Latched from here on
+    assert!(!latch.raise(Fault::Input));
+    assert!(!latch.raise(Fault::UnderVoltage));
+    assert_eq!(latch.fault(), Some(Fault::OverTemperature));
+}
+
+#[test]
+fn switching_to_off_shuts_down_a_held_input_fault() {
+    let mut latch = holding();
+    latch.raise(Fault::Input);
+    assert!(latch.set_input_action(InputFaultAction::Off));
+    assert!(latch.shut_down());
+    assert!(!latch.set_input_action(InputFaultAction::Off));
+
+    // And back
+    assert!(!latch.set_input_action(InputFaultAction::Hold));
+    assert!(!latch.shut_down());
+}
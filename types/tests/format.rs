@@ -0,0 +1,45 @@
+use lmc_types::format::Duty;
+use lmc_types::status::{Fault, Freq, Health, State, Status, Supply, PWM_MAX};
+
+#[test]
+fn duty() {
+    assert_eq!(Duty(0).to_string(), "0.0%");
+    assert_eq!(Duty(PWM_MAX / 2).to_string(), "50.0%");
+    assert_eq!(Duty(PWM_MAX).to_string(), "100.0%");
+    assert_eq!(Duty(PWM_MAX / 3).compact().to_string(), "33%");
+    assert_eq!(Duty(PWM_MAX).compact().to_string(), "100%");
+}
+
+#[test]
+fn freq() {
+    assert_eq!(Freq::Continuous.to_string(), "continuous");
+    assert_eq!(Freq::Continuous.compact().to_string(), "CONT");
+    assert_eq!(Freq::Periodic(10_000).to_string(), "10 Hz");
+    assert_eq!(Freq::Periodic(2500).compact().to_string(), "2.5Hz");
+    assert_eq!(Freq::Periodic(1_500_000).to_string(), "1.5 kHz");
+    assert_eq!(Freq::Periodic(1_500_000).compact().to_string(), "1.5kHz");
+    // Period below 1 Hz
+    assert_eq!(Freq::Periodic(500).to_string(), "period 2.0 s");
+    assert_eq!(Freq::Periodic(400).compact().to_string(), "2.5s");
+}
+
+#[test]
+fn status() {
+    let status = Status::new(State::On, PWM_MAX, Freq::Periodic(10_000))
+        .with_outputs(true, true)
+        .with_supply(Some(Supply::new(3310, 31)));
+    assert_eq!(
+        status.to_string(),
+        "on, 100.0% 10 Hz, OE on, relay on, 3.310 V, 31 C, display ok"
+    );
+    assert_eq!(status.compact().to_string(), "ON 100% 10Hz");
+
+    let status = Status::new(State::Error, 0, Freq::Continuous)
+        .with_fault(Some(Fault::OverTemperature))
+        .with_display(Health::Lost);
+    assert_eq!(
+        status.to_string(),
+        "error: over temperature, 0.0% continuous, OE off, relay off, display lost"
+    );
+    assert_eq!(status.compact().to_string(), "ERR OT 0% CONT");
+}
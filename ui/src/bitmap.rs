@@ -0,0 +1,18 @@
+use embedded_graphics::image::{Image, Image1BPP};
+use embedded_graphics::pixelcolor::PixelColorU8;
+
+// Monochrome image, rows of MSB first bytes padded to a whole byte with set
+// bits lit. The firmware build script generates these from the BMP and PBM
+// files in assets/.
+#[derive(Debug, Clone, Copy)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub data: &'static [u8],
+}
+
+impl Bitmap {
+    pub fn image(&self) -> Image1BPP<'static, PixelColorU8> {
+        Image1BPP::new(self.data, self.width, self.height)
+    }
+}
@@ -0,0 +1,162 @@
+use core::fmt;
+use embedded_graphics::coord::Coord;
+use embedded_graphics::drawable::Pixel;
+use embedded_graphics::pixelcolor::PixelColorU8;
+use embedded_graphics::unsignedcoord::UnsignedCoord;
+use embedded_graphics::Drawing;
+
+// Largest panel, 128x64 at one bit per pixel
+const BUFFER_SIZE: usize = 128 * 64 / 8;
+
+// What the screens render into
+pub trait Canvas: Drawing<PixelColorU8> {
+    // Turn every pixel off
+    fn clear(&mut self);
+
+    // Width and height in pixels
+    fn dimensions(&self) -> (u8, u8);
+}
+
+// In memory canvas for host tools and tests. Pixels can be copied out with
+// pixel() into the embedded-graphics simulator, or printed with Display as
+// '#' for on and '.' for off, one row per line.
+pub struct Framebuffer {
+    width: u8,
+    height: u8,
+    pixels: [u8; BUFFER_SIZE],
+}
+
+impl Framebuffer {
+    pub fn new(width: u8, height: u8) -> Self {
+        assert!(width as usize * height as usize <= BUFFER_SIZE * 8);
+        Framebuffer {
+            width,
+            height,
+            pixels: [0; BUFFER_SIZE],
+        }
+    }
+
+    pub fn pixel(&self, x: u8, y: u8) -> bool {
+        let (byte, bit) = self.index(x, y);
+        self.pixels[byte] & bit != 0
+    }
+
+    fn index(&self, x: u8, y: u8) -> (usize, u8) {
+        let i = y as usize * self.width as usize + x as usize;
+        (i / 8, 1 << (i % 8))
+    }
+}
+
+impl Drawing<PixelColorU8> for Framebuffer {
+    fn draw<T>(&mut self, item_pixels: T)
+    where
+        T: Iterator<Item = Pixel<PixelColorU8>>,
+    {
+        for Pixel(coord, color) in item_pixels {
+            // Off screen pixels are dropped, like on the panel
+            if coord[0] >= self.width as u32 || coord[1] >= self.height as u32 {
+                continue;
+            }
+
+            let (byte, bit) = self.index(coord[0] as u8, coord[1] as u8);
+            if color.into_inner() == 0 {
+                self.pixels[byte] &= !bit;
+            } else {
+                self.pixels[byte] |= bit;
+            }
+        }
+    }
+}
+
+impl Canvas for Framebuffer {
+    fn clear(&mut self) {
+        self.pixels = [0; BUFFER_SIZE];
+    }
+
+    fn dimensions(&self) -> (u8, u8) {
+        (self.width, self.height)
+    }
+}
+
+impl fmt::Display for Framebuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                f.write_str(if self.pixel(x, y) { "#" } else { "." })?;
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+// Moves everything drawn by an offset, pixels moved off the canvas are
+// dropped. Used to shift a static layout around against burn-in.
+pub struct Translate<'a, C> {
+    canvas: &'a mut C,
+    offset: Coord,
+}
+
+impl<'a, C> Translate<'a, C>
+where
+    C: Canvas,
+{
+    pub fn new(canvas: &'a mut C, offset: Coord) -> Self {
+        Translate { canvas, offset }
+    }
+}
+
+impl<'a, C> Drawing<PixelColorU8> for Translate<'a, C>
+where
+    C: Canvas,
+{
+    fn draw<T>(&mut self, item_pixels: T)
+    where
+        T: Iterator<Item = Pixel<PixelColorU8>>,
+    {
+        let offset = self.offset;
+        self.canvas
+            .draw(item_pixels.filter_map(|Pixel(coord, color)| {
+                let x = coord[0] as i32 + offset[0];
+                let y = coord[1] as i32 + offset[1];
+                if x < 0 || y < 0 {
+                    None
+                } else {
+                    Some(Pixel(UnsignedCoord::new(x as u32, y as u32), color))
+                }
+            }));
+    }
+}
+
+impl<'a, C> Canvas for Translate<'a, C>
+where
+    C: Canvas,
+{
+    fn clear(&mut self) {
+        self.canvas.clear();
+    }
+
+    fn dimensions(&self) -> (u8, u8) {
+        self.canvas.dimensions()
+    }
+}
+
+#[cfg(feature = "ssd1306")]
+mod ssd1306_canvas {
+    use super::Canvas;
+    use ssd1306::interface::DisplayInterface;
+    use ssd1306::mode::GraphicsMode;
+
+    impl<DI> Canvas for GraphicsMode<DI>
+    where
+        DI: DisplayInterface,
+    {
+        fn clear(&mut self) {
+            GraphicsMode::clear(self);
+        }
+
+        fn dimensions(&self) -> (u8, u8) {
+            self.get_dimensions()
+        }
+    }
+}
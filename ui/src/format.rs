@@ -0,0 +1,38 @@
+use crate::history::Trace;
+use crate::telemetry::Format;
+use core::fmt;
+
+// The status types format in lmc-types, these are the screen and telemetry
+// ones
+
+impl Trace {
+    // Short form for the OLED
+    pub fn compact(self) -> &'static str {
+        match self {
+            Trace::Pwm => "PWM",
+            Trace::PwmInput => "PWM IN",
+            Trace::FreqInput => "FREQ IN",
+            Trace::Temperature => "TEMP",
+        }
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Trace::Pwm => "PWM",
+            Trace::PwmInput => "PWM input",
+            Trace::FreqInput => "strobe rate input",
+            Trace::Temperature => "temperature",
+        })
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+        })
+    }
+}
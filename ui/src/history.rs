@@ -0,0 +1,129 @@
+use heapless::{ArrayLength, Vec};
+use lmc_types::time::Instant;
+
+// One reading of every trace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sample {
+    pub pwm: u16,
+    // Setpoint inputs, PWM then strobe rate
+    pub inputs: [u16; 2],
+    // None without a supply measurement
+    pub temperature: Option<i16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trace {
+    Pwm,
+    PwmInput,
+    FreqInput,
+    Temperature,
+}
+
+impl Trace {
+    pub fn value(self, sample: &Sample) -> Option<i32> {
+        match self {
+            Trace::Pwm => Some(sample.pwm as i32),
+            Trace::PwmInput => Some(sample.inputs[0] as i32),
+            Trace::FreqInput => Some(sample.inputs[1] as i32),
+            Trace::Temperature => sample.temperature.map(i32::from),
+        }
+    }
+
+    // Smallest range a graph scales to, so noise doesn't fill its height
+    pub fn min_span(self) -> i32 {
+        match self {
+            Trace::Pwm => 41,
+            Trace::PwmInput | Trace::FreqInput => 16,
+            Trace::Temperature => 4,
+        }
+    }
+}
+
+// Ring of the last N samples, taken at most once per interval
+pub struct History<N>
+where
+    N: ArrayLength<Sample>,
+{
+    samples: Vec<Sample, N>,
+    // Oldest sample once full
+    next: usize,
+    recorded: u32,
+    interval_ms: u32,
+    last: Option<Instant>,
+}
+
+impl<N> History<N>
+where
+    N: ArrayLength<Sample>,
+{
+    pub fn new(interval_ms: u32) -> Self {
+        History {
+            samples: Vec::new(),
+            next: 0,
+            recorded: 0,
+            interval_ms,
+            last: None,
+        }
+    }
+
+    pub fn set_interval(&mut self, interval_ms: u32) {
+        self.interval_ms = interval_ms;
+    }
+
+    // Records the sample once the interval has passed since the last one,
+    // returns whether it did
+    pub fn update(&mut self, now: Instant, sample: Sample) -> bool {
+        match self.last {
+            Some(last) if now.since(last) < self.interval_ms => false,
+            _ => {
+                self.last = Some(now);
+                self.push(sample);
+                true
+            }
+        }
+    }
+
+    // Overwrites the oldest sample when full
+    pub fn push(&mut self, sample: Sample) {
+        if self.samples.len() < self.samples.capacity() {
+            self.samples.push(sample).ok();
+        } else {
+            self.samples[self.next] = sample;
+            self.next = (self.next + 1) % self.samples.len();
+        }
+        self.recorded = self.recorded.wrapping_add(1);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples = Vec::new();
+        self.next = 0;
+        self.last = None;
+        self.recorded = self.recorded.wrapping_add(1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // Counts every push and clear, wrapping, for spotting changes
+    pub fn recorded(&self) -> u32 {
+        self.recorded
+    }
+
+    // Oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Sample> {
+        let (newer, older) = self.samples.split_at(self.next);
+        older.iter().chain(newer.iter())
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        match self.next {
+            0 => self.samples.last(),
+            i => self.samples.get(i - 1),
+        }
+    }
+}
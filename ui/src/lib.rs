@@ -0,0 +1,15 @@
+// The screens, menu, trend history, telemetry and console shell, shared by
+// the firmware and the host tools. Screens render into any Canvas, the
+// SSD1306 on the target or a Framebuffer on the host.
+
+#![no_std]
+
+pub mod bitmap;
+pub mod canvas;
+pub mod format;
+pub mod history;
+pub mod menu;
+pub mod screen;
+pub mod shell;
+pub mod telemetry;
+pub mod widgets;
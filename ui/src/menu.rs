@@ -0,0 +1,322 @@
+use core::fmt::Write;
+use heapless::consts::{U16, U24, U8};
+use heapless::{String, Vec};
+
+pub enum Field {
+    // Read only, skipped by the cursor
+    Text(String<U16>),
+    Number {
+        value: i32,
+        min: i32,
+        max: i32,
+        step: i32,
+    },
+    Choice {
+        index: usize,
+        options: &'static [&'static str],
+    },
+    // Returned from Menu::handle when selected
+    Action,
+}
+
+impl Field {
+    fn selectable(&self) -> bool {
+        !matches!(self, Field::Text(_))
+    }
+
+    fn step(&mut self, up: bool) {
+        match self {
+            Field::Number {
+                value,
+                min,
+                max,
+                step,
+            } => {
+                let next = if up {
+                    value.saturating_add(*step)
+                } else {
+                    value.saturating_sub(*step)
+                };
+                *value = next.clamp(*min, *max);
+            }
+            Field::Choice { index, options } => {
+                let len = options.len();
+                *index = if up {
+                    (*index + 1) % len
+                } else {
+                    (*index + len - 1) % len
+                };
+            }
+            _ => (),
+        }
+    }
+}
+
+struct Item<K> {
+    key: K,
+    label: &'static str,
+    field: Field,
+}
+
+struct Page<K> {
+    title: &'static str,
+    hidden: bool,
+    items: Vec<Item<K>, U8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    // Previous item, or decrement while editing
+    Up,
+    // Next item, or increment while editing
+    Down,
+    // Edit or confirm a field, run an action
+    Select,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PageId(u8);
+
+// No room for another page, or another item on the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Full;
+
+// Pages of items keyed by K. Moving the cursor past the first or last item
+// moves to the previous or next page. Edits apply on every step, read them
+// back with number() and choice().
+pub struct Menu<K> {
+    pages: Vec<Page<K>, U8>,
+    page: usize,
+    cursor: usize,
+    editing: bool,
+}
+
+impl<K> Default for Menu<K>
+where
+    K: Copy + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> Menu<K>
+where
+    K: Copy + PartialEq,
+{
+    pub fn new() -> Self {
+        Menu {
+            pages: Vec::new(),
+            page: 0,
+            cursor: 0,
+            editing: false,
+        }
+    }
+
+    pub fn add_page(&mut self, title: &'static str) -> Result<PageId, Full> {
+        let id = PageId(self.pages.len() as u8);
+        self.pages
+            .push(Page {
+                title,
+                hidden: false,
+                items: Vec::new(),
+            })
+            .map_err(|_| Full)?;
+        Ok(id)
+    }
+
+    pub fn add_item(
+        &mut self,
+        page: PageId,
+        key: K,
+        label: &'static str,
+        field: Field,
+    ) -> Result<(), Full> {
+        self.pages[page.0 as usize]
+            .items
+            .push(Item { key, label, field })
+            .map_err(|_| Full)
+    }
+
+    pub fn page(&self) -> PageId {
+        PageId(self.page as u8)
+    }
+
+    pub fn set_page(&mut self, page: PageId) {
+        self.page = page.0 as usize;
+        self.editing = false;
+        self.cursor = self.first_selectable().unwrap_or(0);
+    }
+
+    // Whether a number or choice is being edited
+    pub fn editing(&self) -> bool {
+        self.editing
+    }
+
+    // Hidden pages are skipped when moving between pages
+    pub fn set_hidden(&mut self, page: PageId, hidden: bool) {
+        self.pages[page.0 as usize].hidden = hidden;
+    }
+
+    pub fn number(&self, key: K) -> i32 {
+        match self.field(key) {
+            Some(Field::Number { value, .. }) => *value,
+            _ => 0,
+        }
+    }
+
+    pub fn set_number(&mut self, key: K, v: i32) {
+        if let Some(Field::Number {
+            value, min, max, ..
+        }) = self.field_mut(key)
+        {
+            *value = v.clamp(*min, *max);
+        }
+    }
+
+    pub fn choice(&self, key: K) -> usize {
+        match self.field(key) {
+            Some(Field::Choice { index, .. }) => *index,
+            _ => 0,
+        }
+    }
+
+    pub fn set_choice(&mut self, key: K, i: usize) {
+        if let Some(Field::Choice { index, options }) = self.field_mut(key) {
+            *index = i % options.len();
+        }
+    }
+
+    pub fn text_mut(&mut self, key: K) -> Option<&mut String<U16>> {
+        match self.field_mut(key) {
+            Some(Field::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    // Returns the key of a selected action
+    pub fn handle(&mut self, event: Event) -> Option<K> {
+        let page = &mut self.pages[self.page];
+
+        if self.editing {
+            let field = &mut page.items[self.cursor].field;
+            match event {
+                Event::Up => field.step(false),
+                Event::Down => field.step(true),
+                Event::Select => self.editing = false,
+            }
+            return None;
+        }
+
+        match event {
+            Event::Up => match self.prev_selectable() {
+                Some(i) => self.cursor = i,
+                None => self.move_page(false),
+            },
+            Event::Down => match self.next_selectable() {
+                Some(i) => self.cursor = i,
+                None => self.move_page(true),
+            },
+            Event::Select => {
+                let item = page.items.get(self.cursor)?;
+                match item.field {
+                    Field::Action => return Some(item.key),
+                    Field::Number { .. } | Field::Choice { .. } => self.editing = true,
+                    Field::Text(_) => (),
+                }
+            }
+        }
+
+        None
+    }
+
+    // Title row followed by up to rows item rows (at most 7), 21 columns each
+    pub fn render(&self, rows: usize) -> Vec<String<U24>, U8> {
+        let page = &self.pages[self.page];
+        let rows = rows.min(7);
+        let mut lines: Vec<String<U24>, U8> = Vec::new();
+
+        let mut title = String::new();
+        let visible = self.pages.iter().filter(|p| !p.hidden).count();
+        let index = self.pages[..self.page].iter().filter(|p| !p.hidden).count();
+        write!(title, "{:<16}{}/{}", page.title, index + 1, visible).ok();
+        lines.push(title).ok();
+
+        // Keep the cursor on screen
+        let top = (self.cursor + 1).saturating_sub(rows);
+        for (i, item) in page.items.iter().enumerate().skip(top).take(rows) {
+            let mut row = String::new();
+            let marker = match (i == self.cursor && item.field.selectable(), self.editing) {
+                (true, true) => '*',
+                (true, false) => '>',
+                _ => ' ',
+            };
+
+            match &item.field {
+                Field::Text(text) => write!(row, "{}{}", marker, text),
+                Field::Number { value, .. } => {
+                    write!(row, "{}{:<12}{:>8}", marker, item.label, value)
+                }
+                Field::Choice { index, options } => {
+                    write!(row, "{}{:<12}{:>8}", marker, item.label, options[*index])
+                }
+                Field::Action => write!(row, "{}{}", marker, item.label),
+            }
+            .ok();
+            lines.push(row).ok();
+        }
+
+        lines
+    }
+
+    fn field(&self, key: K) -> Option<&Field> {
+        self.pages
+            .iter()
+            .flat_map(|p| p.items.iter())
+            .find(|item| item.key == key)
+            .map(|item| &item.field)
+    }
+
+    fn field_mut(&mut self, key: K) -> Option<&mut Field> {
+        self.pages
+            .iter_mut()
+            .flat_map(|p| p.items.iter_mut())
+            .find(|item| item.key == key)
+            .map(|item| &mut item.field)
+    }
+
+    fn first_selectable(&self) -> Option<usize> {
+        self.pages[self.page]
+            .items
+            .iter()
+            .position(|item| item.field.selectable())
+    }
+
+    fn prev_selectable(&self) -> Option<usize> {
+        let items = &self.pages[self.page].items;
+        (0..self.cursor)
+            .rev()
+            .find(|&i| items[i].field.selectable())
+    }
+
+    fn next_selectable(&self) -> Option<usize> {
+        let items = &self.pages[self.page].items;
+        (self.cursor + 1..items.len()).find(|&i| items[i].field.selectable())
+    }
+
+    fn move_page(&mut self, forward: bool) {
+        let len = self.pages.len();
+        let mut page = self.page;
+        loop {
+            page = if forward {
+                (page + 1) % len
+            } else {
+                (page + len - 1) % len
+            };
+            if !self.pages[page].hidden || page == self.page {
+                break;
+            }
+        }
+        self.set_page(PageId(page as u8));
+    }
+}
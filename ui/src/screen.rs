@@ -0,0 +1,313 @@
+use crate::bitmap::Bitmap;
+use crate::canvas::Canvas;
+use crate::history::{History, Sample, Trace};
+use crate::menu::Menu;
+use crate::widgets::{Icon, IconKind, LevelBar, StrobeIndicator, TrendGraph, Widget};
+use core::fmt::Write;
+use embedded_graphics::fonts::{Font12x16, Font6x12, Font6x8};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Line;
+use heapless::consts::{U32, U8};
+use heapless::{ArrayLength, String};
+use lmc_types::format::Duty;
+use lmc_types::status::Status;
+use lmc_types::time::Instant;
+
+// The screens clear the canvas and draw a whole frame, flushing it to the
+// panel is up to the caller
+
+// Right aligned text ends this far from the right edge, leaving room for the
+// pixel shift
+const MARGIN: i32 = 4;
+
+// Picked from the canvas size, both need the 128 columns of a landscape
+// panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    // 128x32, Font6x8 throughout
+    Compact,
+    // 128x64, the setpoint and state in a larger font and more menu rows
+    Tall,
+}
+
+impl Layout {
+    pub fn of<C>(canvas: &C) -> Self
+    where
+        C: Canvas,
+    {
+        let (_, height) = canvas.dimensions();
+        if height >= 64 {
+            Layout::Tall
+        } else {
+            Layout::Compact
+        }
+    }
+}
+
+// Logo on the left with up to 3 lines of text beside it, both centered
+// vertically
+pub fn draw_splash<C>(canvas: &mut C, logo: &Bitmap, lines: &[&str])
+where
+    C: Canvas,
+{
+    canvas.clear();
+
+    let (_, height) = canvas.dimensions();
+    let top = (height as i32 - logo.height as i32) / 2;
+    canvas.draw(logo.image().translate(Coord::new(0, top)).into_iter());
+
+    let lines = &lines[..lines.len().min(3)];
+    let x = logo.width as i32 + 6;
+    let top = (height as i32 - 10 * lines.len() as i32) / 2;
+    for (row, line) in lines.iter().enumerate() {
+        canvas.draw(
+            Font6x8::render_str(line)
+                .translate(Coord::new(x, top + 10 * row as i32 + 1))
+                .into_iter(),
+        );
+    }
+}
+
+// Up to 3 lines of text
+pub fn draw_prompt<C>(canvas: &mut C, lines: &[&str])
+where
+    C: Canvas,
+{
+    canvas.clear();
+
+    for (row, line) in lines.iter().take(3).enumerate() {
+        let y = row as i32;
+        match Layout::of(canvas) {
+            Layout::Compact => canvas.draw(
+                Font6x8::render_str(line)
+                    .translate(Coord::new(0, 12 * y))
+                    .into_iter(),
+            ),
+            Layout::Tall => canvas.draw(
+                Font6x12::render_str(line)
+                    .translate(Coord::new(0, 20 * y))
+                    .into_iter(),
+            ),
+        }
+    }
+}
+
+pub fn draw_menu<C, K>(canvas: &mut C, menu: &Menu<K>)
+where
+    C: Canvas,
+    K: Copy + PartialEq,
+{
+    canvas.clear();
+
+    // As many Font6x8 rows as fit below the title
+    let (width, height) = canvas.dimensions();
+    let rows = height as usize / 8 - 1;
+    for (row, line) in menu.render(rows).iter().enumerate() {
+        canvas.draw(
+            Font6x8::render_str(line)
+                .translate(Coord::new(0, 8 * row as i32))
+                .into_iter(),
+        );
+    }
+
+    // Underline the title
+    canvas.draw(
+        Line::new(Coord::new(0, 7), Coord::new(width as i32 - 1, 7))
+            .with_stroke(Some(1u8.into()))
+            .into_iter(),
+    );
+}
+
+pub fn draw_status<C>(canvas: &mut C, status: &Status, now: Instant)
+where
+    C: Canvas,
+{
+    canvas.clear();
+
+    match Layout::of(canvas) {
+        Layout::Compact => draw_status_compact(canvas, status, now),
+        Layout::Tall => draw_status_tall(canvas, status, now),
+    }
+}
+
+fn draw_status_compact<C>(canvas: &mut C, status: &Status, now: Instant)
+where
+    C: Canvas,
+{
+    LevelBar {
+        origin: Coord::new(0, 0),
+        width: 96,
+        height: 10,
+        label: true,
+    }
+    .draw(status, now, canvas);
+
+    StrobeIndicator {
+        origin: Coord::new(0, 12),
+        size: 10,
+    }
+    .draw(status, now, canvas);
+
+    Icon {
+        origin: Coord::new(72, 12),
+        kind: IconKind::Relay,
+    }
+    .draw(status, now, canvas);
+
+    Icon {
+        origin: Coord::new(100, 12),
+        kind: IconKind::OutputEnable,
+    }
+    .draw(status, now, canvas);
+
+    let mut state_str: String<U32> = String::new();
+    match status.fault() {
+        Some(fault) => write!(state_str, "STAT: {}", fault.compact()).ok(),
+        None => write!(state_str, "STAT: {}", status.state().compact()).ok(),
+    };
+
+    canvas.draw(
+        Font6x8::render_str(&state_str)
+            .translate(Coord::new(0, 24))
+            .into_iter(),
+    );
+
+    let mut supply_str: String<U32> = String::new();
+    if let Some(supply) = status.supply() {
+        write!(supply_str, "{}", supply.compact()).ok();
+    }
+
+    canvas.draw(
+        Font6x8::render_str(&supply_str)
+            .translate(Coord::new(68, 24))
+            .into_iter(),
+    );
+}
+
+fn draw_status_tall<C>(canvas: &mut C, status: &Status, now: Instant)
+where
+    C: Canvas,
+{
+    let (width, _) = canvas.dimensions();
+
+    // Percent on the left and state on the right in the large font
+    let mut duty_str: String<U8> = String::new();
+    write!(duty_str, "{}", Duty(status.pwm()).compact()).ok();
+    canvas.draw(Font12x16::render_str(&duty_str).into_iter());
+
+    let mut state_str: String<U8> = String::new();
+    match status.fault() {
+        Some(fault) => write!(state_str, "{}", fault.compact()).ok(),
+        None => write!(state_str, "{}", status.state().compact()).ok(),
+    };
+    let x = width as i32 - MARGIN - 12 * state_str.len() as i32;
+    canvas.draw(
+        Font12x16::render_str(&state_str)
+            .translate(Coord::new(x, 0))
+            .into_iter(),
+    );
+
+    LevelBar {
+        origin: Coord::new(0, 20),
+        width: width as i32 - MARGIN,
+        height: 8,
+        label: false,
+    }
+    .draw(status, now, canvas);
+
+    StrobeIndicator {
+        origin: Coord::new(0, 32),
+        size: 10,
+    }
+    .draw(status, now, canvas);
+
+    Icon {
+        origin: Coord::new(72, 32),
+        kind: IconKind::Relay,
+    }
+    .draw(status, now, canvas);
+
+    Icon {
+        origin: Coord::new(100, 32),
+        kind: IconKind::OutputEnable,
+    }
+    .draw(status, now, canvas);
+
+    let mut supply_str: String<U32> = String::new();
+    if let Some(supply) = status.supply() {
+        write!(supply_str, "{}", supply.compact()).ok();
+    }
+
+    canvas.draw(
+        Font6x8::render_str(&supply_str)
+            .translate(Coord::new(0, 44))
+            .into_iter(),
+    );
+
+    // Spelled out, the top row only has room for the short form
+    let mut fault_str: String<U32> = String::new();
+    if let Some(fault) = status.fault() {
+        write!(fault_str, "{}", fault).ok();
+    }
+
+    canvas.draw(
+        Font6x8::render_str(&fault_str)
+            .translate(Coord::new(0, 55))
+            .into_iter(),
+    );
+}
+
+// The trace name and latest value over a graph of the history, with the
+// scale in the top right
+pub fn draw_trend<C, N>(canvas: &mut C, history: &History<N>, trace: Trace)
+where
+    C: Canvas,
+    N: ArrayLength<Sample>,
+{
+    canvas.clear();
+
+    let (width, height) = canvas.dimensions();
+    let graph = TrendGraph {
+        origin: Coord::new(0, 10),
+        width: width as i32 - MARGIN,
+        height: height as i32 - 11,
+        trace,
+        history,
+    };
+    graph.draw(canvas);
+
+    let mut title_str: String<U32> = String::new();
+    write!(title_str, "{}", trace.compact()).ok();
+    if let Some(value) = history.latest().and_then(|s| trace.value(s)) {
+        title_str.push(' ').ok();
+        write_value(&mut title_str, trace, value);
+    }
+
+    canvas.draw(Font6x8::render_str(&title_str).into_iter());
+
+    let mut range_str: String<U32> = String::new();
+    if let Some((lo, hi)) = graph.range() {
+        write_value(&mut range_str, trace, lo);
+        range_str.push('-').ok();
+        write_value(&mut range_str, trace, hi);
+    }
+
+    let x = width as i32 - MARGIN - 6 * range_str.len() as i32;
+    canvas.draw(
+        Font6x8::render_str(&range_str)
+            .translate(Coord::new(x, 0))
+            .into_iter(),
+    );
+}
+
+fn write_value<W>(w: &mut W, trace: Trace, value: i32)
+where
+    W: Write,
+{
+    match trace {
+        Trace::Pwm => write!(w, "{}", Duty(value as u16).compact()),
+        Trace::PwmInput | Trace::FreqInput => write!(w, "{}", value),
+        Trace::Temperature => write!(w, "{}C", value),
+    }
+    .ok();
+}
@@ -0,0 +1,418 @@
+use crate::telemetry::Format;
+use core::fmt::{self, Write};
+use heapless::consts::{U64, U8};
+use heapless::{String, Vec};
+use lmc_proto::packet::{ErrorCode, Request};
+use lmc_types::status::PWM_MAX;
+
+const PROMPT: &str = "> ";
+
+const HELP: &str = "\
+help              this list
+status            status and inputs
+pwm <0-4095>      hold the PWM until the pot moves
+freq <hz|cont>    hold the strobe rate until the pot moves
+relay on|off      like B1 and B0, off also clears a fault
+oe on|off         like holding B2, lapses after 500 ms unless sent again
+preset [n]        list or apply a preset
+log [level]       show or set: off, error, info, debug
+telemetry [f] [n] show or set: off, csv, json, every n ms
+reboot            outputs off and reset
+";
+
+// Console messages at or below the level are shown, shell replies always are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Off,
+    Error,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(Level::Off),
+            "error" => Some(Level::Error),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Level::Off => "off",
+            Level::Error => "error",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Status,
+    Pwm(u16),
+    // Hz, None for continuous
+    Freq(Option<u32>),
+    Relay(bool),
+    Oe(bool),
+    Presets,
+    Preset(usize),
+    // None shows the current level
+    Log(Option<Level>),
+    // Format, None for off, and interval in ms. None shows the current
+    // setting.
+    Telemetry(Option<(Option<Format>, Option<u32>)>),
+    Reboot,
+}
+
+impl Command {
+    // The error is the usage to print
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let arg = words.next();
+        // Only telemetry takes a second argument
+        let interval = if name == "telemetry" {
+            words.next()
+        } else {
+            None
+        };
+        if words.next().is_some() {
+            return Err("too many arguments, try help");
+        }
+
+        match (name, arg) {
+            ("help", None) | ("?", None) => Ok(Command::Help),
+            ("status", None) => Ok(Command::Status),
+            ("pwm", Some(n)) => n
+                .parse()
+                .ok()
+                .filter(|&n| n <= PWM_MAX)
+                .map(Command::Pwm)
+                .ok_or("usage: pwm <0-4095>"),
+            ("freq", Some("cont")) => Ok(Command::Freq(None)),
+            ("freq", Some(hz)) => match hz.parse() {
+                Ok(0) => Ok(Command::Freq(None)),
+                Ok(hz) => Ok(Command::Freq(Some(hz))),
+                Err(_) => Err("usage: freq <hz|cont>"),
+            },
+            ("relay", Some(s)) => on_off(s).map(Command::Relay).ok_or("usage: relay on|off"),
+            ("oe", Some(s)) => on_off(s).map(Command::Oe).ok_or("usage: oe on|off"),
+            ("preset", None) => Ok(Command::Presets),
+            ("preset", Some(n)) => n
+                .parse()
+                .map(Command::Preset)
+                .map_err(|_| "usage: preset [n]"),
+            ("log", None) => Ok(Command::Log(None)),
+            ("log", Some(level)) => Level::parse(level)
+                .map(|l| Command::Log(Some(l)))
+                .ok_or("usage: log [off|error|info|debug]"),
+            ("telemetry", None) => Ok(Command::Telemetry(None)),
+            ("telemetry", Some(format)) => {
+                let usage = "usage: telemetry [off|csv|json] [100-60000]";
+                let format = match format {
+                    "off" => None,
+                    "csv" => Some(Format::Csv),
+                    "json" => Some(Format::Json),
+                    _ => return Err(usage),
+                };
+                let interval = match interval {
+                    Some(ms) => Some(
+                        ms.parse()
+                            .ok()
+                            .filter(|ms| (100..=60_000).contains(ms))
+                            .ok_or(usage)?,
+                    ),
+                    None => None,
+                };
+                Ok(Command::Telemetry(Some((format, interval))))
+            }
+            ("reboot", None) => Ok(Command::Reboot),
+            ("pwm", None) | ("freq", None) | ("relay", None) | ("oe", None) => {
+                Err("missing argument, try help")
+            }
+            _ => Err("unknown command, try help"),
+        }
+    }
+
+    // What a protocol request does, None for a ping which the link answers
+    // itself
+    pub fn from_request(request: Request) -> Option<Self> {
+        match request {
+            Request::Ping => None,
+            Request::GetStatus => Some(Command::Status),
+            Request::SetPwm(pwm) => Some(Command::Pwm(pwm)),
+            Request::SetFreq(0) => Some(Command::Freq(None)),
+            Request::SetFreq(hz) => Some(Command::Freq(Some(hz))),
+            Request::SetRelay(on) => Some(Command::Relay(on)),
+            Request::SetOe(on) => Some(Command::Oe(on)),
+            Request::ApplyPreset(i) => Some(Command::Preset(i as usize)),
+        }
+    }
+
+    // False for a PWM or strobe rate above the limits, continuous is always
+    // within them
+    pub fn within(self, pwm_max: u16, freq_max: u32) -> bool {
+        match self {
+            Command::Pwm(pwm) => pwm <= pwm_max,
+            Command::Freq(Some(hz)) => hz <= freq_max,
+            _ => true,
+        }
+    }
+
+    // The refusals known before carrying the command out, so several can be
+    // refused together. Faulted is only known once carried out.
+    pub fn check(
+        self,
+        menu_open: bool,
+        pwm_max: u16,
+        freq_max: u32,
+        presets: usize,
+    ) -> Result<(), Refusal> {
+        match self {
+            Command::Relay(_) | Command::Oe(_) if menu_open => Err(Refusal::MenuOpen),
+            Command::Preset(i) if i >= presets => Err(Refusal::NoSuchPreset),
+            command if !command.within(pwm_max, freq_max) => Err(Refusal::OutOfRange),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn help() -> &'static str {
+        HELP
+    }
+}
+
+// Why a command wasn't carried out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Refusal {
+    MenuOpen,
+    Faulted,
+    OutOfRange,
+    NoSuchPreset,
+}
+
+impl Refusal {
+    pub fn code(self) -> ErrorCode {
+        match self {
+            Refusal::MenuOpen => ErrorCode::Busy,
+            Refusal::Faulted => ErrorCode::Refused,
+            Refusal::OutOfRange | Refusal::NoSuchPreset => ErrorCode::OutOfRange,
+        }
+    }
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Refusal::MenuOpen => "close the menu first",
+            Refusal::Faulted => "refused, clear the fault first",
+            Refusal::OutOfRange => "out of range",
+            Refusal::NoSuchPreset => "no such preset",
+        })
+    }
+}
+
+fn on_off(s: &str) -> Option<bool> {
+    match s {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    // After ESC
+    Start,
+    // After ESC [
+    Csi,
+}
+
+// Line editor for a serial terminal. Echoes as it goes, with backspace,
+// Ctrl-U to erase the line, Ctrl-C to drop it, and the up and down arrows
+// (or Ctrl-P and Ctrl-N) to recall earlier lines.
+pub struct Shell {
+    line: String<U64>,
+    // Oldest first
+    history: Vec<String<U64>, U8>,
+    // Index into history while recalling
+    recall: Option<usize>,
+    escape: Escape,
+    // Swallows the LF of a CR LF
+    after_cr: bool,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        Shell {
+            line: String::new(),
+            history: Vec::new(),
+            recall: None,
+            escape: Escape::None,
+            after_cr: false,
+        }
+    }
+
+    pub fn prompt<W>(&self, out: &mut W)
+    where
+        W: Write,
+    {
+        out.write_str(PROMPT).ok();
+    }
+
+    // Returns a completed non-empty line, run it and then show the prompt
+    // again
+    pub fn feed<W>(&mut self, byte: u8, out: &mut W) -> Option<String<U64>>
+    where
+        W: Write,
+    {
+        let after_cr = self.after_cr;
+        self.after_cr = byte == b'\r';
+
+        match (self.escape, byte) {
+            (Escape::Start, b'[') => {
+                self.escape = Escape::Csi;
+                return None;
+            }
+            (Escape::Csi, b'A') => self.recall_older(out),
+            (Escape::Csi, b'B') => self.recall_newer(out),
+            // Other sequences are ignored up to their final byte
+            (Escape::Csi, 0x20..=0x3F) => return None,
+            (Escape::Start, _) | (Escape::Csi, _) => (),
+
+            (Escape::None, 0x1B) => {
+                self.escape = Escape::Start;
+                return None;
+            }
+            (Escape::None, b'\n') if after_cr => (),
+            (Escape::None, b'\r') | (Escape::None, b'\n') => return self.enter(out),
+            // Backspace and DEL
+            (Escape::None, 0x08) | (Escape::None, 0x7F) => self.backspace(out),
+            // Ctrl-U
+            (Escape::None, 0x15) => {
+                self.recall = None;
+                self.replace(None, out);
+            }
+            // Ctrl-C
+            (Escape::None, 0x03) => {
+                self.line = String::new();
+                self.recall = None;
+                out.write_str("^C\r\n").ok();
+                self.prompt(out);
+            }
+            // Ctrl-P and Ctrl-N
+            (Escape::None, 0x10) => self.recall_older(out),
+            (Escape::None, 0x0E) => self.recall_newer(out),
+            (Escape::None, 0x20..=0x7E) => self.insert(byte as char, out),
+            _ => (),
+        }
+
+        self.escape = Escape::None;
+        None
+    }
+
+    // Dropped once the line is full
+    fn insert<W>(&mut self, c: char, out: &mut W)
+    where
+        W: Write,
+    {
+        if self.line.push(c).is_ok() {
+            out.write_char(c).ok();
+        }
+    }
+
+    fn backspace<W>(&mut self, out: &mut W)
+    where
+        W: Write,
+    {
+        if self.line.pop().is_some() {
+            out.write_str("\x08 \x08").ok();
+        }
+    }
+
+    fn enter<W>(&mut self, out: &mut W) -> Option<String<U64>>
+    where
+        W: Write,
+    {
+        out.write_str("\r\n").ok();
+        self.recall = None;
+
+        let line = core::mem::replace(&mut self.line, String::new());
+        if line.trim().is_empty() {
+            self.prompt(out);
+            return None;
+        }
+
+        if self.history.last() != Some(&line) {
+            if self.history.len() == self.history.capacity() {
+                for i in 1..self.history.len() {
+                    self.history[i - 1] = self.history[i].clone();
+                }
+                self.history.pop();
+            }
+            self.history.push(line.clone()).ok();
+        }
+
+        Some(line)
+    }
+
+    fn recall_older<W>(&mut self, out: &mut W)
+    where
+        W: Write,
+    {
+        let i = match self.recall {
+            Some(0) => return,
+            Some(i) => i - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.recall = Some(i);
+        self.replace(Some(i), out);
+    }
+
+    fn recall_newer<W>(&mut self, out: &mut W)
+    where
+        W: Write,
+    {
+        match self.recall {
+            Some(i) if i + 1 < self.history.len() => {
+                self.recall = Some(i + 1);
+                self.replace(Some(i + 1), out);
+            }
+            Some(_) => {
+                self.recall = None;
+                self.replace(None, out);
+            }
+            None => (),
+        }
+    }
+
+    // Erases the line on the terminal, then shows the history entry, if any
+    fn replace<W>(&mut self, entry: Option<usize>, out: &mut W)
+    where
+        W: Write,
+    {
+        for _ in 0..self.line.len() {
+            out.write_str("\x08 \x08").ok();
+        }
+
+        self.line = match entry {
+            Some(i) => self.history[i].clone(),
+            None => String::new(),
+        };
+        out.write_str(&self.line).ok();
+    }
+}
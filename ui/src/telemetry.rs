@@ -0,0 +1,246 @@
+use core::fmt::{self, Write};
+use heapless::consts::U512;
+use heapless::String;
+use lmc_types::fault::FaultCounts;
+use lmc_types::status::{Fault, Freq, Status};
+use lmc_types::time::Instant;
+
+// One record, and the CSV header before the first after enabling CSV
+pub type Line = String<U512>;
+
+// Columns of a record, also the JSON keys. The state and fault use the
+// compact forms, empty or null without a fault or supply measurement.
+const COLUMNS: [&str; 18] = [
+    "time_ms",
+    "state",
+    "fault",
+    "pwm",
+    "freq_mhz",
+    "oe",
+    "relay",
+    "vdda_mv",
+    "temperature",
+    "display",
+    "ain_pwm",
+    "ain_freq",
+    "loop_us",
+    "loop_max_us",
+    "under_voltage_faults",
+    "over_temperature_faults",
+    "input_faults",
+    "dropped",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Csv,
+    // JSON Lines, an object per line
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Record {
+    pub time: Instant,
+    pub status: Status,
+    // Setpoint inputs, PWM then strobe rate
+    pub inputs: [u16; 2],
+    // The last loop and the longest since the previous record
+    pub loop_us: u32,
+    pub loop_max_us: u32,
+    pub faults: FaultCounts,
+    // Console writes dropped for want of room, wrapping
+    pub dropped: u32,
+}
+
+enum Value<'a> {
+    Null,
+    Bool(bool),
+    Int(i32),
+    Uint(u32),
+    Text(&'a dyn fmt::Display),
+}
+
+impl<'a> Value<'a> {
+    fn write<W>(&self, format: Format, out: &mut W) -> fmt::Result
+    where
+        W: Write,
+    {
+        match (self, format) {
+            (Value::Null, Format::Csv) => Ok(()),
+            (Value::Null, Format::Json) => out.write_str("null"),
+            (Value::Bool(b), Format::Csv) => out.write_str(if *b { "1" } else { "0" }),
+            (Value::Bool(b), Format::Json) => write!(out, "{}", b),
+            (Value::Int(n), _) => write!(out, "{}", n),
+            (Value::Uint(n), _) => write!(out, "{}", n),
+            // None of the texts need quoting or escaping
+            (Value::Text(t), Format::Csv) => write!(out, "{}", t),
+            (Value::Text(t), Format::Json) => write!(out, "\"{}\"", t),
+        }
+    }
+}
+
+impl Record {
+    pub fn write_header<W>(out: &mut W) -> fmt::Result
+    where
+        W: Write,
+    {
+        for (i, column) in COLUMNS.iter().enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            out.write_str(column)?;
+        }
+        out.write_char('\n')
+    }
+
+    // A line, newline included
+    pub fn write<W>(&self, format: Format, out: &mut W) -> fmt::Result
+    where
+        W: Write,
+    {
+        let status = &self.status;
+        let state = status.state().compact();
+        let fault = status.fault().map(Fault::compact);
+        let supply = status.supply();
+        let display = status.display();
+
+        let values: [Value; 18] = [
+            Value::Uint(self.time.millis()),
+            Value::Text(&state),
+            match fault {
+                Some(ref fault) => Value::Text(fault),
+                None => Value::Null,
+            },
+            Value::Uint(status.pwm() as u32),
+            Value::Uint(match status.freq() {
+                Freq::Continuous => 0,
+                Freq::Periodic(mhz) => mhz,
+            }),
+            Value::Bool(status.pwm_oe()),
+            Value::Bool(status.pwm_relay()),
+            supply.map_or(Value::Null, |s| Value::Uint(s.vdda_mv() as u32)),
+            supply.map_or(Value::Null, |s| Value::Int(s.temperature() as i32)),
+            Value::Text(&display),
+            Value::Uint(self.inputs[0] as u32),
+            Value::Uint(self.inputs[1] as u32),
+            Value::Uint(self.loop_us),
+            Value::Uint(self.loop_max_us),
+            Value::Uint(self.faults.under_voltage as u32),
+            Value::Uint(self.faults.over_temperature as u32),
+            Value::Uint(self.faults.input as u32),
+            Value::Uint(self.dropped),
+        ];
+
+        if format == Format::Json {
+            out.write_char('{')?;
+        }
+        for (i, (column, value)) in COLUMNS.iter().zip(values.iter()).enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            if format == Format::Json {
+                write!(out, "\"{}\":", column)?;
+            }
+            value.write(format, out)?;
+        }
+        if format == Format::Json {
+            out.write_char('}')?;
+        }
+        out.write_char('\n')
+    }
+}
+
+// Emits a record every interval while enabled, and keeps the loop time and
+// fault counts in between
+pub struct Publisher {
+    // None when off
+    format: Option<Format>,
+    interval_ms: u32,
+    last: Option<Instant>,
+    header: bool,
+    loop_max_us: u32,
+    faults: FaultCounts,
+    fault: Option<Fault>,
+}
+
+impl Publisher {
+    pub fn new(interval_ms: u32) -> Self {
+        Publisher {
+            format: None,
+            interval_ms,
+            last: None,
+            header: false,
+            loop_max_us: 0,
+            faults: FaultCounts::default(),
+            fault: None,
+        }
+    }
+
+    pub fn format(&self) -> Option<Format> {
+        self.format
+    }
+
+    // Starts over with a record right away, and a header for CSV
+    pub fn set_format(&mut self, format: Option<Format>) {
+        self.format = format;
+        self.last = None;
+        self.loop_max_us = 0;
+        self.header = format == Some(Format::Csv);
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval_ms
+    }
+
+    pub fn set_interval(&mut self, interval_ms: u32) {
+        self.interval_ms = interval_ms;
+    }
+
+    pub fn faults(&self) -> FaultCounts {
+        self.faults
+    }
+
+    // Call every loop, returns the line to send when a record is due
+    pub fn update(
+        &mut self,
+        now: Instant,
+        status: &Status,
+        inputs: [u16; 2],
+        loop_us: u32,
+        dropped: u32,
+    ) -> Option<Line> {
+        if let Some(fault) = status.fault() {
+            if self.fault != Some(fault) {
+                self.faults.count(fault);
+            }
+        }
+        self.fault = status.fault();
+        self.loop_max_us = self.loop_max_us.max(loop_us);
+
+        let format = self.format?;
+        match self.last {
+            Some(last) if now.since(last) < self.interval_ms => return None,
+            _ => self.last = Some(now),
+        }
+
+        let record = Record {
+            time: now,
+            status: *status,
+            inputs,
+            loop_us,
+            loop_max_us: self.loop_max_us,
+            faults: self.faults,
+            dropped,
+        };
+        self.loop_max_us = 0;
+
+        let mut line = Line::new();
+        if self.header {
+            self.header = false;
+            Record::write_header(&mut line).ok();
+        }
+        // Always fits, the longest JSON record is under 400 bytes
+        record.write(format, &mut line).ok();
+        Some(line)
+    }
+}
@@ -0,0 +1,262 @@
+use crate::history::{History, Sample, Trace};
+use core::fmt::Write;
+use embedded_graphics::fonts::Font6x8;
+use embedded_graphics::pixelcolor::PixelColorU8;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Line, Rect};
+use embedded_graphics::Drawing;
+use heapless::consts::U8;
+use heapless::{ArrayLength, String};
+use lmc_types::format::Duty;
+use lmc_types::status::{Freq, Status, PWM_MAX};
+use lmc_types::time::Instant;
+
+// Above this the panel refresh can't keep up with a blink, the indicator
+// shows a steady fast-strobe pattern instead
+const BLINK_MAX_HZ: u32 = 5;
+
+// A status screen component, drawn at its position from the latest Status
+pub trait Widget {
+    fn draw<D>(&self, status: &Status, now: Instant, target: &mut D)
+    where
+        D: Drawing<PixelColorU8>;
+}
+
+// Horizontal PWM level bar, optionally with the percent to its right
+pub struct LevelBar {
+    pub origin: Coord,
+    pub width: i32,
+    pub height: i32,
+    pub label: bool,
+}
+
+impl Widget for LevelBar {
+    fn draw<D>(&self, status: &Status, _now: Instant, target: &mut D)
+    where
+        D: Drawing<PixelColorU8>,
+    {
+        let pwm = status.pwm() as i32;
+        let (x, y) = (self.origin[0], self.origin[1]);
+        let bottom = y + self.height - 1;
+
+        target.draw(
+            Rect::new(self.origin, Coord::new(x + self.width - 1, bottom))
+                .with_stroke(Some(1u8.into()))
+                .into_iter(),
+        );
+
+        // Inside the outline
+        let fill = (self.width - 4) * pwm / PWM_MAX as i32;
+        if fill > 0 {
+            target.draw(
+                Rect::new(
+                    Coord::new(x + 2, y + 2),
+                    Coord::new(x + 1 + fill, bottom - 2),
+                )
+                .with_fill(Some(1u8.into()))
+                .into_iter(),
+            );
+        }
+
+        if !self.label {
+            return;
+        }
+
+        let mut text: String<U8> = String::new();
+        write!(text, "{}", Duty(status.pwm()).compact()).ok();
+        target.draw(
+            Font6x8::render_str(&text)
+                .translate(Coord::new(x + self.width + 3, y + (self.height - 8) / 2))
+                .into_iter(),
+        );
+    }
+}
+
+// Square lit for continuous output, blinking at the strobe rate, with the
+// rate to its right
+pub struct StrobeIndicator {
+    pub origin: Coord,
+    pub size: i32,
+}
+
+impl StrobeIndicator {
+    // Whether the square is filled at the given time
+    pub fn lit(status: &Status, now: Instant) -> bool {
+        match status.freq() {
+            Freq::Continuous => true,
+            Freq::Periodic(mhz) if mhz > BLINK_MAX_HZ * 1000 => false,
+            Freq::Periodic(mhz) => {
+                // On for the first half of each period
+                let half_periods = now.millis() as u64 * 2 * mhz as u64 / 1_000_000;
+                half_periods & 1 == 0
+            }
+        }
+    }
+}
+
+impl Widget for StrobeIndicator {
+    fn draw<D>(&self, status: &Status, now: Instant, target: &mut D)
+    where
+        D: Drawing<PixelColorU8>,
+    {
+        let (x, y) = (self.origin[0], self.origin[1]);
+        let far = Coord::new(x + self.size - 1, y + self.size - 1);
+        let mut text: String<U8> = String::new();
+
+        write!(text, "{}", status.freq().compact()).ok();
+        let fast = match status.freq() {
+            Freq::Continuous => false,
+            Freq::Periodic(mhz) => mhz > BLINK_MAX_HZ * 1000,
+        };
+
+        let outline = Rect::new(self.origin, far).with_stroke(Some(1u8.into()));
+        if Self::lit(status, now) {
+            target.draw(outline.with_fill(Some(1u8.into())).into_iter());
+        } else {
+            target.draw(outline.into_iter());
+        }
+
+        if fast {
+            let c = self.size / 2;
+            target.draw(
+                Rect::new(
+                    Coord::new(x + c - 1, y + c - 1),
+                    Coord::new(x + c + 1, y + c + 1),
+                )
+                .with_fill(Some(1u8.into()))
+                .into_iter(),
+            );
+        }
+
+        target.draw(
+            Font6x8::render_str(&text)
+                .translate(Coord::new(x + self.size + 3, y + (self.size - 8) / 2))
+                .into_iter(),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconKind {
+    Relay,
+    OutputEnable,
+}
+
+// Labelled box, inverted while the relay or OE is on
+pub struct Icon {
+    pub origin: Coord,
+    pub kind: IconKind,
+}
+
+impl Widget for Icon {
+    fn draw<D>(&self, status: &Status, _now: Instant, target: &mut D)
+    where
+        D: Drawing<PixelColorU8>,
+    {
+        let (label, on) = match self.kind {
+            IconKind::Relay => ("RLY", status.pwm_relay()),
+            IconKind::OutputEnable => ("OE", status.pwm_oe()),
+        };
+
+        let (x, y) = (self.origin[0], self.origin[1]);
+        let width = label.len() as i32 * 6 + 4;
+        let (fg, bg) = if on { (0u8, 1u8) } else { (1u8, 0u8) };
+
+        target.draw(
+            Rect::new(self.origin, Coord::new(x + width - 1, y + 9))
+                .with_stroke(Some(1u8.into()))
+                .with_fill(Some(bg.into()))
+                .into_iter(),
+        );
+        target.draw(
+            Font6x8::render_str(label)
+                .with_stroke(Some(fg.into()))
+                .with_fill(Some(bg.into()))
+                .translate(Coord::new(x + 2, y + 1))
+                .into_iter(),
+        );
+    }
+}
+
+// Scrolling line graph of one trace inside an outline, a sample per column
+// with the newest on the right, scaled to the range of the samples shown
+pub struct TrendGraph<'a, N>
+where
+    N: ArrayLength<Sample>,
+{
+    pub origin: Coord,
+    pub width: i32,
+    pub height: i32,
+    pub trace: Trace,
+    pub history: &'a History<N>,
+}
+
+impl<'a, N> TrendGraph<'a, N>
+where
+    N: ArrayLength<Sample>,
+{
+    // Bottom and top of the scale, None without any values to show
+    pub fn range(&self) -> Option<(i32, i32)> {
+        let mut values = self.values().flatten();
+        let first = values.next()?;
+        let (lo, hi) = values.fold((first, first), |(lo, hi), v| (lo.min(v), hi.max(v)));
+
+        let span = self.trace.min_span();
+        if hi - lo >= span {
+            Some((lo, hi))
+        } else {
+            let lo = (lo + hi - span) / 2;
+            Some((lo, lo + span))
+        }
+    }
+
+    // The samples that fit, oldest first
+    fn values(&self) -> impl Iterator<Item = Option<i32>> + '_ {
+        let columns = (self.width - 2).max(0) as usize;
+        let trace = self.trace;
+        self.history
+            .iter()
+            .skip(self.history.len().saturating_sub(columns))
+            .map(move |sample| trace.value(sample))
+    }
+
+    // Draws from the history rather than a Status
+    pub fn draw<D>(&self, target: &mut D)
+    where
+        D: Drawing<PixelColorU8>,
+    {
+        let (x, y) = (self.origin[0], self.origin[1]);
+        let bottom = y + self.height - 1;
+
+        target.draw(
+            Rect::new(self.origin, Coord::new(x + self.width - 1, bottom))
+                .with_stroke(Some(1u8.into()))
+                .into_iter(),
+        );
+
+        let (lo, hi) = match self.range() {
+            Some(range) => range,
+            None => return,
+        };
+
+        // Right aligned inside the outline
+        let plot_height = self.height - 3;
+        let shown = self.values().count() as i32;
+        let left = x + self.width - 1 - shown;
+        let point =
+            |i: i32, v: i32| Coord::new(left + i, bottom - 1 - (v - lo) * plot_height / (hi - lo));
+
+        let mut prev: Option<Coord> = None;
+        for (i, value) in self.values().enumerate() {
+            let next = value.map(|v| point(i as i32, v));
+            if let Some(end) = next {
+                target.draw(
+                    Line::new(prev.unwrap_or(end), end)
+                        .with_stroke(Some(1u8.into()))
+                        .into_iter(),
+                );
+            }
+            prev = next;
+        }
+    }
+}
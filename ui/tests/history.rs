@@ -0,0 +1,52 @@
+use heapless::consts::U4;
+use lmc_types::time::Instant;
+use lmc_ui::history::{History, Sample, Trace};
+
+fn pwm(pwm: u16) -> Sample {
+    Sample {
+        pwm,
+        ..Sample::default()
+    }
+}
+
+fn pwms(history: &History<U4>) -> Vec<u16> {
+    history.iter().map(|s| s.pwm).collect()
+}
+
+#[test]
+fn ring_keeps_the_newest() {
+    let mut history: History<U4> = History::new(0);
+    for i in 1..=3 {
+        history.push(pwm(i));
+    }
+    assert_eq!(pwms(&history), [1, 2, 3]);
+
+    for i in 4..=6 {
+        history.push(pwm(i));
+    }
+    assert_eq!(pwms(&history), [3, 4, 5, 6]);
+    assert_eq!(history.latest(), Some(&pwm(6)));
+}
+
+#[test]
+fn samples_once_per_interval() {
+    let mut history: History<U4> = History::new(500);
+    assert!(history.update(Instant::from_millis(1000), pwm(1)));
+    assert!(!history.update(Instant::from_millis(1499), pwm(2)));
+    assert!(history.update(Instant::from_millis(1500), pwm(3)));
+    assert_eq!(pwms(&history), [1, 3]);
+
+    history.clear();
+    assert!(history.is_empty());
+    assert!(history.update(Instant::from_millis(1600), pwm(4)));
+}
+
+#[test]
+fn temperature_is_optional() {
+    let sample = Sample {
+        temperature: None,
+        ..pwm(100)
+    };
+    assert_eq!(Trace::Pwm.value(&sample), Some(100));
+    assert_eq!(Trace::Temperature.value(&sample), None);
+}
@@ -0,0 +1,39 @@
+use lmc_ui::menu::{Event, Field, Full, Menu};
+
+#[test]
+fn full_pages_and_items_are_errors() {
+    let mut menu: Menu<u8> = Menu::new();
+    for _ in 0..8 {
+        assert!(menu.add_page("PAGE").is_ok());
+    }
+    assert_eq!(menu.add_page("PAGE"), Err(Full));
+
+    let page = menu.page();
+    for i in 0..8 {
+        assert_eq!(menu.add_item(page, i, "ITEM", Field::Action), Ok(()));
+    }
+    assert_eq!(menu.add_item(page, 8, "ITEM", Field::Action), Err(Full));
+}
+
+#[test]
+fn select_starts_and_ends_an_edit() {
+    let mut menu: Menu<u8> = Menu::new();
+    let page = menu.add_page("PAGE").unwrap();
+    let field = Field::Number {
+        value: 1,
+        min: 1,
+        max: 247,
+        step: 1,
+    };
+    menu.add_item(page, 0, "ADDRESS", field).unwrap();
+    menu.set_page(page);
+    assert!(!menu.editing());
+
+    menu.handle(Event::Select);
+    assert!(menu.editing());
+    menu.handle(Event::Down);
+    assert_eq!(menu.number(0), 2);
+    menu.handle(Event::Select);
+    assert!(!menu.editing());
+    assert_eq!(menu.number(0), 2);
+}
@@ -0,0 +1,226 @@
+// Snapshots of the screens rendered into a Framebuffer, '#' for on pixels.
+// After an intended change to a screen, regenerate them with
+// UPDATE_SNAPSHOTS=1 cargo test --target <host triple>
+
+use heapless::consts::U128;
+use lmc_types::status::{Fault, Freq, State, Status, Supply, PWM_MAX};
+use lmc_types::time::Instant;
+use lmc_ui::bitmap::Bitmap;
+use lmc_ui::canvas::Framebuffer;
+use lmc_ui::history::{History, Sample, Trace};
+use lmc_ui::menu::{Event, Field, Menu};
+use lmc_ui::screen;
+use std::fs;
+use std::path::PathBuf;
+
+fn check(name: &str, fb: &Framebuffer) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots", name]
+        .iter()
+        .collect();
+    let actual = fb.to_string();
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected =
+        fs::read_to_string(&path).unwrap_or_else(|_| panic!("missing snapshot {}", path.display()));
+    assert!(
+        actual == expected,
+        "{} differs from the snapshot, got:\n{}",
+        name,
+        actual
+    );
+}
+
+fn supply() -> Option<Supply> {
+    Some(Supply::new(3310, 31))
+}
+
+#[test]
+fn status_on_continuous() {
+    let status = Status::new(State::On, PWM_MAX / 2, Freq::Continuous)
+        .with_outputs(true, true)
+        .with_supply(supply());
+
+    let mut fb = Framebuffer::new(128, 32);
+    screen::draw_status(&mut fb, &status, Instant::from_millis(0));
+    check("status_on_continuous.txt", &fb);
+}
+
+#[test]
+fn status_off() {
+    let status = Status::new(State::Off, 0, Freq::Continuous);
+
+    let mut fb = Framebuffer::new(128, 32);
+    screen::draw_status(&mut fb, &status, Instant::from_millis(0));
+    check("status_off.txt", &fb);
+}
+
+#[test]
+fn status_fault() {
+    let status = Status::new(State::Error, PWM_MAX, Freq::Periodic(10_000))
+        .with_supply(supply())
+        .with_fault(Some(Fault::UnderVoltage));
+
+    let mut fb = Framebuffer::new(128, 32);
+    screen::draw_status(&mut fb, &status, Instant::from_millis(0));
+    check("status_fault.txt", &fb);
+}
+
+#[test]
+fn strobe_blinks() {
+    let status = Status::new(State::On, PWM_MAX, Freq::Periodic(2000)).with_outputs(true, true);
+
+    // 2 Hz, on for the first 250 ms of each 500 ms period
+    let mut on = Framebuffer::new(128, 32);
+    screen::draw_status(&mut on, &status, Instant::from_millis(1100));
+    let mut off = Framebuffer::new(128, 32);
+    screen::draw_status(&mut off, &status, Instant::from_millis(1300));
+
+    assert!(on.pixel(4, 16));
+    assert!(!off.pixel(4, 16));
+}
+
+#[test]
+fn menu_editing() {
+    let mut menu = Menu::new();
+    let page = menu.add_page("SETTINGS").unwrap();
+    menu.add_item(
+        page,
+        0,
+        "PWM MAX",
+        Field::Number {
+            value: 100,
+            min: 0,
+            max: 100,
+            step: 5,
+        },
+    )
+    .unwrap();
+    menu.add_item(
+        page,
+        1,
+        "ON FAULT",
+        Field::Choice {
+            index: 0,
+            options: &["OFF", "HOLD"],
+        },
+    )
+    .unwrap();
+    menu.set_page(page);
+    menu.handle(Event::Select);
+    menu.handle(Event::Up);
+
+    let mut fb = Framebuffer::new(128, 32);
+    screen::draw_menu(&mut fb, &menu);
+    check("menu_editing.txt", &fb);
+}
+
+#[test]
+fn status_tall() {
+    let status = Status::new(State::Error, PWM_MAX / 4, Freq::Periodic(2500))
+        .with_outputs(false, true)
+        .with_supply(supply())
+        .with_fault(Some(Fault::OverTemperature));
+
+    let mut fb = Framebuffer::new(128, 64);
+    screen::draw_status(&mut fb, &status, Instant::from_millis(0));
+    check("status_tall.txt", &fb);
+}
+
+#[test]
+fn menu_tall_shows_more_rows() {
+    let mut menu = Menu::new();
+    let page = menu.add_page("PRESETS").unwrap();
+    for (i, label) in ["DIM", "HALF", "FULL", "STROBE 10HZ", "SLOW"]
+        .iter()
+        .enumerate()
+    {
+        menu.add_item(page, i, label, Field::Action).unwrap();
+    }
+    menu.set_page(page);
+    for _ in 0..4 {
+        menu.handle(Event::Down);
+    }
+
+    // The compact layout scrolls to keep the cursor in its 3 rows
+    let mut compact = Framebuffer::new(128, 32);
+    screen::draw_menu(&mut compact, &menu);
+    check("menu_compact_scrolled.txt", &compact);
+
+    let mut tall = Framebuffer::new(128, 64);
+    screen::draw_menu(&mut tall, &menu);
+    check("menu_tall.txt", &tall);
+}
+
+#[test]
+fn prompt_tall() {
+    let mut fb = Framebuffer::new(128, 64);
+    screen::draw_prompt(&mut fb, &["CAL PWM", "TURN TO MIN", "B1: SET"]);
+    check("prompt_tall.txt", &fb);
+}
+
+// 12x8 outlined box with a cross, rows padded to 2 bytes
+const BOX: Bitmap = Bitmap {
+    width: 12,
+    height: 8,
+    data: &[
+        0xff, 0xf0, 0xc0, 0x30, 0xa0, 0x50, 0x90, 0x90, 0x89, 0x10, 0x86, 0x10, 0x80, 0x10, 0xff,
+        0xf0,
+    ],
+};
+
+#[test]
+fn splash() {
+    let mut fb = Framebuffer::new(128, 32);
+    screen::draw_splash(&mut fb, &BOX, &["LMC 0.1.0", "BUILD abc1234"]);
+    check("splash.txt", &fb);
+
+    // Centered vertically on the taller panel
+    let mut tall = Framebuffer::new(128, 64);
+    screen::draw_splash(&mut tall, &BOX, &["LMC 0.1.0", "BUILD abc1234"]);
+    assert!(tall.pixel(0, 28));
+    assert!(!tall.pixel(0, 27));
+}
+
+fn ramp() -> History<U128> {
+    // Up and back down, with the temperature dropping out for a while
+    let mut history = History::new(0);
+    for i in 0..150 {
+        let level = if i < 75 { i } else { 150 - i };
+        history.push(Sample {
+            pwm: level * 40,
+            inputs: [level * 50, 2048],
+            temperature: if (60..70).contains(&i) {
+                None
+            } else {
+                Some(30 + level as i16 / 10)
+            },
+        });
+    }
+    history
+}
+
+#[test]
+fn trend_pwm() {
+    let mut fb = Framebuffer::new(128, 32);
+    screen::draw_trend(&mut fb, &ramp(), Trace::Pwm);
+    check("trend_pwm.txt", &fb);
+}
+
+#[test]
+fn trend_temperature_tall() {
+    let mut fb = Framebuffer::new(128, 64);
+    screen::draw_trend(&mut fb, &ramp(), Trace::Temperature);
+    check("trend_temperature_tall.txt", &fb);
+}
+
+#[test]
+fn trend_flat_input() {
+    // Scaled to the minimum span, the line sits mid height
+    let mut fb = Framebuffer::new(128, 32);
+    screen::draw_trend(&mut fb, &ramp(), Trace::FreqInput);
+    assert!(fb.pixel(100, 20));
+}
@@ -0,0 +1,154 @@
+use lmc_proto::packet::Request;
+use lmc_ui::shell::{Command, Refusal, Shell};
+
+// Feeds the bytes, returning the completed lines and what was echoed
+fn feed(shell: &mut Shell, bytes: &[u8]) -> (Vec<String>, String) {
+    let mut out = String::new();
+    let mut lines = Vec::new();
+    for &byte in bytes {
+        if let Some(line) = shell.feed(byte, &mut out) {
+            lines.push(line.as_str().to_string());
+        }
+    }
+    (lines, out)
+}
+
+#[test]
+fn cr_lf_completes_one_line() {
+    let mut shell = Shell::new();
+    let (lines, out) = feed(&mut shell, b"status\r\nhelp\r\n");
+    assert_eq!(lines, ["status", "help"]);
+    assert_eq!(out, "status\r\nhelp\r\n");
+
+    // A bare CR or LF ends a line too
+    let (lines, _) = feed(&mut shell, b"pwm 1\rpwm 2\n");
+    assert_eq!(lines, ["pwm 1", "pwm 2"]);
+}
+
+#[test]
+fn empty_lines_only_prompt() {
+    let mut shell = Shell::new();
+    let (lines, out) = feed(&mut shell, b"  \r\n\n");
+    assert!(lines.is_empty());
+    assert_eq!(out, "  \r\n> \r\n> ");
+}
+
+#[test]
+fn backspace_and_ctrl_u_edit_the_line() {
+    let mut shell = Shell::new();
+    let (lines, _) = feed(&mut shell, b"statux\x7Fs\r");
+    assert_eq!(lines, ["status"]);
+
+    let (lines, out) = feed(&mut shell, b"ab\x15help\r");
+    assert_eq!(lines, ["help"]);
+    assert_eq!(out, "ab\x08 \x08\x08 \x08help\r\n");
+}
+
+#[test]
+fn arrows_recall_history() {
+    let mut shell = Shell::new();
+    feed(&mut shell, b"status\rhelp\r");
+
+    // Up twice, down once
+    let (lines, _) = feed(&mut shell, b"\x1B[A\x1B[A\x1B[B\r");
+    assert_eq!(lines, ["help"]);
+
+    // Ctrl-P and Ctrl-N do the same
+    let (lines, _) = feed(&mut shell, b"\x10\x10\x10\r");
+    assert_eq!(lines, ["status"]);
+
+    // Down past the newest entry gives an empty line back
+    let (lines, _) = feed(&mut shell, b"\x1B[A\x1B[Bpwm 0\r");
+    assert_eq!(lines, ["pwm 0"]);
+}
+
+#[test]
+fn other_escapes_are_ignored() {
+    let mut shell = Shell::new();
+    // Right arrow, Delete and F5, then an Alt-x
+    let (lines, out) = feed(&mut shell, b"st\x1B[C\x1B[3~\x1B[15~\x1Bxatus\r");
+    assert_eq!(lines, ["status"]);
+    assert_eq!(out, "status\r\n");
+}
+
+#[test]
+fn history_keeps_the_last_eight() {
+    let mut shell = Shell::new();
+    for i in 0..10 {
+        feed(&mut shell, format!("pwm {}\r", i).as_bytes());
+    }
+    // A repeat isn't stored twice
+    feed(&mut shell, b"pwm 9\r");
+
+    let (lines, _) = feed(&mut shell, &[0x10; 8]);
+    assert!(lines.is_empty());
+    let (lines, _) = feed(&mut shell, b"\r");
+    assert_eq!(lines, ["pwm 2"]);
+
+    // The oldest is as far as recall goes
+    let (lines, _) = feed(&mut shell, &[0x10; 12]);
+    assert!(lines.is_empty());
+    let (lines, _) = feed(&mut shell, b"\r");
+    assert_eq!(lines, ["pwm 3"]);
+}
+
+#[test]
+fn long_lines_are_cut_at_64() {
+    let mut shell = Shell::new();
+    let long = [b'x'; 80];
+    let (_, out) = feed(&mut shell, &long);
+    assert_eq!(out.len(), 64);
+    let (lines, _) = feed(&mut shell, b"\r");
+    assert_eq!(lines[0].len(), 64);
+}
+
+#[test]
+fn commands_parse() {
+    assert_eq!(Command::parse("pwm 4095"), Ok(Command::Pwm(4095)));
+    assert!(Command::parse("pwm 4096").is_err());
+    assert_eq!(Command::parse("freq cont"), Ok(Command::Freq(None)));
+    assert_eq!(Command::parse("freq 0"), Ok(Command::Freq(None)));
+    assert_eq!(Command::parse("oe on"), Ok(Command::Oe(true)));
+    assert!(Command::parse("oe").is_err());
+    assert!(Command::parse("status now").is_err());
+}
+
+#[test]
+fn setpoints_above_the_limits_are_not_within() {
+    let huge = Command::parse("freq 3000000000").unwrap();
+    assert_eq!(huge, Command::Freq(Some(3_000_000_000)));
+    assert!(!huge.within(4095, 100));
+
+    let request = Command::from_request(Request::SetFreq(u32::MAX)).unwrap();
+    assert!(!request.within(4095, 100));
+    assert!(Command::Freq(Some(100)).within(4095, 100));
+    assert!(!Command::Freq(Some(101)).within(4095, 100));
+    assert!(Command::Freq(None).within(4095, 0));
+
+    assert!(Command::Pwm(2048).within(2048, 100));
+    assert!(!Command::Pwm(2049).within(2048, 100));
+    assert!(Command::Status.within(0, 0));
+}
+
+#[test]
+fn refusals_known_before_carrying_out() {
+    assert_eq!(
+        Command::Relay(true).check(true, 4095, 100, 4),
+        Err(Refusal::MenuOpen)
+    );
+    assert_eq!(Command::Oe(false).check(false, 4095, 100, 4), Ok(()));
+    assert_eq!(Command::Pwm(4095).check(true, 4095, 100, 4), Ok(()));
+    assert_eq!(
+        Command::Pwm(4095).check(false, 2048, 100, 4),
+        Err(Refusal::OutOfRange)
+    );
+    assert_eq!(
+        Command::Freq(Some(101)).check(false, 4095, 100, 4),
+        Err(Refusal::OutOfRange)
+    );
+    assert_eq!(Command::Preset(3).check(false, 4095, 100, 4), Ok(()));
+    assert_eq!(
+        Command::Preset(4).check(false, 4095, 100, 4),
+        Err(Refusal::NoSuchPreset)
+    );
+}
@@ -0,0 +1,138 @@
+use lmc_types::fault::FaultCounts;
+use lmc_types::status::{Fault, Freq, Health, State, Status, Supply, PWM_MAX};
+use lmc_types::time::Instant;
+use lmc_ui::telemetry::{Format, Publisher, Record};
+
+fn record() -> Record {
+    Record {
+        time: Instant::from_millis(61_250),
+        status: Status::new(State::On, 2048, Freq::Periodic(12_500))
+            .with_outputs(true, false)
+            .with_supply(Some(Supply::new(3301, -4))),
+        inputs: [2047, 310],
+        loop_us: 820,
+        loop_max_us: 24_310,
+        faults: FaultCounts {
+            under_voltage: 1,
+            over_temperature: 0,
+            input: 2,
+        },
+        dropped: 3,
+    }
+}
+
+fn write(record: &Record, format: Format) -> String {
+    let mut out = String::new();
+    record.write(format, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn csv() {
+    let mut header = String::new();
+    Record::write_header(&mut header).unwrap();
+    assert_eq!(
+        header,
+        "time_ms,state,fault,pwm,freq_mhz,oe,relay,vdda_mv,temperature,display,ain_pwm,\
+         ain_freq,loop_us,loop_max_us,under_voltage_faults,over_temperature_faults,\
+         input_faults,dropped\n"
+    );
+    assert_eq!(
+        write(&record(), Format::Csv),
+        "61250,ON,,2048,12500,1,0,3301,-4,ok,2047,310,820,24310,1,0,2,3\n"
+    );
+
+    let mut faulted = record();
+    faulted.status = Status::new(State::Error, 0, Freq::Continuous)
+        .with_fault(Some(Fault::UnderVoltage))
+        .with_display(Health::Lost);
+    assert_eq!(
+        write(&faulted, Format::Csv),
+        "61250,ERR,UV,0,0,0,0,,,lost,2047,310,820,24310,1,0,2,3\n"
+    );
+}
+
+#[test]
+fn json() {
+    assert_eq!(
+        write(&record(), Format::Json),
+        "{\"time_ms\":61250,\"state\":\"ON\",\"fault\":null,\"pwm\":2048,\
+         \"freq_mhz\":12500,\"oe\":true,\"relay\":false,\"vdda_mv\":3301,\
+         \"temperature\":-4,\"display\":\"ok\",\"ain_pwm\":2047,\"ain_freq\":310,\
+         \"loop_us\":820,\"loop_max_us\":24310,\"under_voltage_faults\":1,\
+         \"over_temperature_faults\":0,\"input_faults\":2,\"dropped\":3}\n"
+    );
+}
+
+#[test]
+fn longest_record_fits_a_line() {
+    let mut record = record();
+    record.time = Instant::from_millis(u32::MAX);
+    record.status = Status::new(State::Error, PWM_MAX, Freq::Periodic(u32::MAX))
+        .with_outputs(false, false)
+        .with_supply(Some(Supply::new(u16::MAX, i16::MIN)))
+        .with_fault(Some(Fault::OverTemperature))
+        .with_display(Health::Missing);
+    record.inputs = [u16::MAX; 2];
+    record.loop_us = u32::MAX;
+    record.loop_max_us = u32::MAX;
+    record.faults = FaultCounts {
+        under_voltage: u16::MAX,
+        over_temperature: u16::MAX,
+        input: u16::MAX,
+    };
+    record.dropped = u32::MAX;
+
+    let mut publisher = Publisher::new(100);
+    publisher.set_format(Some(Format::Csv));
+    let csv = publisher.update(
+        record.time,
+        &record.status,
+        record.inputs,
+        u32::MAX,
+        u32::MAX,
+    );
+    assert_eq!(csv.unwrap().lines().count(), 2);
+
+    let json = write(&record, Format::Json);
+    assert!(json.len() < 400, "{}", json.len());
+}
+
+#[test]
+fn publisher() {
+    let status = Status::new(State::Off, 0, Freq::Continuous);
+    let faulted = status.with_fault(Some(Fault::Input));
+    let at = Instant::from_millis;
+    let mut publisher = Publisher::new(100);
+
+    // Off, but counting
+    assert_eq!(publisher.format(), None);
+    assert!(publisher.update(at(0), &faulted, [0; 2], 10, 0).is_none());
+    assert!(publisher.update(at(10), &faulted, [0; 2], 10, 0).is_none());
+    assert!(publisher.update(at(20), &status, [0; 2], 10, 0).is_none());
+    assert!(publisher.update(at(30), &faulted, [0; 2], 10, 0).is_none());
+    assert_eq!(publisher.faults().input, 2);
+
+    publisher.set_format(Some(Format::Csv));
+    let line = publisher.update(at(40), &status, [0; 2], 30, 0).unwrap();
+    let lines: Vec<&str> = line.lines().collect();
+    assert!(lines[0].starts_with("time_ms,"));
+    assert_eq!(lines[1], "40,OFF,,0,0,0,0,,,ok,0,0,30,30,0,0,2,0");
+
+    // The longest loop in between
+    assert!(publisher.update(at(60), &status, [0; 2], 900, 0).is_none());
+    let line = publisher.update(at(140), &status, [0; 2], 20, 0).unwrap();
+    assert_eq!(line, "140,OFF,,0,0,0,0,,,ok,0,0,20,900,0,0,2,0\n");
+    let line = publisher.update(at(240), &status, [0; 2], 20, 0).unwrap();
+    assert_eq!(line, "240,OFF,,0,0,0,0,,,ok,0,0,20,20,0,0,2,0\n");
+
+    publisher.set_format(Some(Format::Json));
+    publisher.set_interval(1000);
+    let line = publisher.update(at(250), &status, [0; 2], 20, 0).unwrap();
+    assert!(line.starts_with("{\"time_ms\":250,"));
+    assert!(publisher.update(at(1249), &status, [0; 2], 20, 0).is_none());
+    assert!(publisher.update(at(1250), &status, [0; 2], 20, 0).is_some());
+
+    publisher.set_format(None);
+    assert!(publisher.update(at(5000), &status, [0; 2], 20, 0).is_none());
+}